@@ -71,6 +71,53 @@ pub struct ExpandInfo {
     pub algorithm: Algorithm,
     pub children: Vec<u64>,
 }
+// 节点的说明信息，叶节点和非叶节点都可以有
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct NodeMetadata {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub unit: String,
+    // 数值的预期范围，如“60～100”，只用于提示
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub expected_range: String,
+    // 参考文献，每项一条
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+impl NodeMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == NodeMetadata::default()
+    }
+}
+
+// 模型文件的头部信息
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ModelHeader {
+    pub title: String,
+    pub description: String,
+    pub authors: Vec<String>,
+    // 每次保存有修改的模型时自动递增最后一段数字
+    pub version: String,
+    // 创建和修改时间由保存时自动维护，格式为“年-月-日 时:分:秒”
+    pub created: String,
+    pub modified: String,
+    pub changelog: String,
+}
+
+impl ModelHeader {
+    pub fn is_empty(&self) -> bool {
+        *self == ModelHeader::default()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Model {
     pub id: u64,
@@ -78,6 +125,7 @@ pub struct Model {
     pub ref_count: u64,
     pub expand_info: Option<ExpandInfo>,
     pub value: Option<f64>,
+    pub metadata: NodeMetadata,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -108,6 +156,16 @@ pub struct DeleteArgs {
     pub parent: Option<u64>,
 }
 
+// 将节点从oldParent下移动到newParent下，index为None时放在最后
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[allow(non_snake_case)]
+pub struct MoveArgs {
+    pub id: u64,
+    pub oldParent: u64,
+    pub newParent: u64,
+    pub index: Option<u64>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[allow(non_snake_case)]
 pub struct RenameArgs {
@@ -130,6 +188,134 @@ pub struct DeleteResponse {
     pub ids_to_update: Vec<u64>,
 }
 
+// 删除parent下的id及其子树中因此无法到达的节点
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DeleteSubtreeArgs {
+    pub id: u64,
+    pub parent: u64,
+}
+
+// 合并两个节点时子节点的取法
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ChildrenMerge {
+    // 保留的节点的子节点在前，被合并的节点中其余的子节点在后
+    Union,
+    KeepLeft,
+    KeepRight,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum MergeSide {
+    Left,
+    Right,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MergeSettings {
+    pub children: ChildrenMerge,
+    // 两个节点的算法不同时采用哪一个
+    pub algorithm: MergeSide,
+}
+
+// 将right合并到left：保留left的id和名称，所有对right的引用改为引用left，然后删除right
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MergeArgs {
+    pub left: u64,
+    pub right: u64,
+    pub settings: MergeSettings,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MergePreviewArgs {
+    pub left: u64,
+    pub right: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MergePreview {
+    pub left_name: String,
+    pub right_name: String,
+    // 引用right的节点，合并后改为引用left
+    pub rewritten_parents: Vec<String>,
+    // 同时引用两个节点的节点，合并后只保留一个引用
+    pub shared_parents: Vec<String>,
+    pub left_algorithm: Option<Algorithm>,
+    pub right_algorithm: Option<Algorithm>,
+    pub left_children: u64,
+    pub right_children: u64,
+    pub common_children: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum FindMode {
+    // 按原文查找
+    Literal,
+    // 正则表达式，替换内容中可以用$1、${name}引用分组
+    Regex,
+}
+
+// 在所有节点名称中查找find并替换为replace
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FindReplaceSettings {
+    pub find: String,
+    pub replace: String,
+    pub mode: FindMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FindReplaceArgs {
+    pub settings: FindReplaceSettings,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NameChange {
+    pub id: u64,
+    pub old_name: String,
+    pub new_name: String,
+    pub is_leaf: bool,
+}
+
+// 替换后多个节点名称相同：与重命名相同，叶节点合并到同名的节点；
+// error不为空时无法合并，整个替换不能执行
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NameCollision {
+    pub name: String,
+    // 保留的节点，其余节点合并到它
+    pub target: u64,
+    pub merged: Vec<u64>,
+    // 替换前的名称，保留的节点在最前
+    pub old_names: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FindReplacePreview {
+    pub changes: Vec<NameChange>,
+    pub collisions: Vec<NameCollision>,
+}
+
+// 从根节点无法到达的节点
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UnreachableNode {
+    pub id: u64,
+    pub name: String,
+    pub is_leaf: bool,
+    // 仍然引用它的节点，或者撤销历史中最近一次引用它的节点；已被删除的父节点名称后标注“（已删除）”
+    pub former_parents: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RemoveUnreachableArgs {
+    pub ids: Vec<u64>,
+}
+
+// 删除子树或清除不可达节点的结果
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RemoveNodesResponse {
+    pub removed: Vec<u64>,
+    pub ids_to_update: Vec<u64>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[allow(non_snake_case)]
 pub struct UpdateAlgorithmArgs {
@@ -137,6 +323,126 @@ pub struct UpdateAlgorithmArgs {
     pub newAlgorithm: Algorithm,
 }
 
+// 复制子树时新节点名称的生成规则
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum RenameRule {
+    Prefix(String),
+    Suffix(String),
+    Replace { from: String, to: String },
+}
+
+impl RenameRule {
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            RenameRule::Prefix(prefix) => format!("{}{}", prefix, name),
+            RenameRule::Suffix(suffix) => format!("{}{}", name, suffix),
+            RenameRule::Replace { from, .. } if from.is_empty() => name.to_string(),
+            RenameRule::Replace { from, to } => name.replace(from.as_str(), to),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CopySettings {
+    pub rule: RenameRule,
+    // 为true时被多个父节点共享的叶节点也复制一份，否则副本直接引用原叶节点
+    pub duplicate_shared_leaves: bool,
+}
+
+impl Default for CopySettings {
+    fn default() -> Self {
+        CopySettings {
+            rule: RenameRule::Suffix("（副本）".to_string()),
+            duplicate_shared_leaves: false,
+        }
+    }
+}
+
+// 将id的子树复制一份，作为parent的子节点
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CopySubtreeArgs {
+    pub id: u64,
+    pub parent: u64,
+    pub settings: CopySettings,
+}
+
+// 在parent下将共享的节点id换成名为newName的副本，copySettings为None时只复制节点本身（浅复制），
+// 否则按其中的规则复制整个子树（深复制）
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[allow(non_snake_case)]
+pub struct UnshareArgs {
+    pub id: u64,
+    pub parent: u64,
+    pub newName: String,
+    pub copySettings: Option<CopySettings>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UnshareResponse {
+    pub new_id: u64,
+    // 新建的节点数量，包括new_id
+    pub created: u64,
+    pub ids_to_update: Vec<u64>,
+}
+
+// 将已有的节点id加为parent的子节点
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LinkChildArgs {
+    pub id: u64,
+    pub parent: u64,
+}
+
+// 按名称搜索节点，parent不为空时排除不能作为其子节点的节点
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SearchNodesArgs {
+    pub query: String,
+    pub parent: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NodeSearchResult {
+    pub id: u64,
+    pub name: String,
+    pub is_leaf: bool,
+    pub ref_count: u64,
+}
+
+// 将子节点从from位置移到to位置，位置从0开始
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReorderChildArgs {
+    pub id: u64,
+    pub from: u64,
+    pub to: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ChildrenOrder {
+    // 按名称的字符编码
+    Name,
+    // 按名称的拼音，声调不同时按声调排列
+    Pinyin,
+    // 按上次计算的结果，没有结果的排在最后
+    Value,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SortChildrenArgs {
+    pub id: u64,
+    pub order: ChildrenOrder,
+    pub descending: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UpdateHeaderArgs {
+    pub header: ModelHeader,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UpdateMetadataArgs {
+    pub id: u64,
+    pub metadata: NodeMetadata,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[allow(non_snake_case)]
 pub struct QueryValuesArgs {
@@ -147,3 +453,161 @@ pub struct QueryValuesArgs {
 pub struct QueryValuesResponse {
     pub values: HashMap<String, f64>,
 }
+
+// 导出图形时的范围，root为None时导出整个模型，maxDepth为None时不限制深度
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[allow(non_snake_case)]
+pub struct ExportGraphArgs {
+    pub root: Option<u64>,
+    pub maxDepth: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveSettings {
+    // 保存时在模型文件旁保留的轮换备份数量，0表示不备份
+    pub backup_count: u64,
+    // 自动保存的间隔（分钟），0表示不自动保存
+    pub autosave_minutes: u64,
+}
+
+impl Default for SaveSettings {
+    fn default() -> Self {
+        SaveSettings {
+            backup_count: 3,
+            autosave_minutes: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveSettingsArgs {
+    pub settings: SaveSettings,
+}
+
+// 表格数据文件（CSV、Excel）中数据的排列方式
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum DataLayout {
+    // 每行一个指标，由名称列和数值列给出
+    #[default]
+    KeyValue,
+    // 每行一个对象，表头为指标名称
+    Wide,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DataFileSettings {
+    pub layout: DataLayout,
+    // 列可以用表头文字、列字母（如B）或从1开始的列号指定
+    pub name_column: String,
+    pub value_column: String,
+    // 每行一个对象时用于选择对象的列和值，为空时使用第一行数据
+    pub subject_column: String,
+    pub subject: String,
+    // Excel工作表名称，为空时使用第一个工作表
+    pub sheet: String,
+}
+
+impl Default for DataFileSettings {
+    fn default() -> Self {
+        DataFileSettings {
+            layout: DataLayout::KeyValue,
+            name_column: "名称".to_string(),
+            value_column: "值".to_string(),
+            subject_column: String::new(),
+            subject: String::new(),
+            sheet: String::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DataFileSettingsArgs {
+    pub settings: DataFileSettings,
+}
+
+// 数据模板的文件格式，CSV和Excel模板按数据文件设置中的列排列，可以直接用于计算
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum TemplateFormat {
+    #[default]
+    Json,
+    Csv,
+    Xlsx,
+}
+
+// 数据模板中数值的初始内容
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum TemplateFill {
+    #[default]
+    Zero,
+    // 留空，JSON模板中写为0
+    Empty,
+    // 随机数，用于调试
+    Random,
+    // 从已有的数据文件中读取，文件中没有的指标留空
+    Dataset,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct TemplateSettings {
+    pub format: TemplateFormat,
+    // 只包含该节点子树中的叶节点，为None时包含整个模型
+    pub root: Option<u64>,
+    pub fill: TemplateFill,
+    // CSV和Excel模板中附加的说明列
+    pub include_path: bool,
+    pub include_unit: bool,
+    pub include_expected_range: bool,
+    pub include_description: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TemplateSettingsArgs {
+    pub settings: TemplateSettings,
+}
+
+// 数据文件中疑似拼写错误的名称及最接近的叶节点名称
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RenameSuggestion {
+    pub key: String,
+    pub leaf: String,
+    // 规范化后的编辑距离，0表示只有全角半角、大小写或空白的差别
+    pub distance: u64,
+    // 拼音相同，只是用了同音字
+    #[serde(default)]
+    pub homophone: bool,
+}
+
+// 数据文件的检查结果，名称按模型中的顺序排列
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct DataValidationReport {
+    pub file_path: String,
+    pub leaf_count: u64,
+    // 模型中有但数据文件中没有的叶节点
+    pub missing: Vec<String>,
+    // 数据文件中有但模型中没有的名称
+    pub unused: Vec<String>,
+    pub non_finite: Vec<String>,
+    pub suggestions: Vec<RenameSuggestion>,
+}
+
+impl DataValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.non_finite.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[allow(non_snake_case)]
+pub struct ApplyDataRenamesArgs {
+    pub filePath: String,
+    pub renames: Vec<RenameSuggestion>,
+}
+
+// 可以撤销和重做的操作名称，None表示没有
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct HistoryState {
+    pub undo: Option<String>,
+    pub redo: Option<String>,
+}
@@ -0,0 +1,37 @@
+use crate::models::TauriState;
+use crate::saver::save_state;
+use std::sync::RwLock;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+// 后台线程定期检查，未保存的修改超过设定的时间后自动保存到当前文件
+pub fn start_autosave(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(5));
+        let state = app.state::<RwLock<TauriState>>();
+        let mut state = state.write().unwrap();
+        let autosave_minutes = state.save_settings.autosave_minutes;
+        if autosave_minutes == 0 {
+            continue;
+        }
+        let due = state
+            .unsaved_since
+            .is_some_and(|since| since.elapsed() >= Duration::from_secs(autosave_minutes * 60));
+        if !due {
+            continue;
+        }
+        let file_path = match state.curr_file_path.clone() {
+            Some(file_path) => file_path,
+            None => continue,
+        };
+        match save_state(&mut state, &file_path) {
+            Ok(_) => println!("自动保存到{}", file_path),
+            Err(e) => {
+                eprintln!("自动保存失败：{}", e);
+                // 等到下一个间隔再重试
+                state.unsaved_since = Some(Instant::now());
+            }
+        }
+    });
+}
@@ -1,25 +1,45 @@
-use crate::helper::{suggest_new_name_add, suggest_new_name_dupe};
-use crate::loader::{load_data, load_models};
-use crate::models::{self, FileData, FileModel, FileTreeModel, TauriState, TreeModel};
-use crate::saver::save_models;
+use crate::datafile::{is_workbook, TABLE_EXTENSIONS};
+use crate::exchange::graph::{build_graph, GraphFormat, GRAPH_EXTENSIONS};
+use crate::exchange::levels::{parse_levels, serialize_levels, LEVEL_EXTENSIONS};
+use crate::exchange::mindmap::{parse_mind_map, MIND_MAP_EXTENSIONS};
+use crate::exchange::nested;
+use crate::exchange::outline::{OutlineFormat, OUTLINE_EXTENSIONS};
+use crate::exchange::workbook::build_workbook;
+use crate::find_replace::find_replace_preview;
+use crate::formats::MODEL_EXTENSIONS;
+use crate::history::{replay, History};
+use crate::journal::{
+    read_journal, remove_journal, Journal, JournalEntry, JournalIds, JournalState,
+};
+use crate::loader::{build_tree_model, load_data, load_models, read_file_tree_model};
+use crate::models::{self, FileTreeModel, TauriState, TreeModel};
+use crate::operations;
+use crate::orphans::list_unreachable;
+use crate::saver::{save_models, save_state, to_file_tree_model, write_atomically};
+use crate::search::search_nodes;
+use crate::template::{build_template, template_extension, template_rows};
+use crate::validation::{format_report, list_names, rename_data_keys, validate_data};
 use rand::Rng;
 use shared::{
-    Algorithm, DeleteResponse, ExpandInfo, Model, MyResult, QueryValuesResponse, RenameResponse,
+    Algorithm, ChildrenOrder, CopySettings, DataFileSettings, DataValidationReport, DeleteResponse,
+    FindReplacePreview, FindReplaceSettings, HistoryState, MergePreview, MergeSettings, Model,
+    ModelHeader, MyResult, NodeMetadata, NodeSearchResult, QueryValuesResponse,
+    RemoveNodesResponse, RenameResponse, RenameSuggestion, SaveSettings, TemplateFill,
+    TemplateSettings, UnreachableNode, UnshareResponse,
 };
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
-use std::sync::atomic::Ordering;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{atomic::AtomicU64, RwLock};
+use std::time::Instant;
 use tauri::AppHandle;
-use tauri_plugin_dialog::{Dialog, DialogExt, FileDialogBuilder, FilePath, MessageDialogButtons};
+use tauri_plugin_dialog::{
+    Dialog, DialogExt, FileDialogBuilder, FilePath, MessageDialogButtons, MessageDialogKind,
+};
 
 fn select_file_helper(app: AppHandle) -> Result<String, String> {
     println!("select_file called");
     let file_path = app.dialog().file().blocking_pick_file();
     file_path
-        .map(|path| match path {
-            FilePath::Path(pathbuf) => pathbuf.to_string_lossy().to_string(),
-            FilePath::Url(url) => url.to_string(),
-        })
+        .map(file_path_to_string)
         .ok_or("未选择文件".to_string())
 }
 
@@ -31,6 +51,73 @@ pub fn select_file(app: AppHandle) -> MyResult<String, String> {
     }
 }
 
+// 检查上次编辑是否留下了未保存的修改，若用户同意则重放，
+// 返回成功重放的修改、重放时建立的撤销历史和重放之前的id，后者用于新的日志
+fn recover_journal(
+    app: &AppHandle,
+    file_path: &str,
+    tree_model: &mut TreeModel,
+) -> (Vec<JournalEntry>, History, JournalIds) {
+    let mut history = History::default();
+    let (journal_ids, entries) = match read_journal(file_path) {
+        Ok(JournalState::Clean) => return (vec![], history, tree_model.journal_ids()),
+        Ok(JournalState::Unsaved(journal_ids, entries)) => (journal_ids, entries),
+        Ok(JournalState::Stale) => {
+            app.dialog()
+                .message("模型文件在上次编辑后被修改过，无法恢复上次未保存的修改")
+                .title("恢复未保存的修改")
+                .kind(MessageDialogKind::Warning)
+                .blocking_show();
+            return (vec![], history, tree_model.journal_ids());
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return (vec![], history, tree_model.journal_ids());
+        }
+    };
+    let answer = app
+        .dialog()
+        .message(format!(
+            "上次编辑后有{}项修改未保存（程序可能异常退出），是否恢复这些修改？",
+            entries.len()
+        ))
+        .title("恢复未保存的修改")
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "恢复".to_string(),
+            "丢弃".to_string(),
+        ))
+        .blocking_show();
+    if !answer {
+        return (vec![], history, tree_model.journal_ids());
+    }
+    if let Some(journal_ids) = journal_ids.as_ref() {
+        tree_model.renumber(journal_ids);
+    }
+    let start_ids = tree_model.journal_ids();
+    let total = entries.len();
+    let mut recovered = Vec::new();
+    for entry in entries {
+        if let Err(e) = replay(tree_model, &mut history, &entry) {
+            eprintln!("重放修改{:?}失败，停止恢复：{}", entry, e);
+            app.dialog()
+                .message(format!(
+                    "只恢复了{}项修改，第{}项（{}）无法恢复：{}\n其后的{}项修改已被丢弃",
+                    recovered.len(),
+                    recovered.len() + 1,
+                    entry.label(),
+                    e,
+                    total - recovered.len() - 1
+                ))
+                .title("恢复未保存的修改")
+                .kind(MessageDialogKind::Warning)
+                .blocking_show();
+            break;
+        }
+        recovered.push(entry);
+    }
+    (recovered, history, start_ids)
+}
+
 fn prepare_models_helper(
     app: AppHandle,
     file_path: &str,
@@ -48,9 +135,45 @@ fn prepare_models_helper(
             "不随机化".to_string(),
         ))
         .blocking_show();
-    let tree_model = load_models(file_path, randomize_algorithm)?;
+    let mut tree_model = load_models(file_path, randomize_algorithm)?;
+    let (recovered, history, start_ids) = recover_journal(&app, file_path, &mut tree_model);
+    open_model(
+        &mut state, file_path, tree_model, recovered, history, start_ids,
+    )?;
+    Ok(())
+}
+
+// 将加载好的模型设为当前模型，并为其开始新的恢复日志
+fn open_model(
+    state: &mut TauriState,
+    file_path: &str,
+    tree_model: TreeModel,
+    recovered: Vec<JournalEntry>,
+    history: History,
+    start_ids: JournalIds,
+) -> Result<(), String> {
+    let mut journal = match Journal::create(file_path, start_ids) {
+        Ok(journal) => Some(journal),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    };
+    // 恢复的修改仍未保存，重新写入新的日志
+    if let Some(journal) = journal.as_mut() {
+        for entry in recovered.iter() {
+            journal.append(entry)?;
+        }
+    }
     state.curr_file_path = Some(file_path.to_string());
     state.curr_tree_model = Some(tree_model);
+    state.set_journal(journal, &history);
+    state.history = history;
+    state.unsaved_since = if recovered.is_empty() {
+        None
+    } else {
+        Some(Instant::now())
+    };
     Ok(())
 }
 
@@ -86,6 +209,60 @@ pub fn query_file_path(state: tauri::State<RwLock<TauriState>>) -> MyResult<Stri
     }
 }
 
+fn query_model_header_helper(
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<ModelHeader, String> {
+    println!("Rust: query_model_header called");
+    let state = state.read().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_ref()
+        .ok_or("模型未加载".to_string())?;
+    Ok(tree_model.header.clone())
+}
+
+#[tauri::command]
+pub fn query_model_header(
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<ModelHeader, String> {
+    let result = query_model_header_helper(state);
+    match result {
+        Ok(header) => MyResult::Ok(header),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_update_header_helper(
+    header: ModelHeader,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<(), String> {
+    println!(
+        "Rust: request_update_header called with header: {:?}",
+        header
+    );
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    operations::update_header(tree_model, header.clone());
+    state.record(JournalEntry::UpdateHeader { header }, before);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn request_update_header(
+    header: ModelHeader,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<(), String> {
+    let result = request_update_header_helper(header, state);
+    match result {
+        Ok(_) => MyResult::Ok(()),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
 fn query_node_helper(id: u64, state: tauri::State<RwLock<TauriState>>) -> Result<Model, String> {
     println!("Rust: query_node called with id: {}", id);
     let state = state.read().unwrap();
@@ -109,60 +286,30 @@ pub fn query_node(id: u64, state: tauri::State<RwLock<TauriState>>) -> MyResult<
     }
 }
 
-fn update_reference_count(models: &mut BTreeMap<u64, Model>) {
-    let mut reference_counts = models
-        .iter()
-        .map(|(id, _)| (*id, 0))
-        .collect::<HashMap<u64, u64>>();
-    println!("更新reference count");
-    for (_, model) in models.iter() {
-        if let Some(expand_info) = &model.expand_info {
-            for child_id in expand_info.children.iter() {
-                if let Some(reference_count) = reference_counts.get_mut(child_id) {
-                    *reference_count += 1;
-                } else {
-                    eprintln!(
-                        "错误：在更新reference count时在reference_counts中未找到子节点{}",
-                        child_id
-                    );
-                }
-            }
-        }
-    }
-    for (id, reference_count) in reference_counts.iter() {
-        println!("更新节点{}的reference count为{}", id, reference_count);
-        models.get_mut(id).unwrap().ref_count = *reference_count;
-    }
+fn query_search_nodes_helper(
+    query: String,
+    parent: Option<u64>,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<Vec<NodeSearchResult>, String> {
+    let state = state.read().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_ref()
+        .ok_or("模型未加载".to_string())?;
+    Ok(search_nodes(tree_model, &query, parent))
 }
 
-fn replace_node_and_update_children(
-    id: u64,
-    new_id: Option<u64>,
-    models: &mut BTreeMap<u64, Model>,
-) -> Result<HashSet<u64>, String> {
-    println!("删除节点：{}", id);
-    models
-        .remove(&id)
-        .ok_or(format!("未找到要删除的模型：{}", id))?;
-    let mut ids_to_update = HashSet::new();
-    for (_, model) in models.iter_mut() {
-        if let Some(expand_info) = model.expand_info.as_mut() {
-            if expand_info.children.contains(&id) {
-                if let Some(new_id) = new_id {
-                    expand_info.children.iter_mut().for_each(|child_id| {
-                        if *child_id == id {
-                            *child_id = new_id;
-                        }
-                    });
-                } else {
-                    expand_info.children.retain(|child_id| *child_id != id);
-                }
-                ids_to_update.insert(model.id);
-            }
-        }
+#[tauri::command]
+pub fn query_search_nodes(
+    query: String,
+    parent: Option<u64>,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<Vec<NodeSearchResult>, String> {
+    let result = query_search_nodes_helper(query, parent, state);
+    match result {
+        Ok(results) => MyResult::Ok(results),
+        Err(e) => MyResult::Err(e),
     }
-    update_reference_count(models);
-    Ok(ids_to_update)
 }
 
 fn request_rename_helper(
@@ -170,43 +317,25 @@ fn request_rename_helper(
     new_name: &str,
     state: tauri::State<RwLock<TauriState>>,
 ) -> Result<RenameResponse, String> {
-    // 1. If has children, and name is duplicated, return error
-    // 2. If has no children, and name is duplicated, delete current model and replace other models' children -> (delete message, parent update messages)
-    // 3. If name is not duplicated, rename -> (rename message)
     let mut state = state.write().unwrap();
     println!(
         "Rust: request_rename called with id: {}, new_name: {}",
         id, new_name
     );
-    let models = &mut state
+    let tree_model = state
         .curr_tree_model
         .as_mut()
-        .ok_or("模型未加载".to_string())?
-        .models;
-    let new_name_owner = models.iter().find(|(_, model)| model.name == new_name);
-    let new_name_owner_id = new_name_owner.map(|(id, _)| *id);
-    let model = models.get_mut(&id).ok_or(format!("未找到模型{}", id))?;
-    if new_name == "" {
-        Err("重命名失败：新名称为空".to_string())?;
-    }
-    if model.name == new_name {
-        Err("重命名失败：新名称与旧名称相同".to_string())?;
-    }
-    if let Some(new_name_owner_id) = new_name_owner_id {
-        if model.expand_info.is_some() {
-            Err("重命名失败：新名称已存在".to_string())?;
-        }
-        let mut ids_to_update =
-            replace_node_and_update_children(id, Some(new_name_owner_id), models)?;
-        ids_to_update.insert(new_name_owner_id);
-        Ok(RenameResponse::RemoveSelfUpdateRelated {
-            id_to_remove: id,
-            ids_to_update: ids_to_update.into_iter().collect(),
-        })
-    } else {
-        model.name = new_name.to_string();
-        Ok(RenameResponse::RenameSelf(new_name.to_string()))
-    }
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    let response = operations::rename(tree_model, id, new_name)?;
+    state.record(
+        JournalEntry::Rename {
+            id,
+            new_name: new_name.to_string(),
+        },
+        before,
+    );
+    Ok(response)
 }
 
 #[tauri::command]
@@ -231,46 +360,15 @@ fn request_delete_helper(
         "Rust: request_delete called with id: {}, parent: {:?}",
         id, parent
     );
-    if parent.is_none() {
-        assert_eq!(id, 0);
-        Err("根节点不可删除".to_string())?;
-    }
     let mut state = state.write().unwrap();
-    let models = &mut state
+    let tree_model = state
         .curr_tree_model
         .as_mut()
-        .ok_or("模型未加载".to_string())?
-        .models;
-
-    // if the reference count is 1, we can delete the model, unless it is root
-    let model = models.get_mut(&id).ok_or(format!("未找到模型{}", id))?;
-    if model.ref_count == 1 && id != 0 {
-        let ids_to_update = replace_node_and_update_children(id, None, models)?;
-        Ok(DeleteResponse {
-            id_to_remove: Some(id),
-            ids_to_update: ids_to_update.into_iter().collect(),
-        })
-    } else {
-        let parent_model = models
-            .get_mut(&parent.unwrap())
-            .ok_or(format!("未找到模型{}", parent.unwrap()))?;
-        let parent_children = &mut parent_model
-            .expand_info
-            .as_mut()
-            .ok_or("父节点无子节点".to_string())?
-            .children;
-        parent_children.remove(
-            parent_children
-                .iter()
-                .position(|x| *x == id)
-                .ok_or("未找到要删除的模型")?,
-        );
-        update_reference_count(models);
-        Ok(DeleteResponse {
-            id_to_remove: None,
-            ids_to_update: vec![parent.unwrap(), id],
-        })
-    }
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    let response = operations::delete(tree_model, id, parent)?;
+    state.record(JournalEntry::Delete { id, parent }, before);
+    Ok(response)
 }
 
 #[tauri::command]
@@ -286,260 +384,1396 @@ pub fn request_delete(
     }
 }
 
-fn request_add_helper(id: u64, state: tauri::State<RwLock<TauriState>>) -> Result<u64, String> {
-    let mut state = state.write().unwrap();
-    let tree_model = state
-        .curr_tree_model
-        .as_mut()
-        .ok_or("添加错误：模型未加载".to_string())?;
-    let model = tree_model
-        .models
-        .get_mut(&id)
-        .ok_or(format!("添加错误：未找到模型{}", id))?;
-    let expand_info = model
-        .expand_info
-        .as_mut()
-        .ok_or("添加失败：模型无子节点".to_string())?;
-    let new_id = tree_model.counter.fetch_add(1, Ordering::Relaxed);
-    expand_info.children.push(new_id);
-    let new_name = suggest_new_name_add(&tree_model.models);
-    let new_model = Model {
-        id: new_id,
-        name: new_name,
-        ref_count: 1, // the only parent is the current model
-        expand_info: None,
-        value: None,
-    };
-    tree_model.models.insert(new_id, new_model);
-    Ok(id)
-}
-
-#[tauri::command]
-pub fn request_add(id: u64, state: tauri::State<RwLock<TauriState>>) -> MyResult<u64, String> {
-    let result = request_add_helper(id, state);
-    match result {
-        Ok(new_id) => MyResult::Ok(new_id),
-        Err(e) => MyResult::Err(e),
+// 删除前列出将被删除的节点并请用户确认
+fn confirm_removal(
+    app: &AppHandle,
+    tree_model: &TreeModel,
+    removed: &[u64],
+    title: &str,
+) -> Result<(), String> {
+    let names = removed
+        .iter()
+        .filter_map(|id| tree_model.models.get(id))
+        .map(|model| model.name.clone())
+        .collect::<Vec<String>>();
+    let answer = app
+        .dialog()
+        .message(format!(
+            "将删除以下{}个节点，是否继续？\n{}",
+            names.len(),
+            list_names(&names)
+        ))
+        .title(title)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "确认".to_string(),
+            "取消".to_string(),
+        ))
+        .blocking_show();
+    if !answer {
+        Err("已取消删除".to_string())?;
     }
+    Ok(())
 }
 
-fn request_update_algorithm_helper(
+fn request_delete_subtree_helper(
     id: u64,
-    new_algorithm: Algorithm,
+    parent: u64,
     state: tauri::State<RwLock<TauriState>>,
-) -> Result<u64, String> {
+    app: AppHandle,
+) -> Result<RemoveNodesResponse, String> {
     println!(
-        "Rust: request_update_algorithm called with id: {}, algorithm: {:?}",
-        id, new_algorithm
+        "Rust: request_delete_subtree called with id: {}, parent: {}",
+        id, parent
     );
     let mut state = state.write().unwrap();
-    let models = state
+    let tree_model = state
         .curr_tree_model
         .as_mut()
         .ok_or("模型未加载".to_string())?;
-    let model = models
-        .models
-        .get_mut(&id)
-        .ok_or(format!("未找到模型{}", id))?;
-    if let Some(expand_info) = model.expand_info.as_mut() {
-        expand_info.algorithm = new_algorithm;
-    } else {
-        Err("更新算法失败：模型无子节点".to_string())?;
+    let removed = operations::subtree_deletion(&tree_model.models, id, parent)?;
+    if !removed.is_empty() {
+        confirm_removal(&app, tree_model, &removed, "删除子树确认")?;
     }
-    Ok(id)
+    let before = tree_model.snapshot();
+    let response = operations::delete_subtree(tree_model, id, parent)?;
+    state.record(JournalEntry::DeleteSubtree { id, parent }, before);
+    Ok(response)
 }
 
 #[tauri::command]
-pub fn request_update_algorithm(
+pub fn request_delete_subtree(
     id: u64,
-    new_algorithm: Algorithm,
+    parent: u64,
     state: tauri::State<RwLock<TauriState>>,
-) -> MyResult<u64, String> {
-    let result = request_update_algorithm_helper(id, new_algorithm, state);
+    app: AppHandle,
+) -> MyResult<RemoveNodesResponse, String> {
+    let result = request_delete_subtree_helper(id, parent, state, app);
     match result {
-        Ok(id) => MyResult::Ok(id),
+        Ok(response) => MyResult::Ok(response),
         Err(e) => MyResult::Err(e),
     }
 }
 
-fn request_can_expand_toggling_helper(
-    id: u64,
+fn request_purge_unreachable_helper(
     state: tauri::State<RwLock<TauriState>>,
     app: AppHandle,
-) -> Result<u64, String> {
-    println!("Rust: request_can_expand_toggling called with id: {}", id);
+) -> Result<RemoveNodesResponse, String> {
+    println!("Rust: request_purge_unreachable called");
     let mut state = state.write().unwrap();
     let tree_model = state
         .curr_tree_model
         .as_mut()
         .ok_or("模型未加载".to_string())?;
-    let model = tree_model
-        .models
-        .get_mut(&id)
-        .ok_or(format!("未找到模型{}", id))?;
-    if let Some(expand_info) = &model.expand_info {
-        // ask user if confirm the operation
-        if !expand_info.children.is_empty() {
-            let answer = app
-                .dialog()
-                .message("当前节点下仍有子节点，是否清空所有子节点？")
-                .title("清空子节点确认")
-                .buttons(MessageDialogButtons::OkCancelCustom(
-                    "确认".to_string(),
-                    "取消".to_string(),
-                ))
-                .blocking_show();
-            if answer {
-                model.expand_info = None;
-            } else {
-                Err("已取消清空子节点".to_string())?;
-            }
-        } else {
-            model.expand_info = None;
-        }
-    } else {
-        model.expand_info = Some(ExpandInfo {
-            algorithm: Algorithm::None,
-            children: vec![],
-        });
+    let removed = operations::unreachable_nodes(&tree_model.models);
+    if removed.is_empty() {
+        Err("没有不可达的节点".to_string())?;
     }
-    Ok(id)
+    confirm_removal(&app, tree_model, &removed, "清除不可达节点确认")?;
+    let before = tree_model.snapshot();
+    let response = operations::purge_unreachable(tree_model)?;
+    state.record(JournalEntry::PurgeUnreachable, before);
+    Ok(response)
 }
 
 #[tauri::command]
-pub fn request_can_expand_toggling(
-    id: u64,
+pub fn request_purge_unreachable(
     state: tauri::State<RwLock<TauriState>>,
     app: AppHandle,
-) -> MyResult<u64, String> {
-    let result = request_can_expand_toggling_helper(id, state, app);
+) -> MyResult<RemoveNodesResponse, String> {
+    let result = request_purge_unreachable_helper(state, app);
     match result {
-        Ok(id) => MyResult::Ok(id),
+        Ok(response) => MyResult::Ok(response),
         Err(e) => MyResult::Err(e),
     }
 }
 
-fn request_save_helper(state: tauri::State<RwLock<TauriState>>) -> Result<(), String> {
-    println!("Rust: request_save called");
+fn query_unreachable_nodes_helper(
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<Vec<UnreachableNode>, String> {
     let state = state.read().unwrap();
     let tree_model = state
         .curr_tree_model
         .as_ref()
-        .ok_or("保存错误：模型未加载".to_string())?;
-    let file_path = state
-        .curr_file_path
-        .as_ref()
-        .ok_or("保存错误：文件路径未加载".to_string())?;
-    // let result = crate::saver::save_models(file_path, tree_model)?;
-    let root_name = tree_model.models.get(&0).unwrap().name.clone();
-    let mut file_models = HashMap::<u64, FileModel>::new();
-    let mut met = HashSet::<u64>::new();
-    let mut queue = VecDeque::<u64>::new();
-    queue.push_back(0);
-    let mut counter = 0;
-    while let Some(id) = queue.pop_front() {
-        counter += 1;
-        if counter > 10000 {
-            Err("在保存时遇到错误：循环次数过多".to_string())?;
-        }
-        if met.contains(&id) {
-            continue;
-        }
-        met.insert(id);
-        if file_models.get(&id).is_some() {
-            Err(format!("在保存时遇到错误：重复的模型{}", id))?;
-        }
-        let model = tree_model
-            .models
-            .get(&id)
-            .ok_or(format!("在保存时遇到错误：未找到模型{}", id))?;
-        if model.expand_info.is_none() {
-            // 不保存没有子节点的节点
-            continue;
-        }
-        let ExpandInfo {
-            children,
-            algorithm,
-        } = model.expand_info.clone().unwrap();
-        for child in children.iter() {
-            queue.push_back(*child);
-        }
-        let children_names = children
-            .iter()
-            .map(|child_id| {
-                let model = tree_model.models.get(&child_id).ok_or(format!(
-                    "保存时遇到错误：取children_names时遇到未知模型：{}",
-                    child_id
-                ))?;
-                Ok(model.name.clone())
-            })
-            .collect::<Result<Vec<String>, String>>()?;
-        let file_model = FileModel {
-            name: model.name.clone(),
-            algorithm: algorithm.to_string(),
-            children: children_names,
-        };
-        file_models.insert(id, file_model);
-        queue.extend(children);
-    }
-    let file_models = file_models
-        .into_iter()
-        .map(|(_id, file_model)| file_model)
-        .collect::<Vec<_>>();
-    let file_tree_model = FileTreeModel {
-        root_name,
-        data: file_models,
-    };
-    save_models(file_path, file_tree_model)?;
-    Ok(())
+        .ok_or("模型未加载".to_string())?;
+    Ok(list_unreachable(tree_model, &state.history))
 }
 
 #[tauri::command]
-pub fn request_save(state: tauri::State<RwLock<TauriState>>) -> MyResult<(), String> {
-    let result = request_save_helper(state);
+pub fn query_unreachable_nodes(
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<Vec<UnreachableNode>, String> {
+    let result = query_unreachable_nodes_helper(state);
     match result {
-        Ok(_) => MyResult::Ok(()),
+        Ok(nodes) => MyResult::Ok(nodes),
         Err(e) => MyResult::Err(e),
     }
 }
 
-fn request_calculate_helper(
-    app: AppHandle,
+fn request_remove_unreachable_helper(
+    ids: Vec<u64>,
     state: tauri::State<RwLock<TauriState>>,
-) -> Result<(), String> {
-    println!("Rust: request_calculate called");
-    let file_path = app.dialog().file().blocking_pick_file();
-    let file_path = file_path
-        .map(|path| match path {
-            FilePath::Path(pathbuf) => pathbuf.to_string_lossy().to_string(),
-            FilePath::Url(url) => url.to_string(),
-        })
-        .ok_or("未选择文件".to_string())?;
-    let file_data = load_data(&file_path)?;
+) -> Result<RemoveNodesResponse, String> {
+    println!(
+        "Rust: request_remove_unreachable called with ids: {:?}",
+        ids
+    );
+    if ids.is_empty() {
+        Err("没有选中的节点".to_string())?;
+    }
     let mut state = state.write().unwrap();
     let tree_model = state
         .curr_tree_model
-        .as_ref()
+        .as_mut()
         .ok_or("模型未加载".to_string())?;
-    let models = &tree_model.models;
-    let name_to_id = models
-        .iter()
-        .filter(|(_name, model)| model.expand_info.is_none())
-        .map(|(id, model)| (model.name.clone(), *id))
-        .collect::<HashMap<String, u64>>();
-    let required_names = name_to_id
-        .iter()
-        .map(|(name, _id)| name.clone())
-        .collect::<HashSet<String>>();
-    for required_name in required_names.iter() {
-        if !file_data.contains_key(required_name) {
-            Err(format!("计算失败：文件中缺少数据{}", required_name))?;
-        }
+    let before = tree_model.snapshot();
+    let response = operations::remove_unreachable(tree_model, &ids)?;
+    state.record(JournalEntry::RemoveUnreachable { ids }, before);
+    Ok(response)
+}
+
+#[tauri::command]
+pub fn request_remove_unreachable(
+    ids: Vec<u64>,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<RemoveNodesResponse, String> {
+    let result = request_remove_unreachable_helper(ids, state);
+    match result {
+        Ok(response) => MyResult::Ok(response),
+        Err(e) => MyResult::Err(e),
     }
-    // create a dynamic programming mem for calculation
-    let mut mem = HashMap::<u64, f64>::new();
-    for (name, id) in name_to_id.iter() {
-        mem.insert(*id, file_data.get(name).unwrap().clone());
+}
+
+fn request_move_helper(
+    id: u64,
+    old_parent: u64,
+    new_parent: u64,
+    index: Option<u64>,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<Vec<u64>, String> {
+    println!(
+        "Rust: request_move called with id: {}, old_parent: {}, new_parent: {}, index: {:?}",
+        id, old_parent, new_parent, index
+    );
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    let ids_to_update = operations::move_node(tree_model, id, old_parent, new_parent, index)?;
+    state.record(
+        JournalEntry::Move {
+            id,
+            old_parent,
+            new_parent,
+            index,
+        },
+        before,
+    );
+    Ok(ids_to_update)
+}
+
+#[tauri::command]
+pub fn request_move(
+    id: u64,
+    old_parent: u64,
+    new_parent: u64,
+    index: Option<u64>,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<Vec<u64>, String> {
+    let result = request_move_helper(id, old_parent, new_parent, index, state);
+    match result {
+        Ok(ids_to_update) => MyResult::Ok(ids_to_update),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_add_helper(id: u64, state: tauri::State<RwLock<TauriState>>) -> Result<u64, String> {
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("添加错误：模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    let id = operations::add(tree_model, id)?;
+    state.record(JournalEntry::Add { id }, before);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn request_add(id: u64, state: tauri::State<RwLock<TauriState>>) -> MyResult<u64, String> {
+    let result = request_add_helper(id, state);
+    match result {
+        Ok(new_id) => MyResult::Ok(new_id),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_unshare_helper(
+    id: u64,
+    parent: u64,
+    new_name: String,
+    copy_settings: Option<CopySettings>,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<UnshareResponse, String> {
+    println!(
+        "Rust: request_unshare called with id: {}, parent: {}, new_name: {}, copy_settings: {:?}",
+        id, parent, new_name, copy_settings
+    );
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    let response = operations::unshare(tree_model, id, parent, &new_name, copy_settings.as_ref())?;
+    state.record(
+        JournalEntry::Unshare {
+            id,
+            parent,
+            new_name,
+            copy_settings,
+        },
+        before,
+    );
+    Ok(response)
+}
+
+#[tauri::command]
+pub fn request_unshare(
+    id: u64,
+    parent: u64,
+    new_name: String,
+    copy_settings: Option<CopySettings>,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<UnshareResponse, String> {
+    let result = request_unshare_helper(id, parent, new_name, copy_settings, state);
+    match result {
+        Ok(response) => MyResult::Ok(response),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn query_merge_preview_helper(
+    left: u64,
+    right: u64,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<MergePreview, String> {
+    let state = state.read().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_ref()
+        .ok_or("模型未加载".to_string())?;
+    operations::merge_preview(&tree_model.models, left, right)
+}
+
+#[tauri::command]
+pub fn query_merge_preview(
+    left: u64,
+    right: u64,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<MergePreview, String> {
+    let result = query_merge_preview_helper(left, right, state);
+    match result {
+        Ok(preview) => MyResult::Ok(preview),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_merge_helper(
+    left: u64,
+    right: u64,
+    settings: MergeSettings,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<RemoveNodesResponse, String> {
+    println!(
+        "Rust: request_merge called with left: {}, right: {}, settings: {:?}",
+        left, right, settings
+    );
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    let response = operations::merge_nodes(tree_model, left, right, &settings)?;
+    state.record(
+        JournalEntry::Merge {
+            left,
+            right,
+            settings,
+        },
+        before,
+    );
+    Ok(response)
+}
+
+#[tauri::command]
+pub fn request_merge(
+    left: u64,
+    right: u64,
+    settings: MergeSettings,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<RemoveNodesResponse, String> {
+    let result = request_merge_helper(left, right, settings, state);
+    match result {
+        Ok(response) => MyResult::Ok(response),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn query_find_replace_preview_helper(
+    settings: FindReplaceSettings,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<FindReplacePreview, String> {
+    let state = state.read().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_ref()
+        .ok_or("模型未加载".to_string())?;
+    find_replace_preview(&tree_model.models, &settings)
+}
+
+#[tauri::command]
+pub fn query_find_replace_preview(
+    settings: FindReplaceSettings,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<FindReplacePreview, String> {
+    let result = query_find_replace_preview_helper(settings, state);
+    match result {
+        Ok(preview) => MyResult::Ok(preview),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_find_replace_helper(
+    settings: FindReplaceSettings,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<RemoveNodesResponse, String> {
+    println!(
+        "Rust: request_find_replace called with settings: {:?}",
+        settings
+    );
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    let response = operations::find_replace(tree_model, &settings)?;
+    state.record(JournalEntry::FindReplace { settings }, before);
+    Ok(response)
+}
+
+#[tauri::command]
+pub fn request_find_replace(
+    settings: FindReplaceSettings,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<RemoveNodesResponse, String> {
+    let result = request_find_replace_helper(settings, state);
+    match result {
+        Ok(response) => MyResult::Ok(response),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_link_child_helper(
+    id: u64,
+    parent: u64,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<u64, String> {
+    println!(
+        "Rust: request_link_child called with id: {}, parent: {}",
+        id, parent
+    );
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    let parent = operations::link_child(tree_model, id, parent)?;
+    state.record(JournalEntry::LinkChild { id, parent }, before);
+    Ok(parent)
+}
+
+#[tauri::command]
+pub fn request_link_child(
+    id: u64,
+    parent: u64,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<u64, String> {
+    let result = request_link_child_helper(id, parent, state);
+    match result {
+        Ok(parent) => MyResult::Ok(parent),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_copy_subtree_helper(
+    id: u64,
+    parent: u64,
+    settings: CopySettings,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<u64, String> {
+    println!(
+        "Rust: request_copy_subtree called with id: {}, parent: {}, settings: {:?}",
+        id, parent, settings
+    );
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    operations::copy_subtree(tree_model, id, parent, &settings)?;
+    state.record(
+        JournalEntry::CopySubtree {
+            id,
+            parent,
+            settings,
+        },
+        before,
+    );
+    Ok(parent)
+}
+
+#[tauri::command]
+pub fn request_copy_subtree(
+    id: u64,
+    parent: u64,
+    settings: CopySettings,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<u64, String> {
+    let result = request_copy_subtree_helper(id, parent, settings, state);
+    match result {
+        Ok(id) => MyResult::Ok(id),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_reorder_child_helper(
+    id: u64,
+    from: u64,
+    to: u64,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<u64, String> {
+    println!(
+        "Rust: request_reorder_child called with id: {}, from: {}, to: {}",
+        id, from, to
+    );
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    let children = operations::reorder_child(tree_model, id, from as usize, to as usize)?;
+    state.record(JournalEntry::ReorderChildren { id, children }, before);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn request_reorder_child(
+    id: u64,
+    from: u64,
+    to: u64,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<u64, String> {
+    let result = request_reorder_child_helper(id, from, to, state);
+    match result {
+        Ok(id) => MyResult::Ok(id),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_sort_children_helper(
+    id: u64,
+    order: ChildrenOrder,
+    descending: bool,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<u64, String> {
+    println!(
+        "Rust: request_sort_children called with id: {}, order: {:?}, descending: {}",
+        id, order, descending
+    );
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    let children = operations::sort_children(tree_model, id, order, descending)?;
+    state.record(JournalEntry::ReorderChildren { id, children }, before);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn request_sort_children(
+    id: u64,
+    order: ChildrenOrder,
+    descending: bool,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<u64, String> {
+    let result = request_sort_children_helper(id, order, descending, state);
+    match result {
+        Ok(id) => MyResult::Ok(id),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_update_algorithm_helper(
+    id: u64,
+    new_algorithm: Algorithm,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<u64, String> {
+    println!(
+        "Rust: request_update_algorithm called with id: {}, algorithm: {:?}",
+        id, new_algorithm
+    );
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    let id = operations::update_algorithm(tree_model, id, new_algorithm.clone())?;
+    state.record(JournalEntry::UpdateAlgorithm { id, new_algorithm }, before);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn request_update_algorithm(
+    id: u64,
+    new_algorithm: Algorithm,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<u64, String> {
+    let result = request_update_algorithm_helper(id, new_algorithm, state);
+    match result {
+        Ok(id) => MyResult::Ok(id),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_can_expand_toggling_helper(
+    id: u64,
+    state: tauri::State<RwLock<TauriState>>,
+    app: AppHandle,
+) -> Result<u64, String> {
+    println!("Rust: request_can_expand_toggling called with id: {}", id);
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("模型未加载".to_string())?;
+    let model = tree_model
+        .models
+        .get(&id)
+        .ok_or(format!("未找到模型{}", id))?;
+    if let Some(expand_info) = &model.expand_info {
+        // ask user if confirm the operation
+        if !expand_info.children.is_empty() {
+            let answer = app
+                .dialog()
+                .message("当前节点下仍有子节点，是否清空所有子节点？")
+                .title("清空子节点确认")
+                .buttons(MessageDialogButtons::OkCancelCustom(
+                    "确认".to_string(),
+                    "取消".to_string(),
+                ))
+                .blocking_show();
+            if !answer {
+                Err("已取消清空子节点".to_string())?;
+            }
+        }
+    }
+    let before = tree_model.snapshot();
+    let id = operations::can_expand_toggling(tree_model, id)?;
+    state.record(JournalEntry::CanExpandToggling { id }, before);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn request_can_expand_toggling(
+    id: u64,
+    state: tauri::State<RwLock<TauriState>>,
+    app: AppHandle,
+) -> MyResult<u64, String> {
+    let result = request_can_expand_toggling_helper(id, state, app);
+    match result {
+        Ok(id) => MyResult::Ok(id),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn query_history_helper(state: tauri::State<RwLock<TauriState>>) -> Result<HistoryState, String> {
+    let state = state.read().unwrap();
+    Ok(HistoryState {
+        undo: state.history.undo_label(),
+        redo: state.history.redo_label(),
+    })
+}
+
+#[tauri::command]
+pub fn query_history(state: tauri::State<RwLock<TauriState>>) -> MyResult<HistoryState, String> {
+    let result = query_history_helper(state);
+    match result {
+        Ok(history) => MyResult::Ok(history),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_undo_helper(state: tauri::State<RwLock<TauriState>>) -> Result<String, String> {
+    println!("Rust: request_undo called");
+    let mut state = state.write().unwrap();
+    state.undo()
+}
+
+#[tauri::command]
+pub fn request_undo(state: tauri::State<RwLock<TauriState>>) -> MyResult<String, String> {
+    let result = request_undo_helper(state);
+    match result {
+        Ok(label) => MyResult::Ok(label),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_redo_helper(state: tauri::State<RwLock<TauriState>>) -> Result<String, String> {
+    println!("Rust: request_redo called");
+    let mut state = state.write().unwrap();
+    state.redo()
+}
+
+#[tauri::command]
+pub fn request_redo(state: tauri::State<RwLock<TauriState>>) -> MyResult<String, String> {
+    let result = request_redo_helper(state);
+    match result {
+        Ok(label) => MyResult::Ok(label),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_update_metadata_helper(
+    id: u64,
+    metadata: NodeMetadata,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<u64, String> {
+    println!(
+        "Rust: request_update_metadata called with id: {}, metadata: {:?}",
+        id, metadata
+    );
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("模型未加载".to_string())?;
+    let before = tree_model.snapshot();
+    let id = operations::update_metadata(tree_model, id, metadata.clone())?;
+    state.record(JournalEntry::UpdateMetadata { id, metadata }, before);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn request_update_metadata(
+    id: u64,
+    metadata: NodeMetadata,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<u64, String> {
+    let result = request_update_metadata_helper(id, metadata, state);
+    match result {
+        Ok(id) => MyResult::Ok(id),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_save_helper(state: tauri::State<RwLock<TauriState>>) -> Result<(), String> {
+    println!("Rust: request_save called");
+    let mut state = state.write().unwrap();
+    let file_path = state
+        .curr_file_path
+        .clone()
+        .ok_or("保存错误：文件路径未加载".to_string())?;
+    save_state(&mut state, &file_path)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn request_save(state: tauri::State<RwLock<TauriState>>) -> MyResult<(), String> {
+    let result = request_save_helper(state);
+    match result {
+        Ok(_) => MyResult::Ok(()),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_save_as_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<String, String> {
+    println!("Rust: request_save_as called");
+    let curr_file_path = state.read().unwrap().curr_file_path.clone();
+    let mut dialog = app
+        .dialog()
+        .file()
+        .add_filter("模型文件", &MODEL_EXTENSIONS);
+    if let Some(curr_file_path) = curr_file_path.as_ref() {
+        let curr_file_path = std::path::Path::new(curr_file_path);
+        if let Some(file_name) = curr_file_path.file_name() {
+            dialog = dialog.set_file_name(file_name.to_string_lossy());
+        }
+        if let Some(directory) = curr_file_path.parent() {
+            dialog = dialog.set_directory(directory);
+        }
+    }
+    let file_path = dialog
+        .blocking_save_file()
+        .map(file_path_to_string)
+        .ok_or("未选择文件".to_string())?;
+    let mut state = state.write().unwrap();
+    save_state(&mut state, &file_path)?;
+    // 修改已经保存到新文件，原文件的恢复日志不再需要
+    if let Some(curr_file_path) = curr_file_path.as_ref() {
+        if *curr_file_path != file_path {
+            remove_journal(curr_file_path);
+        }
+    }
+    // 另存为之后，后续的保存都写入新文件
+    state.curr_file_path = Some(file_path.clone());
+    Ok(file_path)
+}
+
+#[tauri::command]
+pub fn request_save_as(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<String, String> {
+    let result = request_save_as_helper(app, state);
+    match result {
+        Ok(file_path) => MyResult::Ok(file_path),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+#[tauri::command]
+pub fn query_save_settings(
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<SaveSettings, String> {
+    println!("Rust: query_save_settings called");
+    let state = state.read().unwrap();
+    MyResult::Ok(state.save_settings.clone())
+}
+
+#[tauri::command]
+pub fn request_update_save_settings(
+    settings: SaveSettings,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<(), String> {
+    println!(
+        "Rust: request_update_save_settings called with settings: {:?}",
+        settings
+    );
+    let mut state = state.write().unwrap();
+    state.save_settings = settings;
+    MyResult::Ok(())
+}
+
+#[tauri::command]
+pub fn query_data_file_settings(
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<DataFileSettings, String> {
+    println!("Rust: query_data_file_settings called");
+    let state = state.read().unwrap();
+    MyResult::Ok(state.data_file_settings.clone())
+}
+
+#[tauri::command]
+pub fn request_update_data_file_settings(
+    settings: DataFileSettings,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<(), String> {
+    println!(
+        "Rust: request_update_data_file_settings called with settings: {:?}",
+        settings
+    );
+    let mut state = state.write().unwrap();
+    state.data_file_settings = settings;
+    MyResult::Ok(())
+}
+
+fn request_convert_model_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<String, String> {
+    println!("Rust: request_convert_model called");
+    let input_path = app
+        .dialog()
+        .file()
+        .set_title("选择要转换的模型文件")
+        .add_filter("模型文件", &MODEL_EXTENSIONS)
+        .blocking_pick_file()
+        .map(file_path_to_string)
+        .ok_or("未选择文件".to_string())?;
+    let file_tree_model = read_file_tree_model(&input_path)?;
+    // 与打开模型时相同的检查，不合法的模型不进行转换
+    build_tree_model(file_tree_model.clone(), false)?;
+    let mut dialog = app
+        .dialog()
+        .file()
+        .set_title("保存转换后的模型文件（格式由扩展名决定）")
+        .add_filter("JSON", &["json"])
+        .add_filter("YAML", &["yaml", "yml"])
+        .add_filter("TOML", &["toml"]);
+    if let Some(file_stem) = std::path::Path::new(&input_path).file_stem() {
+        dialog = dialog.set_file_name(file_stem.to_string_lossy());
+    }
+    let output_path = dialog
+        .blocking_save_file()
+        .map(file_path_to_string)
+        .ok_or("未选择文件".to_string())?;
+    let backup_count = state.read().unwrap().save_settings.backup_count;
+    save_models(&output_path, file_tree_model, backup_count)?;
+    Ok(output_path)
+}
+
+#[tauri::command]
+pub fn request_convert_model(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<String, String> {
+    let result = request_convert_model_helper(app, state);
+    match result {
+        Ok(output_path) => MyResult::Ok(output_path),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn file_path_to_string(path: FilePath) -> String {
+    match path {
+        FilePath::Path(pathbuf) => pathbuf.to_string_lossy().to_string(),
+        FilePath::Url(url) => url.to_string(),
+    }
+}
+
+// 导入的模型先检查，再另存为普通模型文件并打开，返回新模型文件的路径
+fn import_model(
+    app: &AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+    input_path: &str,
+    file_tree_model: FileTreeModel,
+) -> Result<String, String> {
+    build_tree_model(file_tree_model.clone(), false)?;
+    let mut dialog = app
+        .dialog()
+        .file()
+        .set_title("保存导入的模型文件（格式由扩展名决定）")
+        .add_filter("模型文件", &MODEL_EXTENSIONS);
+    if let Some(file_stem) = std::path::Path::new(input_path).file_stem() {
+        dialog = dialog.set_file_name(format!("{}.json", file_stem.to_string_lossy()));
+    }
+    let output_path = dialog
+        .blocking_save_file()
+        .map(file_path_to_string)
+        .ok_or("未选择文件".to_string())?;
+    let mut state = state.write().unwrap();
+    save_models(
+        &output_path,
+        file_tree_model,
+        state.save_settings.backup_count,
+    )?;
+    // 重新加载，保证节点id与以后打开该文件时一致
+    let tree_model = load_models(&output_path, false)?;
+    let start_ids = tree_model.journal_ids();
+    open_model(
+        &mut state,
+        &output_path,
+        tree_model,
+        vec![],
+        History::default(),
+        start_ids,
+    )?;
+    Ok(output_path)
+}
+
+// 将当前模型导出到用户选择的文件，不改变当前模型的路径
+// prepare在持有读锁时从当前模型中取出需要的内容，serialize根据导出路径（扩展名）生成文件内容
+fn export_to_file<T>(
+    app: &AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+    title: &str,
+    filters: &[(&str, &[&str])],
+    prepare: impl FnOnce(&TreeModel) -> Result<T, String>,
+    serialize: impl FnOnce(T, &str) -> Result<Vec<u8>, String>,
+) -> Result<String, String> {
+    let (prepared, file_stem) = {
+        let state = state.read().unwrap();
+        let tree_model = state
+            .curr_tree_model
+            .as_ref()
+            .ok_or("模型未加载".to_string())?;
+        let file_stem = state
+            .curr_file_path
+            .as_ref()
+            .and_then(|path| std::path::Path::new(path).file_stem())
+            .map(|file_stem| file_stem.to_string_lossy().to_string());
+        (prepare(tree_model)?, file_stem)
+    };
+    let mut dialog = app.dialog().file().set_title(title);
+    for (name, extensions) in filters.iter() {
+        dialog = dialog.add_filter(*name, extensions);
+    }
+    if let (Some(file_stem), Some((_, extensions))) = (file_stem, filters.first()) {
+        dialog = dialog.set_file_name(format!("{}.{}", file_stem, extensions[0]));
+    }
+    let output_path = dialog
+        .blocking_save_file()
+        .map(file_path_to_string)
+        .ok_or("未选择文件".to_string())?;
+    let content = serialize(prepared, &output_path)?;
+    write_atomically(&output_path, &content)?;
+    Ok(output_path)
+}
+
+// 以文件模型的形式导出
+fn export_model(
+    app: &AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+    title: &str,
+    filters: &[(&str, &[&str])],
+    serialize: impl FnOnce(&FileTreeModel, &str) -> Result<Vec<u8>, String>,
+) -> Result<String, String> {
+    export_to_file(
+        app,
+        state,
+        title,
+        filters,
+        to_file_tree_model,
+        |file_tree_model, output_path| serialize(&file_tree_model, output_path),
+    )
+}
+
+// 选择要导入的文件，返回路径和文件内容
+fn pick_import_file(
+    app: &AppHandle,
+    title: &str,
+    filters: &[(&str, &[&str])],
+) -> Result<(String, Vec<u8>), String> {
+    let mut dialog = app.dialog().file().set_title(title);
+    for (name, extensions) in filters.iter() {
+        dialog = dialog.add_filter(*name, extensions);
+    }
+    let input_path = dialog
+        .blocking_pick_file()
+        .map(file_path_to_string)
+        .ok_or("未选择文件".to_string())?;
+    let content = std::fs::read(&input_path).map_err(|e| format!("读取文件错误\n{}", e))?;
+    Ok((input_path, content))
+}
+
+// 导入的内容没有唯一的根节点时，以文件名作为根节点名称
+fn default_root_name(input_path: &str) -> String {
+    std::path::Path::new(input_path)
+        .file_stem()
+        .map(|file_stem| file_stem.to_string_lossy().to_string())
+        .unwrap_or("根节点".to_string())
+}
+
+// 导入文本格式的文件需要UTF-8编码
+fn decode_text(content: Vec<u8>) -> Result<String, String> {
+    let content = String::from_utf8(content).map_err(|_| "文件不是UTF-8编码".to_string())?;
+    // 去掉部分编辑器写入的BOM
+    Ok(content.trim_start_matches('\u{feff}').to_string())
+}
+
+fn request_import_nested_json_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<String, String> {
+    println!("Rust: request_import_nested_json called");
+    let (input_path, content) =
+        pick_import_file(&app, "选择嵌套结构的JSON文件", &[("JSON", &["json"])])?;
+    let file_tree_model = nested::parse_nested(&decode_text(content)?)?;
+    import_model(&app, state, &input_path, file_tree_model)
+}
+
+#[tauri::command]
+pub fn request_import_nested_json(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<String, String> {
+    let result = request_import_nested_json_helper(app, state);
+    match result {
+        Ok(output_path) => MyResult::Ok(output_path),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_export_nested_json_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<String, String> {
+    println!("Rust: request_export_nested_json called");
+    export_model(
+        &app,
+        state,
+        "导出为嵌套结构的JSON文件",
+        &[("JSON", &["json"])],
+        |file_tree_model, _| nested::serialize_nested(file_tree_model).map(String::into_bytes),
+    )
+}
+
+#[tauri::command]
+pub fn request_export_nested_json(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<String, String> {
+    let result = request_export_nested_json_helper(app, state);
+    match result {
+        Ok(output_path) => MyResult::Ok(output_path),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_import_outline_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<String, String> {
+    println!("Rust: request_import_outline called");
+    let (input_path, content) = pick_import_file(
+        &app,
+        "选择大纲文件（Markdown列表、缩进文本或OPML）",
+        &[("大纲", &OUTLINE_EXTENSIONS)],
+    )?;
+    // 大纲有多个顶层条目时，以文件名作为根节点
+    let file_tree_model = OutlineFormat::from_path(&input_path)
+        .parse(&decode_text(content)?, &default_root_name(&input_path))?;
+    import_model(&app, state, &input_path, file_tree_model)
+}
+
+#[tauri::command]
+pub fn request_import_outline(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<String, String> {
+    let result = request_import_outline_helper(app, state);
+    match result {
+        Ok(output_path) => MyResult::Ok(output_path),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_export_outline_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<String, String> {
+    println!("Rust: request_export_outline called");
+    let with_algorithm = app
+        .dialog()
+        .message("是否在大纲条目末尾标注算法？例如“总分 [求和]”")
+        .title("导出大纲")
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "标注算法".to_string(),
+            "只导出名称".to_string(),
+        ))
+        .blocking_show();
+    export_model(
+        &app,
+        state,
+        "导出为大纲（格式由扩展名决定）",
+        &[
+            ("Markdown", &["md", "markdown"]),
+            ("缩进文本", &["txt"]),
+            ("OPML", &["opml"]),
+        ],
+        |file_tree_model, output_path| {
+            OutlineFormat::from_path(output_path)
+                .serialize(file_tree_model, with_algorithm)
+                .map(String::into_bytes)
+        },
+    )
+}
+
+#[tauri::command]
+pub fn request_export_outline(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<String, String> {
+    let result = request_export_outline_helper(app, state);
+    match result {
+        Ok(output_path) => MyResult::Ok(output_path),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_import_mind_map_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<String, String> {
+    println!("Rust: request_import_mind_map called");
+    let (input_path, content) = pick_import_file(
+        &app,
+        "选择思维导图文件（XMind或FreeMind）",
+        &[("思维导图", &MIND_MAP_EXTENSIONS)],
+    )?;
+    let file_tree_model = parse_mind_map(&input_path, &content, &default_root_name(&input_path))?;
+    import_model(&app, state, &input_path, file_tree_model)
+}
+
+#[tauri::command]
+pub fn request_import_mind_map(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<String, String> {
+    let result = request_import_mind_map_helper(app, state);
+    match result {
+        Ok(output_path) => MyResult::Ok(output_path),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_import_levels_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<String, String> {
+    println!("Rust: request_import_levels called");
+    let (input_path, content) = pick_import_file(
+        &app,
+        "选择层级指标表（一级指标、二级指标……）",
+        &[("CSV", &LEVEL_EXTENSIONS)],
+    )?;
+    let file_tree_model = parse_levels(&decode_text(content)?, &default_root_name(&input_path))?;
+    import_model(&app, state, &input_path, file_tree_model)
+}
+
+#[tauri::command]
+pub fn request_import_levels(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<String, String> {
+    let result = request_import_levels_helper(app, state);
+    match result {
+        Ok(output_path) => MyResult::Ok(output_path),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_export_levels_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<String, String> {
+    println!("Rust: request_export_levels called");
+    export_model(
+        &app,
+        state,
+        "导出为层级指标表",
+        &[("CSV", &LEVEL_EXTENSIONS)],
+        |file_tree_model, _| serialize_levels(file_tree_model).map(String::into_bytes),
+    )
+}
+
+#[tauri::command]
+pub fn request_export_levels(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<String, String> {
+    let result = request_export_levels_helper(app, state);
+    match result {
+        Ok(output_path) => MyResult::Ok(output_path),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_export_graph_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+    root: Option<u64>,
+    max_depth: Option<u64>,
+) -> Result<String, String> {
+    println!("Rust: request_export_graph called");
+    export_to_file(
+        &app,
+        state,
+        "导出为图形（Graphviz DOT或Mermaid，格式由扩展名决定）",
+        &[
+            ("Graphviz DOT", &["dot", "gv"]),
+            ("Mermaid", &["mmd", "md"]),
+            ("图形", &GRAPH_EXTENSIONS),
+        ],
+        |tree_model| build_graph(tree_model, root.unwrap_or(0), max_depth.map(|d| d as usize)),
+        |graph, output_path| {
+            Ok(GraphFormat::from_path(output_path)
+                .render(&graph, output_path)
+                .into_bytes())
+        },
+    )
+}
+
+#[tauri::command]
+pub fn request_export_graph(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+    root: Option<u64>,
+    max_depth: Option<u64>,
+) -> MyResult<String, String> {
+    let result = request_export_graph_helper(app, state, root, max_depth);
+    match result {
+        Ok(output_path) => MyResult::Ok(output_path),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_export_xlsx_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<String, String> {
+    println!("Rust: request_export_xlsx called");
+    export_to_file(
+        &app,
+        state,
+        "导出为带公式的Excel工作簿",
+        &[("Excel", &["xlsx"])],
+        build_workbook,
+        |content, _| Ok(content),
+    )
+}
+
+#[tauri::command]
+pub fn request_export_xlsx(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<String, String> {
+    let result = request_export_xlsx_helper(app, state);
+    match result {
+        Ok(output_path) => MyResult::Ok(output_path),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+// JSON数据文件或CSV、Excel表格
+fn pick_data_file(app: &AppHandle, title: &str) -> Result<String, String> {
+    let mut extensions = vec!["json"];
+    extensions.extend(TABLE_EXTENSIONS);
+    app.dialog()
+        .file()
+        .set_title(title)
+        .add_filter("数据文件", &extensions)
+        .blocking_pick_file()
+        .map(file_path_to_string)
+        .ok_or("未选择文件".to_string())
+}
+
+fn request_validate_data_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<DataValidationReport, String> {
+    println!("Rust: request_validate_data called");
+    let file_path = pick_data_file(&app, "选取要检查的数据文件")?;
+    let settings = state.read().unwrap().data_file_settings.clone();
+    let file_data = load_data(&file_path, &settings)?;
+    let state = state.read().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_ref()
+        .ok_or("模型未加载".to_string())?;
+    let leaves = template_rows(tree_model, 0)?
+        .into_iter()
+        .map(|row| row.name)
+        .collect::<Vec<String>>();
+    Ok(DataValidationReport {
+        file_path,
+        ..validate_data(&leaves, &file_data)
+    })
+}
+
+#[tauri::command]
+pub fn request_validate_data(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<DataValidationReport, String> {
+    let result = request_validate_data_helper(app, state);
+    match result {
+        Ok(report) => MyResult::Ok(report),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+// JSON和CSV数据文件直接修改，Excel文件另存为新文件
+fn request_apply_data_renames_helper(
+    app: AppHandle,
+    file_path: String,
+    renames: Vec<RenameSuggestion>,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<String, String> {
+    println!(
+        "Rust: request_apply_data_renames called with file_path: {}, renames: {:?}",
+        file_path, renames
+    );
+    let settings = state.read().unwrap().data_file_settings.clone();
+    let output_path = if is_workbook(&file_path) {
+        let file_stem = std::path::Path::new(&file_path)
+            .file_stem()
+            .map(|file_stem| file_stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        app.dialog()
+            .file()
+            .set_title("保存修改后的数据文件（只包含读取的工作表）")
+            .add_filter("Excel工作簿", &["xlsx"])
+            .set_file_name(format!("{}（已修正）.xlsx", file_stem))
+            .blocking_save_file()
+            .map(file_path_to_string)
+            .ok_or("未选择文件".to_string())?
+    } else {
+        file_path.clone()
+    };
+    let count = rename_data_keys(&file_path, &output_path, &settings, &renames)?;
+    Ok(format!("已在{}中修改{}个名称", output_path, count))
+}
+
+#[tauri::command]
+pub fn request_apply_data_renames(
+    app: AppHandle,
+    file_path: String,
+    renames: Vec<RenameSuggestion>,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<String, String> {
+    let result = request_apply_data_renames_helper(app, file_path, renames, state);
+    match result {
+        Ok(message) => MyResult::Ok(message),
+        Err(e) => MyResult::Err(e),
+    }
+}
+
+fn request_calculate_helper(
+    app: AppHandle,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<(), String> {
+    println!("Rust: request_calculate called");
+    let file_path = pick_data_file(&app, "选取数据文件")?;
+    let settings = state.read().unwrap().data_file_settings.clone();
+    let file_data = load_data(&file_path, &settings)?;
+    let mut state = state.write().unwrap();
+    let tree_model = state
+        .curr_tree_model
+        .as_ref()
+        .ok_or("模型未加载".to_string())?;
+    let leaves = template_rows(tree_model, 0)?
+        .into_iter()
+        .map(|row| row.name)
+        .collect::<Vec<String>>();
+    let report = validate_data(&leaves, &file_data);
+    if !report.is_ok() {
+        Err(format!("计算失败：{}", format_report(&report)))?;
+    }
+    let models = &tree_model.models;
+    // create a dynamic programming mem for calculation
+    // 从根节点无法到达的叶节点不参与计算，数据文件中可以没有它们的数值
+    let mut mem = HashMap::<u64, f64>::new();
+    for (id, model) in models.iter() {
+        if model.expand_info.is_some() {
+            continue;
+        }
+        if let Some(value) = file_data.get(&model.name) {
+            mem.insert(*id, *value);
+        }
     }
     // create a helper function for calculating a node's value based on its childrens'
     // this function will be called recursively
@@ -630,58 +1864,83 @@ pub fn query_values(
     }
 }
 
-fn request_template_generation_helper(app: AppHandle, state: tauri::State<RwLock<TauriState>>) -> Result<(), String> {
-    println!("Rust: request_template_generation called");
-    let state = state.read().unwrap();
-    let tree_model = state
-        .curr_tree_model
-        .as_ref()
-        .ok_or("模型未加载".to_string())?;
-    let models = &tree_model.models;
-    let mut file_data = FileData::new();
-    let answer = app.dialog()
-        .message("请选择模板类型")
-        .title("生成模板")
-        .buttons(MessageDialogButtons::OkCancelCustom("零初始化模板".to_string(), "随机初始化模板（调试）".to_string()))
-        .blocking_show();
-    if answer{
-        for (_id, model) in models.iter() {
-            if model.expand_info.is_none() {
-                file_data.insert(model.name.clone(), 0.0);
-            }
+fn request_template_generation_helper(
+    app: AppHandle,
+    settings: TemplateSettings,
+    state: tauri::State<RwLock<TauriState>>,
+) -> Result<String, String> {
+    println!(
+        "Rust: request_template_generation called with settings: {:?}",
+        settings
+    );
+    let data_settings = state.read().unwrap().data_file_settings.clone();
+    let file_data = match settings.fill {
+        TemplateFill::Dataset => Some(load_data(
+            &pick_data_file(&app, "选取用于预填的数据文件")?,
+            &data_settings,
+        )?),
+        _ => None,
+    };
+    let (rows, root_name) = {
+        let state = state.read().unwrap();
+        let tree_model = state
+            .curr_tree_model
+            .as_ref()
+            .ok_or("模型未加载".to_string())?;
+        let root = settings.root.unwrap_or(0);
+        let root_name = tree_model
+            .models
+            .get(&root)
+            .ok_or(format!("未找到模型{}", root))?
+            .name
+            .clone();
+        (template_rows(tree_model, root)?, root_name)
+    };
+    let values = match settings.fill {
+        TemplateFill::Zero => vec![Some(0.0); rows.len()],
+        TemplateFill::Empty => vec![None; rows.len()],
+        TemplateFill::Random => {
+            let mut rng = rand::rng();
+            rows.iter().map(|_| Some(rng.random())).collect()
         }
-    }
-    else{
-        let mut rng = rand::rng();
-        for (_id, model) in models.iter() {
-            if model.expand_info.is_none() {
-                file_data.insert(model.name.clone(), rng.random());
-            }
+        TemplateFill::Dataset => {
+            let file_data = file_data.unwrap_or_default();
+            rows.iter()
+                .map(|row| file_data.get(&row.name).copied())
+                .collect::<Vec<Option<f64>>>()
         }
-    }
-    let file_data_str = serde_json::to_string_pretty(&file_data).unwrap();
+    };
+    let content = build_template(&rows, &values, &settings, &data_settings)?;
+    let extension = template_extension(&settings.format);
     let file_path = app
-    .dialog()
-    .file()
-    .set_file_name("template.json")
-    // .add_filter("My Filter", &["png", "jpeg"])
-    .blocking_save_file();
-    // write str to file path
-    let file_path = file_path
-        .map(|path| match path {
-            FilePath::Path(pathbuf) => pathbuf.to_string_lossy().to_string(),
-            FilePath::Url(url) => url.to_string(),
-        })
+        .dialog()
+        .file()
+        .set_title("保存数据模板")
+        .add_filter("数据模板", &[extension])
+        .set_file_name(format!("{}数据模板.{}", root_name, extension))
+        .blocking_save_file()
+        .map(file_path_to_string)
         .ok_or("未选择文件".to_string())?;
-    std::fs::write(file_path, file_data_str).unwrap();
-    Ok(())
+    write_atomically(&file_path, &content)?;
+    let mut message = format!("模板已保存到{}，共{}项指标", file_path, rows.len());
+    if settings.fill == TemplateFill::Dataset {
+        let missing = values.iter().filter(|value| value.is_none()).count();
+        if missing > 0 {
+            message.push_str(&format!("，其中{}项在数据文件中没有数值", missing));
+        }
+    }
+    Ok(message)
 }
 
 #[tauri::command]
-pub fn request_template_generation(app: AppHandle, state: tauri::State<RwLock<TauriState>>) -> MyResult<(), String> {
-    let result = request_template_generation_helper(app, state);
+pub fn request_template_generation(
+    app: AppHandle,
+    settings: TemplateSettings,
+    state: tauri::State<RwLock<TauriState>>,
+) -> MyResult<String, String> {
+    let result = request_template_generation_helper(app, settings, state);
     match result {
-        Ok(_) => MyResult::Ok(()),
+        Ok(message) => MyResult::Ok(message),
         Err(e) => MyResult::Err(e),
     }
 }
@@ -0,0 +1,573 @@
+use crate::models::FileData;
+use calamine::{open_workbook_auto_from_rs, Data, Reader};
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use shared::{DataFileSettings, DataLayout};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+
+// 表格形式的数据文件（CSV、Excel），读取方式由DataFileSettings决定
+pub const TABLE_EXTENSIONS: [&str; 7] = ["csv", "tsv", "txt", "xlsx", "xlsm", "xls", "ods"];
+
+// 按Excel工作簿读取的扩展名，须包含在TABLE_EXTENSIONS中
+const WORKBOOK_EXTENSIONS: [&str; 4] = ["xlsx", "xlsm", "xls", "ods"];
+
+// 错误信息中最多列出的无效单元格数量
+const MAX_REPORTED_CELLS: usize = 20;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TableCell {
+    Empty,
+    Number(f64),
+    Text(String),
+}
+
+impl TableCell {
+    pub fn text(&self) -> String {
+        match self {
+            TableCell::Empty => String::new(),
+            TableCell::Number(number) => number.to_string(),
+            TableCell::Text(text) => text.trim().to_string(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text().is_empty()
+    }
+
+    pub fn number(&self) -> Option<f64> {
+        match self {
+            TableCell::Empty => None,
+            TableCell::Number(number) => Some(*number).filter(|number| number.is_finite()),
+            TableCell::Text(text) => parse_number(text),
+        }
+    }
+}
+
+// 行和列都从0开始，报告时转换为表格软件中的行号和列字母
+pub type Table = Vec<Vec<TableCell>>;
+
+pub fn column_name(column: usize) -> String {
+    let mut column = column + 1;
+    let mut name = String::new();
+    while column > 0 {
+        let remainder = (column - 1) % 26;
+        name.insert(0, (b'A' + remainder as u8) as char);
+        column = (column - 1) / 26;
+    }
+    name
+}
+
+pub fn cell_position(row: usize, column: usize) -> String {
+    format!(
+        "第{}行第{}列（{}）",
+        row + 1,
+        column + 1,
+        column_name(column)
+    )
+}
+
+// 拆分数字和其后的单位，去掉空白和撇号形式的千位分隔符
+fn split_number(text: &str) -> (String, String) {
+    let text = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\'' && *c != '’')
+        .map(|c| if c == '−' { '-' } else { c })
+        .collect::<String>();
+    let chars = text.chars().collect::<Vec<char>>();
+    let mut end = 0;
+    if matches!(chars.first(), Some('+') | Some('-')) {
+        end = 1;
+    }
+    while end < chars.len()
+        && (chars[end].is_ascii_digit() || chars[end] == '.' || chars[end] == ',')
+    {
+        end += 1;
+    }
+    // 指数部分，例如1.2e-3
+    if end < chars.len() && (chars[end] == 'e' || chars[end] == 'E') {
+        let mut exponent_end = end + 1;
+        if matches!(chars.get(exponent_end), Some('+') | Some('-')) {
+            exponent_end += 1;
+        }
+        if chars.get(exponent_end).is_some_and(|c| c.is_ascii_digit()) {
+            end = exponent_end;
+            while end < chars.len() && chars[end].is_ascii_digit() {
+                end += 1;
+            }
+        }
+    }
+    (chars[..end].iter().collect(), chars[end..].iter().collect())
+}
+
+// 只有一个逗号、没有点且逗号后恰好三位数字时（如“1,234”），
+// 逗号可能是千位分隔符也可能是小数点，不猜测其含义
+fn is_ambiguous_comma(number: &str) -> bool {
+    let mut parts = number.split(',');
+    let (Some(_), Some(fraction), None) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    !number.contains('.') && fraction.chars().take_while(|c| c.is_ascii_digit()).count() == 3
+}
+
+// 数字可以带单位（如“120 mmHg”、“36.5℃”），小数点可以是逗号（如“3,5”），
+// 同时出现逗号和点时后出现的为小数点，另一个为千位分隔符；逗号含义不确定时不是数字
+pub fn parse_number(text: &str) -> Option<f64> {
+    let (number, unit) = split_number(text);
+    // 剩余部分是单位，不能再包含数字，例如“12-15”不是一个数
+    if unit.chars().any(|c| c.is_ascii_digit()) || !number.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    if is_ambiguous_comma(&number) {
+        return None;
+    }
+    let last_dot = number.rfind('.');
+    let last_comma = number.rfind(',');
+    let number = match (last_dot, last_comma) {
+        (Some(dot), Some(comma)) if dot > comma => number.replace(',', ""),
+        (Some(_), Some(_)) => number.replace('.', "").replace(',', "."),
+        (None, Some(_)) if number.matches(',').count() == 1 => number.replace(',', "."),
+        (None, Some(_)) => number.replace(',', ""),
+        (Some(_), None) if number.matches('.').count() > 1 => number.replace('.', ""),
+        _ => number,
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+}
+
+// 分隔符取第一行中出现最多的逗号、分号或制表符，使用小数逗号的地区通常用分号分隔
+pub fn detect_delimiter(content: &str) -> u8 {
+    let first_line = content.lines().next().unwrap_or("");
+    [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|delimiter| {
+            let count = first_line.matches(*delimiter as char).count();
+            // 数量相同时优先使用逗号
+            (count, *delimiter == b',')
+        })
+        .unwrap_or(b',')
+}
+
+pub fn read_csv_table(content: &str) -> Result<Table, String> {
+    let content = content.trim_start_matches('\u{feff}');
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(detect_delimiter(content))
+        .from_reader(content.as_bytes());
+    let mut table = Table::new();
+    for (row, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("解析第{}行错误\n{}", row + 1, e))?;
+        table.push(
+            record
+                .iter()
+                .map(|cell| match cell.trim() {
+                    "" => TableCell::Empty,
+                    cell => TableCell::Text(cell.to_string()),
+                })
+                .collect(),
+        );
+    }
+    Ok(table)
+}
+
+pub fn read_workbook_table(content: Vec<u8>, sheet: &str) -> Result<Table, String> {
+    let mut workbook = open_workbook_auto_from_rs(Cursor::new(content))
+        .map_err(|e| format!("解析Excel文件错误\n{}", e))?;
+    let range = if sheet.trim().is_empty() {
+        workbook
+            .worksheet_range_at(0)
+            .ok_or("Excel文件中没有工作表".to_string())?
+    } else {
+        workbook.worksheet_range(sheet.trim())
+    }
+    .map_err(|e| format!("读取工作表错误\n{}", e))?;
+    // 工作表不一定从A1开始，补齐前面的空行和空列，使行列号与Excel中一致
+    let (start_row, start_column) = range.start().unwrap_or((0, 0));
+    let mut table = vec![vec![]; start_row as usize];
+    for row in range.rows() {
+        let mut cells = vec![TableCell::Empty; start_column as usize];
+        cells.extend(row.iter().map(|cell| match cell {
+            Data::Int(number) => TableCell::Number(*number as f64),
+            Data::Float(number) => TableCell::Number(*number),
+            Data::String(text) => TableCell::Text(text.clone()),
+            Data::Empty => TableCell::Empty,
+            cell => TableCell::Text(cell.to_string()),
+        }));
+        table.push(cells);
+    }
+    Ok(table)
+}
+
+// 按扩展名读取CSV或Excel文件
+pub fn read_table(file_path: &str, sheet: &str) -> Result<Table, String> {
+    let content =
+        std::fs::read(file_path).map_err(|e| format!("读取数据文件{:?}错误\n{}", file_path, e))?;
+    if is_workbook(file_path) {
+        read_workbook_table(content, sheet)
+    } else {
+        let content = String::from_utf8(content).map_err(|_| "CSV文件不是UTF-8编码".to_string())?;
+        read_csv_table(&content)
+    }
+}
+
+// 写出带BOM的UTF-8 CSV，Excel打开时中文不会乱码
+pub fn write_csv_table(table: &Table, delimiter: u8) -> Result<Vec<u8>, String> {
+    let mut writer = csv::WriterBuilder::new()
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_writer("\u{feff}".as_bytes().to_vec());
+    for cells in table.iter() {
+        writer
+            .write_record(cells.iter().map(|cell| cell.text()))
+            .map_err(|e| format!("写入CSV错误\n{}", e))?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| format!("写入CSV错误\n{}", e))
+}
+
+// 第一行作为表头加粗并冻结
+pub fn write_workbook_table(table: &Table, sheet: &str) -> Result<Vec<u8>, String> {
+    let excel_error = |e: XlsxError| format!("生成Excel文件错误\n{}", e);
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(sheet).map_err(excel_error)?;
+    for (row, cells) in table.iter().enumerate() {
+        for (column, cell) in cells.iter().enumerate() {
+            let (row, column) = (row as u32, column as u16);
+            match cell {
+                TableCell::Empty => {}
+                TableCell::Number(number) => {
+                    worksheet
+                        .write_number(row, column, *number)
+                        .map_err(excel_error)?;
+                }
+                TableCell::Text(text) if row == 0 => {
+                    worksheet
+                        .write_string_with_format(row, column, text, &bold)
+                        .map_err(excel_error)?;
+                }
+                TableCell::Text(text) => {
+                    worksheet
+                        .write_string(row, column, text)
+                        .map_err(excel_error)?;
+                }
+            }
+        }
+    }
+    worksheet.set_freeze_panes(1, 0).map_err(excel_error)?;
+    worksheet.set_column_width(0, 30).map_err(excel_error)?;
+    workbook.save_to_buffer().map_err(excel_error)
+}
+
+pub fn is_workbook(file_path: &str) -> bool {
+    let extension = Path::new(file_path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    extension.is_some_and(|extension| WORKBOOK_EXTENSIONS.contains(&extension.as_str()))
+}
+
+fn cell(table: &Table, row: usize, column: usize) -> &TableCell {
+    table
+        .get(row)
+        .and_then(|cells| cells.get(column))
+        .unwrap_or(&TableCell::Empty)
+}
+
+// 列可以用表头文字、列字母或从1开始的列号指定，表头文字优先
+pub fn resolve_column(headers: &[TableCell], spec: &str) -> Option<usize> {
+    let spec = spec.trim();
+    if spec.is_empty() {
+        return None;
+    }
+    if let Some(column) = headers.iter().position(|header| header.text() == spec) {
+        return Some(column);
+    }
+    if let Ok(number) = spec.parse::<usize>() {
+        return number.checked_sub(1);
+    }
+    if spec.chars().all(|c| c.is_ascii_alphabetic()) && spec.len() <= 3 {
+        let column = spec
+            .to_ascii_uppercase()
+            .chars()
+            .fold(0, |column, c| column * 26 + (c as usize - 'A' as usize + 1));
+        return Some(column - 1);
+    }
+    None
+}
+
+fn report(errors: Vec<String>) -> Result<(), String> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let mut message = format!("数据文件中有{}处无效的单元格：", errors.len());
+    for error in errors.iter().take(MAX_REPORTED_CELLS) {
+        message.push('\n');
+        message.push_str(error);
+    }
+    if errors.len() > MAX_REPORTED_CELLS {
+        message.push_str("\n……");
+    }
+    Err(message)
+}
+
+fn invalid_number(row: usize, column: usize, cell: &TableCell) -> String {
+    let text = cell.text();
+    if is_ambiguous_comma(&split_number(&text).0) {
+        return format!(
+            "{}：无法确定\"{}\"中的逗号是千位分隔符还是小数点，请去掉千位分隔符或改用小数点",
+            cell_position(row, column),
+            text
+        );
+    }
+    format!("{}：\"{}\"不是数字", cell_position(row, column), text)
+}
+
+// 第一个非空行为表头
+fn header_row(table: &Table) -> Result<usize, String> {
+    table
+        .iter()
+        .position(|cells| cells.iter().any(|cell| !cell.is_empty()))
+        .ok_or("数据文件为空".to_string())
+}
+
+fn table_to_key_value(table: &Table, settings: &DataFileSettings) -> Result<FileData, String> {
+    let header_row = header_row(table)?;
+    let headers = &table[header_row];
+    let name_column = resolve_column(headers, &settings.name_column).ok_or(format!(
+        "数据文件中找不到名称列\"{}\"",
+        settings.name_column
+    ))?;
+    let value_column = resolve_column(headers, &settings.value_column).ok_or(format!(
+        "数据文件中找不到数值列\"{}\"",
+        settings.value_column
+    ))?;
+    let mut file_data = FileData::new();
+    let mut first_rows = HashMap::<String, usize>::new();
+    let mut errors = Vec::new();
+    for row in header_row + 1..table.len() {
+        let name = cell(table, row, name_column).text();
+        let value = cell(table, row, value_column);
+        if name.is_empty() {
+            if !value.is_empty() {
+                errors.push(format!("{}：缺少名称", cell_position(row, name_column)));
+            }
+            continue;
+        }
+        if let Some(first_row) = first_rows.get(&name) {
+            errors.push(format!(
+                "{}：名称\"{}\"与第{}行重复",
+                cell_position(row, name_column),
+                name,
+                first_row + 1
+            ));
+            continue;
+        }
+        first_rows.insert(name.clone(), row);
+        match value.number() {
+            Some(number) => {
+                file_data.insert(name, number);
+            }
+            None if value.is_empty() => {
+                errors.push(format!("{}：缺少数值", cell_position(row, value_column)));
+            }
+            None => errors.push(invalid_number(row, value_column, value)),
+        }
+    }
+    report(errors)?;
+    Ok(file_data)
+}
+
+fn table_to_wide(table: &Table, settings: &DataFileSettings) -> Result<FileData, String> {
+    let header_row = header_row(table)?;
+    let headers = &table[header_row];
+    let subject_column = match settings.subject_column.trim() {
+        "" => None,
+        spec => Some(
+            resolve_column(headers, spec).ok_or(format!("数据文件中找不到对象列\"{}\"", spec))?,
+        ),
+    };
+    let data_rows = (header_row + 1..table.len())
+        .filter(|row| table[*row].iter().any(|cell| !cell.is_empty()))
+        .collect::<Vec<usize>>();
+    let row = match (subject_column, settings.subject.trim()) {
+        (Some(subject_column), subject) if !subject.is_empty() => *data_rows
+            .iter()
+            .find(|row| cell(table, **row, subject_column).text() == subject)
+            .ok_or(format!("数据文件中找不到对象\"{}\"", subject))?,
+        _ => *data_rows
+            .first()
+            .ok_or("数据文件中没有数据行".to_string())?,
+    };
+    let mut file_data = FileData::new();
+    let mut errors = Vec::new();
+    for (column, header) in headers.iter().enumerate() {
+        let name = header.text();
+        if name.is_empty() || Some(column) == subject_column {
+            continue;
+        }
+        let value = cell(table, row, column);
+        if value.is_empty() {
+            continue;
+        }
+        match value.number() {
+            Some(number) => {
+                file_data.insert(name, number);
+            }
+            None => errors.push(invalid_number(row, column, value)),
+        }
+    }
+    report(errors)?;
+    Ok(file_data)
+}
+
+pub fn table_to_data(table: &Table, settings: &DataFileSettings) -> Result<FileData, String> {
+    match settings.layout {
+        DataLayout::KeyValue => table_to_key_value(table, settings),
+        DataLayout::Wide => table_to_wide(table, settings),
+    }
+}
+
+// 将数据文件中的名称改为renames中对应的名称，每行一个指标时修改名称列，每行一个对象时修改表头，
+// 返回修改的单元格数量
+pub fn rename_in_table(
+    table: &mut Table,
+    settings: &DataFileSettings,
+    renames: &HashMap<String, String>,
+) -> Result<usize, String> {
+    let header_row = header_row(table)?;
+    let cells = match settings.layout {
+        DataLayout::KeyValue => {
+            let name_column = resolve_column(&table[header_row], &settings.name_column).ok_or(
+                format!("数据文件中找不到名称列\"{}\"", settings.name_column),
+            )?;
+            table[header_row + 1..]
+                .iter_mut()
+                .filter_map(|cells| cells.get_mut(name_column))
+                .collect::<Vec<&mut TableCell>>()
+        }
+        DataLayout::Wide => table[header_row].iter_mut().collect(),
+    };
+    let mut count = 0;
+    for cell in cells {
+        if let Some(name) = renames.get(&cell.text()) {
+            *cell = TableCell::Text(name.clone());
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_with_units_and_separators() {
+        assert_eq!(parse_number("120 mmHg"), Some(120.0));
+        assert_eq!(parse_number("36.5℃"), Some(36.5));
+        assert_eq!(parse_number("3,5"), Some(3.5));
+        assert_eq!(parse_number("1,5"), Some(1.5));
+        assert_eq!(parse_number("1,234"), None);
+        assert_eq!(parse_number("-1,234 元"), None);
+        assert_eq!(parse_number("1,2345"), Some(1.2345));
+        assert_eq!(parse_number("1,234,567"), Some(1234567.0));
+        assert_eq!(parse_number("1,234.5"), Some(1234.5));
+        assert_eq!(parse_number("1.234,5"), Some(1234.5));
+        assert_eq!(parse_number("1.234.567"), Some(1234567.0));
+        assert_eq!(parse_number("1'000"), Some(1000.0));
+        assert_eq!(parse_number("−5"), Some(-5.0));
+        assert_eq!(parse_number("1.2e-3"), Some(0.0012));
+        assert_eq!(parse_number("12-15"), None);
+        assert_eq!(parse_number("阴性"), None);
+        assert_eq!(parse_number(""), None);
+    }
+
+    #[test]
+    fn workbooks_are_table_files() {
+        for extension in WORKBOOK_EXTENSIONS {
+            assert!(TABLE_EXTENSIONS.contains(&extension));
+            assert!(is_workbook(&format!("数据.{}", extension.to_uppercase())));
+        }
+        assert!(!is_workbook("数据.csv"));
+    }
+
+    #[test]
+    fn columns_by_header_letter_or_number() {
+        let headers = [
+            TableCell::Text("名称".to_string()),
+            TableCell::Text("值".to_string()),
+        ];
+        assert_eq!(resolve_column(&headers, "值"), Some(1));
+        assert_eq!(resolve_column(&headers, "b"), Some(1));
+        assert_eq!(resolve_column(&headers, "1"), Some(0));
+        assert_eq!(resolve_column(&headers, "AA"), Some(26));
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(resolve_column(&headers, ""), None);
+    }
+
+    // 使用小数逗号的地区用分号分隔
+    #[test]
+    fn csv_round_trip() {
+        let table = read_csv_table("\u{feff}名称;值\n心率;72\n体温;36,5 ℃\n").unwrap();
+        let file_data = table_to_data(&table, &DataFileSettings::default()).unwrap();
+        assert_eq!(file_data["心率"], 72.0);
+        assert_eq!(file_data["体温"], 36.5);
+        let content = write_csv_table(&table, b',').unwrap();
+        let reread = read_csv_table(std::str::from_utf8(&content).unwrap()).unwrap();
+        assert_eq!(
+            table_to_data(&reread, &DataFileSettings::default()).unwrap(),
+            file_data
+        );
+    }
+
+    #[test]
+    fn workbook_round_trip() {
+        let table = vec![
+            vec![
+                TableCell::Text("对象".to_string()),
+                TableCell::Text("心率".to_string()),
+                TableCell::Text("体温".to_string()),
+            ],
+            vec![
+                TableCell::Text("甲".to_string()),
+                TableCell::Number(72.0),
+                TableCell::Number(36.5),
+            ],
+            vec![
+                TableCell::Text("乙".to_string()),
+                TableCell::Number(80.0),
+                TableCell::Empty,
+            ],
+        ];
+        let content = write_workbook_table(&table, "数据").unwrap();
+        let reread = read_workbook_table(content, "数据").unwrap();
+        let settings = DataFileSettings {
+            layout: DataLayout::Wide,
+            subject_column: "对象".to_string(),
+            subject: "乙".to_string(),
+            ..Default::default()
+        };
+        let file_data = table_to_data(&reread, &settings).unwrap();
+        assert_eq!(file_data.len(), 1);
+        assert_eq!(file_data["心率"], 80.0);
+    }
+
+    #[test]
+    fn invalid_cells_are_reported() {
+        let table = read_csv_table("名称,值\n心率,快\n心率,72\n,1\n").unwrap();
+        let error = table_to_data(&table, &DataFileSettings::default()).unwrap_err();
+        assert!(error.starts_with("数据文件中有3处无效的单元格"));
+    }
+
+    #[test]
+    fn ambiguous_comma_is_reported() {
+        let table = read_csv_table("名称,值\n心率,\"1,234\"\n").unwrap();
+        let error = table_to_data(&table, &DataFileSettings::default()).unwrap_err();
+        assert!(error.contains("第2行第2列（B）：无法确定\"1,234\"中的逗号"));
+    }
+}
@@ -0,0 +1,37 @@
+// 与其他工具交换模型的格式，导入的结果都是FileTreeModel，再经过build_tree_model检查
+pub mod graph;
+pub mod levels;
+pub mod mindmap;
+pub mod nested;
+pub mod outline;
+pub mod workbook;
+
+#[cfg(test)]
+pub mod tests {
+    use crate::loader::read_file_tree_model;
+    use crate::models::FileTreeModel;
+    use std::collections::BTreeMap;
+
+    // 用于往返测试的真实模型，包含共享节点和无法解析的算法（“待定”）
+    pub fn sample_model() -> FileTreeModel {
+        read_file_tree_model(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../models/健康指数.json"
+        ))
+        .unwrap()
+    }
+
+    // 每个非叶节点的子节点和算法，用于比较往返前后的模型
+    pub fn structure(
+        file_tree_model: &FileTreeModel,
+    ) -> BTreeMap<String, (Vec<String>, Option<String>)> {
+        file_tree_model
+            .data
+            .iter()
+            .filter_map(|model| {
+                let children = model.children.clone()?;
+                Some((model.name.clone(), (children, model.algorithm.clone())))
+            })
+            .collect()
+    }
+}
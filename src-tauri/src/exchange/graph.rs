@@ -0,0 +1,272 @@
+use crate::models::TreeModel;
+use shared::Algorithm;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+
+// 将模型的有向无环图导出为Graphviz DOT或Mermaid流程图
+// 共享节点只画一次，有多条入边；节点标签为名称、算法和已计算的值
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+pub const GRAPH_EXTENSIONS: [&str; 4] = ["dot", "gv", "mmd", "md"];
+
+impl GraphFormat {
+    pub fn from_path(file_path: &str) -> GraphFormat {
+        let extension = Path::new(file_path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("mmd") | Some("md") => GraphFormat::Mermaid,
+            _ => GraphFormat::Dot,
+        }
+    }
+
+    pub fn render(&self, graph: &Graph, file_path: &str) -> String {
+        match self {
+            GraphFormat::Dot => render_dot(graph),
+            GraphFormat::Mermaid => {
+                let content = render_mermaid(graph);
+                // Markdown文件中需要放在代码块里才能被渲染
+                if file_path.to_lowercase().ends_with(".md") {
+                    format!("```mermaid\n{}```\n", content)
+                } else {
+                    content
+                }
+            }
+        }
+    }
+}
+
+pub struct GraphNode {
+    pub id: u64,
+    pub lines: Vec<String>,
+    pub is_leaf: bool,
+    // 因深度限制没有画出下级节点
+    pub truncated: bool,
+}
+
+pub struct Graph {
+    pub title: String,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<(u64, u64)>,
+}
+
+fn format_value(value: f64) -> String {
+    let text = format!("{:.4}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    text.to_string()
+}
+
+// 算法的显示名称，无法识别的算法显示文件中的原文
+pub fn algorithm_text(tree_model: &TreeModel, id: u64, algorithm: &Algorithm) -> String {
+    match algorithm {
+        Algorithm::None => tree_model
+            .extras
+            .raw_algorithms
+            .get(&id)
+            .filter(|raw| !raw.is_empty())
+            .cloned()
+            .unwrap_or("未设置算法".to_string()),
+        algorithm => algorithm.to_string(),
+    }
+}
+
+// 从root开始按广度优先遍历，节点的深度为离root最近的距离，max_depth为None时不限制深度
+pub fn build_graph(
+    tree_model: &TreeModel,
+    root: u64,
+    max_depth: Option<usize>,
+) -> Result<Graph, String> {
+    let models = &tree_model.models;
+    let root_model = models
+        .get(&root)
+        .ok_or(format!("导出错误：未找到模型{}", root))?;
+    let mut depths = HashMap::<u64, usize>::new();
+    let mut queue = VecDeque::from([root]);
+    depths.insert(root, 0);
+    let mut graph = Graph {
+        title: root_model.name.clone(),
+        nodes: vec![],
+        edges: vec![],
+    };
+    while let Some(id) = queue.pop_front() {
+        let model = models
+            .get(&id)
+            .ok_or(format!("导出错误：未找到模型{}", id))?;
+        let depth = depths[&id];
+        let mut lines = vec![model.name.clone()];
+        let truncated = match model.expand_info.as_ref() {
+            Some(expand_info) => {
+                let algorithm = algorithm_text(tree_model, id, &expand_info.algorithm);
+                lines.push(algorithm);
+                let expand = max_depth.is_none_or(|max_depth| depth < max_depth);
+                if expand {
+                    for child in expand_info.children.iter() {
+                        graph.edges.push((id, *child));
+                        if !depths.contains_key(child) {
+                            depths.insert(*child, depth + 1);
+                            queue.push_back(*child);
+                        }
+                    }
+                }
+                !expand && !expand_info.children.is_empty()
+            }
+            None => false,
+        };
+        if let Some(value) = model.value {
+            lines.push(format!("= {}", format_value(value)));
+        }
+        graph.nodes.push(GraphNode {
+            id,
+            lines,
+            is_leaf: model.expand_info.is_none(),
+            truncated,
+        });
+    }
+    Ok(graph)
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn render_dot(graph: &Graph) -> String {
+    let mut content = format!("digraph \"{}\" {{\n", escape_dot(&graph.title));
+    content.push_str("    rankdir=TB;\n");
+    content.push_str("    node [shape=box, style=rounded, fontname=\"Microsoft YaHei\"];\n");
+    for node in graph.nodes.iter() {
+        let mut lines = node
+            .lines
+            .iter()
+            .map(|line| escape_dot(line))
+            .collect::<Vec<String>>();
+        if node.truncated {
+            lines.push("…".to_string());
+        }
+        let shape = if node.is_leaf { ", shape=ellipse" } else { "" };
+        let style = if node.truncated {
+            ", style=\"rounded,dashed\""
+        } else {
+            ""
+        };
+        content.push_str(&format!(
+            "    n{} [label=\"{}\"{}{}];\n",
+            node.id,
+            lines.join("\\n"),
+            shape,
+            style
+        ));
+    }
+    for (parent, child) in graph.edges.iter() {
+        content.push_str(&format!("    n{} -> n{};\n", parent, child));
+    }
+    content.push_str("}\n");
+    content
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+fn render_mermaid(graph: &Graph) -> String {
+    let mut content = "flowchart TD\n".to_string();
+    for node in graph.nodes.iter() {
+        let mut lines = node
+            .lines
+            .iter()
+            .map(|line| escape_mermaid(line))
+            .collect::<Vec<String>>();
+        if node.truncated {
+            lines.push("…".to_string());
+        }
+        let label = lines.join("<br/>");
+        if node.is_leaf {
+            content.push_str(&format!("    n{}([\"{}\"])\n", node.id, label));
+        } else {
+            content.push_str(&format!("    n{}[\"{}\"]\n", node.id, label));
+        }
+    }
+    for (parent, child) in graph.edges.iter() {
+        content.push_str(&format!("    n{} --> n{}\n", parent, child));
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::build_tree_model;
+
+    fn load() -> TreeModel {
+        let file_tree_model = serde_json::from_str(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["A", "B"], "algorithm": "取最小值"},
+                    {"name": "A", "children": ["S"], "algorithm": "取最大值"},
+                    {"name": "B", "children": ["S", "C"], "algorithm": "待定"},
+                    {"name": "C", "children": ["c"], "algorithm": "求和"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        build_tree_model(file_tree_model, false).unwrap()
+    }
+
+    fn edges(tree_model: &TreeModel, graph: &Graph) -> Vec<(String, String)> {
+        let name = |id: &u64| tree_model.models[id].name.clone();
+        let mut edges = graph
+            .edges
+            .iter()
+            .map(|(parent, child)| (name(parent), name(child)))
+            .collect::<Vec<(String, String)>>();
+        edges.sort();
+        edges
+    }
+
+    // 共享节点S只画一次，有两条入边
+    #[test]
+    fn shared_node_drawn_once() {
+        let tree_model = load();
+        let graph = build_graph(&tree_model, 0, None).unwrap();
+        assert_eq!(graph.title, "Root");
+        assert_eq!(graph.nodes.len(), 6);
+        assert_eq!(
+            edges(&tree_model, &graph),
+            [
+                ("A", "S"),
+                ("B", "C"),
+                ("B", "S"),
+                ("C", "c"),
+                ("Root", "A"),
+                ("Root", "B")
+            ]
+            .map(|(parent, child)| (parent.to_string(), child.to_string()))
+        );
+        let b = graph
+            .nodes
+            .iter()
+            .find(|node| node.lines[0] == "B")
+            .unwrap();
+        assert_eq!(b.lines[1], "待定");
+    }
+
+    #[test]
+    fn depth_limit_truncates() {
+        let tree_model = load();
+        let graph = build_graph(&tree_model, 0, Some(1)).unwrap();
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.edges.len(), 2);
+        assert!(graph.nodes.iter().skip(1).all(|node| node.truncated));
+        let dot = GraphFormat::Dot.render(&graph, "graph.dot");
+        assert!(dot.contains("style=\"rounded,dashed\""));
+        let mermaid = GraphFormat::Mermaid.render(&graph, "graph.md");
+        assert!(mermaid.starts_with("```mermaid\nflowchart TD\n"));
+        assert_eq!(mermaid.matches(" --> ").count(), 2);
+    }
+}
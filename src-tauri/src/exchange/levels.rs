@@ -0,0 +1,390 @@
+use crate::models::{FileModel, FileTreeModel};
+use serde_json::{Map, Number, Value};
+use shared::NodeMetadata;
+use std::collections::{HashMap, HashSet};
+
+// 层级列表格：每行描述一个指标，“一级指标”“二级指标”……列给出从上到下的路径
+// 左侧的空单元格继承上一行同一列的指标（其左侧的指标须与上一行相同），同名指标视为同一个共享节点
+// 可选的“算法”和“权重”列描述该行最深一级的指标
+// 父子关系只记录一次，同一父节点下重复引用的子节点导入后只保留第一个
+pub const LEVEL_EXTENSIONS: [&str; 1] = ["csv"];
+
+// 权重不参与计算，保存在节点的额外字段中，导出时原样写回
+pub const WEIGHT_FIELD: &str = "weight";
+
+const DIGITS: [&str; 10] = ["", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+
+// 1到99的中文数字，用于列名“一级指标”“十二级指标”
+fn chinese_number(n: usize) -> String {
+    match n {
+        1..=9 => DIGITS[n].to_string(),
+        10..=19 => format!("十{}", DIGITS[n % 10]),
+        20..=99 => format!("{}十{}", DIGITS[n / 10], DIGITS[n % 10]),
+        _ => n.to_string(),
+    }
+}
+
+pub fn level_header(level: usize) -> String {
+    format!("{}级指标", chinese_number(level))
+}
+
+fn is_level_column(header: &str) -> bool {
+    header.ends_with("级指标") || header.to_lowercase().starts_with("level")
+}
+
+struct Columns {
+    levels: Vec<usize>,
+    algorithm: Option<usize>,
+    weight: Option<usize>,
+}
+
+fn find_columns(headers: &csv::StringRecord) -> Result<Columns, String> {
+    let mut columns = Columns {
+        levels: vec![],
+        algorithm: None,
+        weight: None,
+    };
+    for (index, header) in headers.iter().enumerate() {
+        let header = header.trim();
+        if is_level_column(header) {
+            columns.levels.push(index);
+        } else if header == "算法" || header.eq_ignore_ascii_case("algorithm") {
+            columns.algorithm = Some(index);
+        } else if header == "权重" || header.eq_ignore_ascii_case("weight") {
+            columns.weight = Some(index);
+        }
+    }
+    if columns.levels.is_empty() {
+        Err("导入错误：表头中没有“一级指标”等层级列".to_string())?;
+    }
+    Ok(columns)
+}
+
+struct LevelsBuilder {
+    data: Vec<FileModel>,
+    index: HashMap<String, usize>,
+    tops: Vec<String>,
+}
+
+impl LevelsBuilder {
+    fn node(&mut self, name: &str) -> &mut FileModel {
+        let index = match self.index.get(name) {
+            Some(index) => *index,
+            None => {
+                self.index.insert(name.to_string(), self.data.len());
+                self.data.push(FileModel {
+                    name: name.to_string(),
+                    children: None,
+                    algorithm: None,
+                    metadata: NodeMetadata::default(),
+                    extra: Map::new(),
+                });
+                self.data.len() - 1
+            }
+        };
+        &mut self.data[index]
+    }
+
+    fn add_edge(&mut self, parent: &str, child: &str) {
+        self.node(child);
+        let children = self.node(parent).children.get_or_insert_with(Vec::new);
+        if !children.iter().any(|name| name == child) {
+            children.push(child.to_string());
+        }
+    }
+}
+
+fn cell(record: &csv::StringRecord, index: Option<usize>) -> &str {
+    index
+        .and_then(|index| record.get(index))
+        .map(|cell| cell.trim())
+        .unwrap_or("")
+}
+
+// 行号和列号从1开始，与表格软件中显示的一致
+pub fn parse_levels(content: &str, default_root_name: &str) -> Result<FileTreeModel, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("解析表头错误\n{}", e))?
+        .clone();
+    let columns = find_columns(&headers)?;
+    let mut builder = LevelsBuilder {
+        data: vec![],
+        index: HashMap::new(),
+        tops: vec![],
+    };
+    let mut previous_path: Vec<String> = vec![];
+    for (row, record) in reader.records().enumerate() {
+        let row = row + 2;
+        let record = record.map_err(|e| format!("解析第{}行错误\n{}", row, e))?;
+        let cells = columns
+            .levels
+            .iter()
+            .map(|index| cell(&record, Some(*index)))
+            .collect::<Vec<&str>>();
+        let depth = match cells.iter().rposition(|cell| !cell.is_empty()) {
+            Some(depth) => depth,
+            None => continue,
+        };
+        let mut path = Vec::new();
+        // 左侧的指标都与上一行相同时才继承，出现不同的指标后上一行的指标属于另一个上级
+        let mut inheriting = true;
+        for (level, name) in cells[..=depth].iter().enumerate() {
+            if !name.is_empty() {
+                inheriting =
+                    inheriting && previous_path.get(level).is_some_and(|prev| prev == name);
+                path.push(name.to_string());
+            } else if !inheriting {
+                Err(format!(
+                    "导入错误：第{}行第{}列为空，但左侧的指标与上一行不同，无法继承上一行的指标",
+                    row,
+                    columns.levels[level] + 1
+                ))?;
+            } else {
+                let inherited = previous_path.get(level).ok_or(format!(
+                    "导入错误：第{}行第{}列为空，且上一行没有可继承的指标",
+                    row,
+                    columns.levels[level] + 1
+                ))?;
+                path.push(inherited.clone());
+            }
+        }
+        if !builder.tops.contains(&path[0]) {
+            builder.tops.push(path[0].clone());
+        }
+        builder.node(&path[0]);
+        for pair in path.windows(2) {
+            builder.add_edge(&pair[0], &pair[1]);
+        }
+        let name = &path[depth];
+        let algorithm = cell(&record, columns.algorithm);
+        if !algorithm.is_empty() {
+            let model = builder.node(name);
+            match model.algorithm.as_deref() {
+                Some(existing) if existing != algorithm => Err(format!(
+                    "导入错误：第{}行中指标\"{}\"的算法\"{}\"与之前的\"{}\"不一致",
+                    row, name, algorithm, existing
+                ))?,
+                _ => {}
+            }
+            model.algorithm = Some(algorithm.to_string());
+            model.children.get_or_insert_with(Vec::new);
+        }
+        let weight = cell(&record, columns.weight);
+        if !weight.is_empty() {
+            let weight = weight
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .ok_or(format!(
+                    "导入错误：第{}行第{}列的权重\"{}\"不是数字",
+                    row,
+                    columns.weight.unwrap() + 1,
+                    weight
+                ))?;
+            builder
+                .node(name)
+                .extra
+                .insert(WEIGHT_FIELD.to_string(), Value::Number(weight));
+        }
+        previous_path = path;
+    }
+    let root_name = match builder.tops.len() {
+        0 => Err("导入错误：表格中没有指标".to_string())?,
+        1 => builder.tops[0].clone(),
+        _ => {
+            let root_name = default_root_name.to_string();
+            if builder.index.contains_key(&root_name) {
+                Err(format!(
+                    "导入错误：表格有多个一级指标，作为根节点的文件名\"{}\"与指标重名",
+                    root_name
+                ))?;
+            }
+            for top in builder.tops.clone().iter() {
+                builder.add_edge(&root_name, top);
+            }
+            root_name
+        }
+    };
+    // 只有带权重的叶节点需要写入文件
+    let data = builder
+        .data
+        .into_iter()
+        .filter(|model| {
+            model.children.is_some() || !model.extra.is_empty() || model.name == root_name
+        })
+        .map(|mut model| {
+            if model.name == root_name {
+                model.children.get_or_insert_with(Vec::new);
+            }
+            model
+        })
+        .collect();
+    Ok(FileTreeModel {
+        header: Default::default(),
+        root_name,
+        data,
+        extra: Map::new(),
+    })
+}
+
+fn write_rows(
+    name: &str,
+    path: &mut Vec<String>,
+    definitions: &HashMap<&str, &FileModel>,
+    visited: &mut HashSet<String>,
+    rows: &mut Vec<(Vec<String>, String, String)>,
+) {
+    path.push(name.to_string());
+    let model = definitions.get(name);
+    let first_visit = visited.insert(name.to_string());
+    // 共享节点只在第一次出现时写出算法和下级指标
+    let algorithm = model
+        .filter(|model| first_visit && model.children.is_some())
+        .and_then(|model| model.algorithm.clone())
+        .unwrap_or_default();
+    let weight = model
+        .and_then(|model| model.extra.get(WEIGHT_FIELD))
+        .map(|weight| match weight {
+            Value::String(weight) => weight.clone(),
+            weight => weight.to_string(),
+        })
+        .unwrap_or_default();
+    rows.push((path.clone(), algorithm, weight));
+    if first_visit {
+        if let Some(children) = model.and_then(|model| model.children.as_ref()) {
+            for child in children.iter() {
+                write_rows(child, path, definitions, visited, rows);
+            }
+        }
+    }
+    path.pop();
+}
+
+// 每个节点一行，上级指标全部填写；从根节点无法到达的节点无法用层级表示，不会导出
+pub fn serialize_levels(file_tree_model: &FileTreeModel) -> Result<String, String> {
+    let definitions = file_tree_model
+        .data
+        .iter()
+        .map(|model| (model.name.as_str(), model))
+        .collect::<HashMap<&str, &FileModel>>();
+    let mut rows = Vec::new();
+    write_rows(
+        &file_tree_model.root_name,
+        &mut Vec::new(),
+        &definitions,
+        &mut HashSet::new(),
+        &mut rows,
+    );
+    let depth = rows
+        .iter()
+        .map(|(path, _, _)| path.len())
+        .max()
+        .unwrap_or(1);
+    let with_weight = rows.iter().any(|(_, _, weight)| !weight.is_empty());
+    let mut writer = csv::Writer::from_writer(vec![]);
+    let mut headers = (1..=depth).map(level_header).collect::<Vec<String>>();
+    headers.push("算法".to_string());
+    if with_weight {
+        headers.push("权重".to_string());
+    }
+    writer
+        .write_record(&headers)
+        .map_err(|e| format!("写入CSV错误\n{}", e))?;
+    for (path, algorithm, weight) in rows.into_iter() {
+        let mut record = path;
+        record.resize(depth, String::new());
+        record.push(algorithm);
+        if with_weight {
+            record.push(weight);
+        }
+        writer
+            .write_record(&record)
+            .map_err(|e| format!("写入CSV错误\n{}", e))?;
+    }
+    let content = writer
+        .into_inner()
+        .map_err(|e| format!("写入CSV错误\n{}", e))?;
+    // 带BOM的UTF-8，Excel打开时中文不会乱码
+    let mut bytes = "\u{feff}".as_bytes().to_vec();
+    bytes.extend(content);
+    String::from_utf8(bytes).map_err(|e| format!("写入CSV错误\n{}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::tests::{sample_model, structure};
+
+    fn children<'a>(file_tree_model: &'a FileTreeModel, name: &str) -> Vec<&'a str> {
+        file_tree_model
+            .data
+            .iter()
+            .find(|model| model.name == name)
+            .and_then(|model| model.children.as_ref())
+            .map(|children| children.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn blank_cells_inherit_from_previous_row() {
+        let content = "一级指标,二级指标,三级指标,算法\n总分,,,取最小值\n,A,,取最大值\n,,A1,\n,,A2,\n,B,,取最大值\n,,B1,\n";
+        let file_tree_model = parse_levels(content, "模型").unwrap();
+        assert_eq!(file_tree_model.root_name, "总分");
+        assert_eq!(children(&file_tree_model, "总分"), ["A", "B"]);
+        assert_eq!(children(&file_tree_model, "A"), ["A1", "A2"]);
+        assert_eq!(children(&file_tree_model, "B"), ["B1"]);
+    }
+
+    // 第三行的C左侧是新的A2，不能继承上一行属于A1的B1
+    #[test]
+    fn blank_after_changed_column_is_rejected() {
+        let content = "一级指标,二级指标,三级指标\nA1,B1,\nA2,,C\n";
+        assert!(parse_levels(content, "模型").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let content = "一级指标,二级指标,三级指标,算法,权重\n总分,,,取最小值,\n,A,,取最大值,0.6\n,,S,,\n,B,,取最大值,0.4\n,,S,,\n";
+        let parsed = parse_levels(content, "模型").unwrap();
+        let reparsed = parse_levels(&serialize_levels(&parsed).unwrap(), "模型").unwrap();
+        assert_eq!(reparsed.root_name, parsed.root_name);
+        for name in ["总分", "A", "B"] {
+            assert_eq!(children(&reparsed, name), children(&parsed, name));
+        }
+        let weight = |file_tree_model: &FileTreeModel| {
+            file_tree_model
+                .data
+                .iter()
+                .find(|model| model.name == "A")
+                .and_then(|model| model.extra.get(WEIGHT_FIELD).cloned())
+        };
+        assert_eq!(weight(&reparsed), weight(&parsed));
+    }
+
+    // 每行一条路径，名称的首尾空白和重复引用的子节点无法保留
+    fn normalized(mut file_tree_model: FileTreeModel) -> FileTreeModel {
+        for model in file_tree_model.data.iter_mut() {
+            model.name = model.name.trim().to_string();
+            if let Some(children) = model.children.as_mut() {
+                let mut seen = HashSet::new();
+                children.retain(|child| seen.insert(child.trim().to_string()));
+                for child in children.iter_mut() {
+                    *child = child.trim().to_string();
+                }
+            }
+        }
+        file_tree_model
+    }
+
+    #[test]
+    fn sample_model_round_trip() {
+        let model = normalized(sample_model());
+        let reparsed = parse_levels(&serialize_levels(&model).unwrap(), "模型").unwrap();
+        assert_eq!(reparsed.root_name, model.root_name);
+        assert_eq!(structure(&reparsed), structure(&model));
+    }
+}
@@ -0,0 +1,386 @@
+use crate::exchange::nested::{import_nested, NestedModel, NestedTreeModel};
+use crate::exchange::outline::{attach, new_model, wrap_roots};
+use crate::models::FileTreeModel;
+use serde::Deserialize;
+use shared::{Algorithm, ModelHeader};
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+// 思维导图：XMind（zip包，新版为content.json，XMind 8为content.xml）和FreeMind的.mm文件
+// 主题成为节点，主题的标签、图标或标题末尾的“[求和]”设置算法，备注成为节点描述
+// 浮动主题不属于层级结构，导入时忽略
+pub const MIND_MAP_EXTENSIONS: [&str; 2] = ["xmind", "mm"];
+
+// 图标到算法的对应关系，其他算法请使用标签
+const MARKER_ALGORITHMS: [(&str, &str); 5] = [
+    // XMind
+    ("symbol-plus", "求和"),
+    ("arrow-up", "取最大值"),
+    ("arrow-down", "取最小值"),
+    // FreeMind
+    ("up", "取最大值"),
+    ("down", "取最小值"),
+];
+
+#[derive(Deserialize)]
+struct XmindSheet {
+    #[serde(default)]
+    title: String,
+    #[serde(rename = "rootTopic")]
+    root_topic: XmindTopic,
+}
+
+#[derive(Deserialize)]
+struct XmindTopic {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    children: Option<XmindChildren>,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    markers: Vec<XmindMarker>,
+    #[serde(default)]
+    notes: Option<XmindNotes>,
+}
+
+#[derive(Deserialize)]
+struct XmindChildren {
+    #[serde(default)]
+    attached: Vec<XmindTopic>,
+}
+
+#[derive(Deserialize)]
+struct XmindMarker {
+    #[serde(rename = "markerId")]
+    marker_id: String,
+}
+
+#[derive(Deserialize)]
+struct XmindNotes {
+    plain: Option<XmindPlain>,
+}
+
+#[derive(Deserialize)]
+struct XmindPlain {
+    content: String,
+}
+
+// 标签可以直接是算法名称，也可以写作“算法：待定”
+fn algorithm_from_label(label: &str) -> Option<String> {
+    let label = label.trim();
+    if let Some(raw) = label
+        .strip_prefix("算法：")
+        .or_else(|| label.strip_prefix("算法:"))
+    {
+        return Some(raw.trim().to_string());
+    }
+    match label.parse::<Algorithm>() {
+        Ok(Algorithm::None) | Err(_) => None,
+        Ok(_) => Some(label.to_string()),
+    }
+}
+
+fn algorithm_from_marker(marker: &str) -> Option<String> {
+    MARKER_ALGORITHMS
+        .iter()
+        .find(|(id, _)| *id == marker)
+        .map(|(_, algorithm)| algorithm.to_string())
+}
+
+// 多行主题合并为一行作为节点名称
+fn topic_model(title: &str) -> NestedModel {
+    let title = title
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ");
+    new_model(&title)
+}
+
+fn set_algorithm(model: &mut NestedModel, algorithm: Option<String>) {
+    if let Some(algorithm) = algorithm {
+        model.algorithm = Some(algorithm);
+        model.children.get_or_insert_with(Vec::new);
+    }
+}
+
+fn set_note(model: &mut NestedModel, note: &str) {
+    let note = note.trim();
+    if !note.is_empty() {
+        model.metadata.description = note.to_string();
+    }
+}
+
+fn convert_xmind_topic(topic: &XmindTopic) -> NestedModel {
+    let mut model = topic_model(&topic.title);
+    let algorithm = topic
+        .labels
+        .iter()
+        .find_map(|label| algorithm_from_label(label))
+        .or_else(|| {
+            topic
+                .markers
+                .iter()
+                .find_map(|marker| algorithm_from_marker(&marker.marker_id))
+        });
+    set_algorithm(&mut model, algorithm);
+    if let Some(plain) = topic.notes.as_ref().and_then(|notes| notes.plain.as_ref()) {
+        set_note(&mut model, &plain.content);
+    }
+    for child in topic
+        .children
+        .iter()
+        .flat_map(|children| children.attached.iter())
+    {
+        attach(&mut model, convert_xmind_topic(child));
+    }
+    model
+}
+
+fn child_elements<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    tag_name: &'a str,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.has_tag_name(tag_name))
+}
+
+fn child_text(node: roxmltree::Node, tag_name: &str) -> String {
+    child_elements(node, tag_name)
+        .next()
+        .map(|child| {
+            child
+                .descendants()
+                .filter(|descendant| descendant.is_text())
+                .filter_map(|descendant| descendant.text())
+                .collect::<String>()
+        })
+        .unwrap_or_default()
+}
+
+// XMind 8的content.xml
+fn convert_xmind_xml_topic(topic: roxmltree::Node) -> NestedModel {
+    let mut model = topic_model(&child_text(topic, "title"));
+    let labels = child_elements(topic, "labels").flat_map(|labels| child_elements(labels, "label"));
+    let markers = child_elements(topic, "marker-refs")
+        .flat_map(|markers| child_elements(markers, "marker-ref"));
+    let algorithm = labels
+        .filter_map(|label| label.text())
+        .find_map(algorithm_from_label)
+        .or_else(|| {
+            markers
+                .filter_map(|marker| marker.attribute("marker-id"))
+                .find_map(algorithm_from_marker)
+        });
+    set_algorithm(&mut model, algorithm);
+    if let Some(notes) = child_elements(topic, "notes").next() {
+        set_note(&mut model, &child_text(notes, "plain"));
+    }
+    let children = child_elements(topic, "children")
+        .flat_map(|children| child_elements(children, "topics"))
+        .filter(|topics| topics.attribute("type") == Some("attached"))
+        .flat_map(|topics| child_elements(topics, "topic"));
+    for child in children {
+        attach(&mut model, convert_xmind_xml_topic(child));
+    }
+    model
+}
+
+fn read_zip_entry(archive: &mut zip::ZipArchive<Cursor<&[u8]>>, name: &str) -> Option<String> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut content = String::new();
+    file.read_to_string(&mut content).ok()?;
+    Some(content)
+}
+
+// 只导入第一张画布
+fn parse_xmind(content: &[u8], default_root_name: &str) -> Result<NestedTreeModel, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(content))
+        .map_err(|e| format!("解析XMind文件错误\n{}", e))?;
+    let mut header = ModelHeader::default();
+    let root = if let Some(content) = read_zip_entry(&mut archive, "content.json") {
+        let sheets = serde_json::from_str::<Vec<XmindSheet>>(&content)
+            .map_err(|e| format!("解析XMind文件中的content.json错误\n{}", e))?;
+        let sheet = sheets
+            .first()
+            .ok_or("导入错误：XMind文件中没有画布".to_string())?;
+        header.title = sheet.title.trim().to_string();
+        convert_xmind_topic(&sheet.root_topic)
+    } else if let Some(content) = read_zip_entry(&mut archive, "content.xml") {
+        let document = roxmltree::Document::parse(&content)
+            .map_err(|e| format!("解析XMind文件中的content.xml错误\n{}", e))?;
+        let sheet = child_elements(document.root_element(), "sheet")
+            .next()
+            .ok_or("导入错误：XMind文件中没有画布".to_string())?;
+        header.title = child_text(sheet, "title").trim().to_string();
+        let topic = child_elements(sheet, "topic")
+            .next()
+            .ok_or("导入错误：XMind画布中没有中心主题".to_string())?;
+        convert_xmind_xml_topic(topic)
+    } else {
+        Err("导入错误：XMind文件中没有content.json或content.xml".to_string())?
+    };
+    wrap_roots(vec![root], default_root_name, header)
+}
+
+fn convert_freemind_node(node: roxmltree::Node) -> NestedModel {
+    let text = match node.attribute("TEXT") {
+        Some(text) => text.to_string(),
+        // 富文本节点的内容在richcontent TYPE="NODE"中
+        None => child_elements(node, "richcontent")
+            .find(|content| content.attribute("TYPE") == Some("NODE"))
+            .map(|content| {
+                content
+                    .descendants()
+                    .filter(|descendant| descendant.is_text())
+                    .filter_map(|descendant| descendant.text())
+                    .collect::<String>()
+            })
+            .unwrap_or_default(),
+    };
+    let mut model = topic_model(&text);
+    // 节点属性“算法”优先于图标
+    let algorithm = child_elements(node, "attribute")
+        .find(|attribute| {
+            matches!(
+                attribute.attribute("NAME"),
+                Some("算法") | Some("algorithm")
+            )
+        })
+        .and_then(|attribute| attribute.attribute("VALUE"))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .or_else(|| {
+            child_elements(node, "icon")
+                .filter_map(|icon| icon.attribute("BUILTIN"))
+                .find_map(algorithm_from_marker)
+        });
+    set_algorithm(&mut model, algorithm);
+    if let Some(note) = child_elements(node, "richcontent")
+        .find(|content| content.attribute("TYPE") == Some("NOTE"))
+    {
+        let text = note
+            .descendants()
+            .filter(|descendant| descendant.is_text())
+            .filter_map(|descendant| descendant.text())
+            .map(|text| text.trim())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<&str>>()
+            .join("\n");
+        set_note(&mut model, &text);
+    }
+    for child in child_elements(node, "node") {
+        attach(&mut model, convert_freemind_node(child));
+    }
+    model
+}
+
+fn parse_freemind(content: &[u8], default_root_name: &str) -> Result<NestedTreeModel, String> {
+    let content = std::str::from_utf8(content).map_err(|_| "文件不是UTF-8编码".to_string())?;
+    let document =
+        roxmltree::Document::parse(content).map_err(|e| format!("解析FreeMind文件错误\n{}", e))?;
+    let root = child_elements(document.root_element(), "node")
+        .next()
+        .ok_or("导入错误：FreeMind文件中没有根节点".to_string())?;
+    wrap_roots(
+        vec![convert_freemind_node(root)],
+        default_root_name,
+        ModelHeader::default(),
+    )
+}
+
+// 按扩展名区分XMind和FreeMind
+pub fn parse_mind_map(
+    file_path: &str,
+    content: &[u8],
+    default_root_name: &str,
+) -> Result<FileTreeModel, String> {
+    let extension = Path::new(file_path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    let nested = match extension.as_deref() {
+        Some("xmind") => parse_xmind(content, default_root_name)?,
+        Some("mm") => parse_freemind(content, default_root_name)?,
+        _ => Err("导入错误：只支持.xmind和.mm文件".to_string())?,
+    };
+    import_nested(nested)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::tests::structure;
+    use std::io::Write;
+
+    fn description(file_tree_model: &FileTreeModel, name: &str) -> String {
+        file_tree_model
+            .data
+            .iter()
+            .find(|model| model.name == name)
+            .map(|model| model.metadata.description.clone())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn freemind_attributes_icons_and_notes() {
+        let content = r#"<map version="1.0.1">
+            <node TEXT="Root">
+                <attribute NAME="算法" VALUE="求和"/>
+                <node TEXT="A">
+                    <icon BUILTIN="up"/>
+                    <node TEXT="x"/>
+                </node>
+                <node TEXT="B">
+                    <richcontent TYPE="NOTE"><html><body><p>说明</p></body></html></richcontent>
+                    <node TEXT="x"/>
+                    <node TEXT="y [取最小值]"/>
+                </node>
+            </node>
+        </map>"#;
+        let model = parse_mind_map("test.mm", content.as_bytes(), "根").unwrap();
+        let structure = structure(&model);
+        assert_eq!(model.root_name, "Root");
+        assert_eq!(
+            structure["Root"],
+            (vec!["A".into(), "B".into()], Some("求和".into()))
+        );
+        assert_eq!(structure["A"], (vec!["x".into()], Some("取最大值".into())));
+        assert_eq!(structure["B"], (vec!["x".into(), "y".into()], None));
+        assert_eq!(structure["y"], (vec![], Some("取最小值".into())));
+        assert_eq!(description(&model, "B"), "说明");
+    }
+
+    #[test]
+    fn xmind_content_json() {
+        let content = r#"[{
+            "title": "画布",
+            "rootTopic": {"title": "Root", "labels": ["算法：待定"], "children": {"attached": [
+                {"title": "A", "markers": [{"markerId": "symbol-plus"}],
+                 "children": {"attached": [{"title": "x"}]}},
+                {"title": "B", "notes": {"plain": {"content": "说明"}}}
+            ]}}
+        }]"#;
+        let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        archive
+            .start_file("content.json", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        archive.write_all(content.as_bytes()).unwrap();
+        let bytes = archive.finish().unwrap().into_inner();
+        let model = parse_mind_map("test.xmind", &bytes, "根").unwrap();
+        let structure = structure(&model);
+        assert_eq!(model.header.title, "画布");
+        assert_eq!(
+            structure["Root"],
+            (vec!["A".into(), "B".into()], Some("待定".into()))
+        );
+        assert_eq!(structure["A"], (vec!["x".into()], Some("求和".into())));
+        assert_eq!(description(&model, "B"), "说明");
+    }
+
+    #[test]
+    fn unknown_extension_is_rejected() {
+        assert!(parse_mind_map("test.txt", b"", "根").is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use crate::models::{FileModel, FileTreeModel};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use shared::{ModelHeader, NodeMetadata};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// 嵌套的树状JSON：子节点直接嵌入父节点中
+// 被多个父节点共享的节点只在第一次出现时完整写出，之后用{"ref": 名称}引用
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum NestedNode {
+    Ref(NestedRef),
+    Node(Box<NestedModel>),
+}
+
+// 引用只能有ref一个字段，带有其他字段（如name）的对象按完整节点解析
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NestedRef {
+    #[serde(rename = "ref")]
+    pub reference: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NestedModel {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+    #[serde(default, skip_serializing_if = "NodeMetadata::is_empty")]
+    pub metadata: NodeMetadata,
+    // 叶节点没有children
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<NestedNode>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NestedTreeModel {
+    #[serde(default, skip_serializing_if = "ModelHeader::is_empty")]
+    pub header: ModelHeader,
+    pub root: NestedModel,
+    // 从根节点无法到达的节点
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub detached: Vec<NestedModel>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+fn export_node(
+    name: &str,
+    definitions: &HashMap<&str, &FileModel>,
+    visited: &mut HashSet<String>,
+) -> NestedNode {
+    if !visited.insert(name.to_string()) {
+        return NestedNode::Ref(NestedRef {
+            reference: name.to_string(),
+        });
+    }
+    let model = match definitions.get(name) {
+        Some(model) => model,
+        None => {
+            return NestedNode::Node(Box::new(NestedModel {
+                name: name.to_string(),
+                algorithm: None,
+                metadata: NodeMetadata::default(),
+                children: None,
+                extra: Map::new(),
+            }))
+        }
+    };
+    let children = model.children.as_ref().map(|children| {
+        children
+            .iter()
+            .map(|child| export_node(child, definitions, visited))
+            .collect()
+    });
+    NestedNode::Node(Box::new(NestedModel {
+        name: model.name.clone(),
+        algorithm: model.algorithm.clone(),
+        metadata: model.metadata.clone(),
+        children,
+        extra: model.extra.clone(),
+    }))
+}
+
+pub fn export_nested(file_tree_model: &FileTreeModel) -> Result<NestedTreeModel, String> {
+    let definitions = file_tree_model
+        .data
+        .iter()
+        .map(|model| (model.name.as_str(), model))
+        .collect::<HashMap<&str, &FileModel>>();
+    let mut visited = HashSet::<String>::new();
+    let root = match export_node(&file_tree_model.root_name, &definitions, &mut visited) {
+        NestedNode::Node(root) => *root,
+        NestedNode::Ref(_) => Err("导出错误：根节点重复".to_string())?,
+    };
+    let mut detached = Vec::new();
+    for model in file_tree_model.data.iter() {
+        if visited.contains(&model.name) {
+            continue;
+        }
+        if let NestedNode::Node(model) = export_node(&model.name, &definitions, &mut visited) {
+            detached.push(*model);
+        }
+    }
+    Ok(NestedTreeModel {
+        header: file_tree_model.header.clone(),
+        root,
+        detached,
+        extra: file_tree_model.extra.clone(),
+    })
+}
+
+struct Importer {
+    data: Vec<FileModel>,
+    // 名称到data中下标的映射，用于合并重复出现的同名节点
+    defined: HashMap<String, usize>,
+    // 出现过的所有节点名称，包括没有写入data的叶节点
+    names: HashSet<String>,
+    references: BTreeMap<String, String>,
+}
+
+impl Importer {
+    fn import_model(&mut self, model: &NestedModel) -> Result<(), String> {
+        if model.name.is_empty() {
+            Err("导入错误：节点名称为空".to_string())?;
+        }
+        self.names.insert(model.name.clone());
+        let children = match model.children.as_ref() {
+            Some(children) => {
+                let mut names = Vec::new();
+                for child in children.iter() {
+                    match child {
+                        NestedNode::Ref(NestedRef { reference }) => {
+                            self.references
+                                .insert(reference.clone(), model.name.clone());
+                            names.push(reference.clone());
+                        }
+                        NestedNode::Node(child) => {
+                            self.import_model(child)?;
+                            names.push(child.name.clone());
+                        }
+                    }
+                }
+                Some(names)
+            }
+            None => None,
+        };
+        let file_model = FileModel {
+            name: model.name.clone(),
+            children,
+            algorithm: model.algorithm.clone(),
+            metadata: model.metadata.clone(),
+            extra: model.extra.clone(),
+        };
+        let is_plain_leaf = file_model.children.is_none()
+            && file_model.metadata.is_empty()
+            && file_model.extra.is_empty();
+        match self.defined.get(&model.name) {
+            // 同名节点再次完整出现时视为同一个共享节点，内容必须一致
+            Some(index) => {
+                let existing = &mut self.data[*index];
+                if existing.children.is_none() && !is_plain_leaf {
+                    *existing = file_model;
+                } else if !is_plain_leaf
+                    && (existing.children != file_model.children
+                        || existing.algorithm != file_model.algorithm)
+                {
+                    Err(format!("导入错误：节点\"{}\"的多处定义不一致", model.name))?;
+                }
+            }
+            None if is_plain_leaf => {}
+            None => {
+                self.defined.insert(model.name.clone(), self.data.len());
+                self.data.push(file_model);
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn import_nested(nested: NestedTreeModel) -> Result<FileTreeModel, String> {
+    let mut importer = Importer {
+        data: Vec::new(),
+        defined: HashMap::new(),
+        names: HashSet::new(),
+        references: BTreeMap::new(),
+    };
+    importer.import_model(&nested.root)?;
+    for model in nested.detached.iter() {
+        importer.import_model(model)?;
+    }
+    for (reference, parent) in importer.references.iter() {
+        if !importer.names.contains(reference) {
+            Err(format!(
+                "导入错误：节点\"{}\"引用了不存在的节点\"{}\"",
+                parent, reference
+            ))?;
+        }
+    }
+    // 根节点即使没有子节点也必须有定义
+    if !importer.defined.contains_key(&nested.root.name) {
+        importer.data.insert(
+            0,
+            FileModel {
+                name: nested.root.name.clone(),
+                children: Some(vec![]),
+                algorithm: nested.root.algorithm.clone(),
+                metadata: nested.root.metadata.clone(),
+                extra: nested.root.extra.clone(),
+            },
+        );
+    }
+    Ok(FileTreeModel {
+        header: nested.header,
+        root_name: nested.root.name,
+        data: importer.data,
+        extra: nested.extra,
+    })
+}
+
+pub fn parse_nested(content: &str) -> Result<FileTreeModel, String> {
+    let nested = serde_json::from_str::<NestedTreeModel>(content)
+        .map_err(|e| format!("解析嵌套JSON错误\n{}", e))?;
+    import_nested(nested)
+}
+
+pub fn serialize_nested(file_tree_model: &FileTreeModel) -> Result<String, String> {
+    let nested = export_nested(file_tree_model)?;
+    serde_json::to_string_pretty(&nested).map_err(|e| format!("序列化嵌套JSON错误\n{}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::tests::{sample_model, structure};
+
+    #[test]
+    fn round_trip() {
+        let model = sample_model();
+        let content = serialize_nested(&model).unwrap();
+        let reparsed = parse_nested(&content).unwrap();
+        assert_eq!(reparsed.root_name, model.root_name);
+        assert_eq!(structure(&reparsed), structure(&model));
+    }
+
+    // 共享节点第二次出现时写成引用，说明信息只写一次
+    #[test]
+    fn shared_nodes_and_metadata() {
+        let content = r#"{
+            "root": {"name": "Root", "algorithm": "取最小值", "children": [
+                {"name": "A", "algorithm": "取最大值", "children": [
+                    {"name": "S", "metadata": {"unit": "分"}}
+                ]},
+                {"name": "B", "algorithm": "取最大值", "children": [{"ref": "S"}]}
+            ]}
+        }"#;
+        let model = parse_nested(content).unwrap();
+        let leaf = model.data.iter().find(|model| model.name == "S").unwrap();
+        assert_eq!(leaf.metadata.unit, "分");
+        let nested = serde_json::to_value(export_nested(&model).unwrap()).unwrap();
+        assert_eq!(nested["root"]["children"][1]["children"][0]["ref"], "S");
+    }
+
+    // 节点中不认识的ref字段作为额外字段保留，不会把节点当作引用而丢掉子树
+    #[test]
+    fn node_with_ref_field_keeps_subtree() {
+        let content = r#"{
+            "root": {"name": "Root", "algorithm": "求和", "children": [
+                {"name": "A", "ref": "文献1", "algorithm": "求和", "children": [
+                    {"name": "a"}
+                ]}
+            ]}
+        }"#;
+        let model = parse_nested(content).unwrap();
+        let a = model.data.iter().find(|model| model.name == "A").unwrap();
+        assert_eq!(a.children, Some(vec!["a".to_string()]));
+        assert_eq!(a.extra["ref"], "文献1");
+        let nested = serde_json::to_value(export_nested(&model).unwrap()).unwrap();
+        assert_eq!(nested["root"]["children"][0]["ref"], "文献1");
+        assert_eq!(nested["root"]["children"][0]["children"][0]["name"], "a");
+    }
+
+    #[test]
+    fn dangling_reference_is_rejected() {
+        let content = r#"{"root": {"name": "Root", "children": [{"ref": "X"}]}}"#;
+        assert!(parse_nested(content).is_err());
+    }
+}
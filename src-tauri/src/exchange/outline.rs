@@ -0,0 +1,461 @@
+use crate::exchange::nested::{
+    export_nested, import_nested, NestedModel, NestedNode, NestedRef, NestedTreeModel,
+};
+use crate::models::FileTreeModel;
+use serde_json::Map;
+use shared::{Algorithm, ModelHeader, NodeMetadata};
+use std::path::Path;
+
+// 大纲格式：Markdown列表、缩进文本和OPML
+// 条目末尾可以用“[求和]”或“【求和】”标注算法，无法识别的算法写作“[算法：待定]”
+// 同名条目视为同一个共享节点，共享节点的子节点只需在一处写出
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutlineFormat {
+    Markdown,
+    Indented,
+    Opml,
+}
+
+pub const OUTLINE_EXTENSIONS: [&str; 5] = ["md", "markdown", "txt", "opml", "xml"];
+
+// 缩进中的制表符按4个空格计算
+const TAB_WIDTH: usize = 4;
+
+impl OutlineFormat {
+    pub fn from_path(file_path: &str) -> OutlineFormat {
+        let extension = Path::new(file_path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("md") | Some("markdown") => OutlineFormat::Markdown,
+            Some("opml") | Some("xml") => OutlineFormat::Opml,
+            _ => OutlineFormat::Indented,
+        }
+    }
+
+    // 大纲有多个顶层条目时，用default_root_name作为根节点把它们包起来
+    pub fn parse(&self, content: &str, default_root_name: &str) -> Result<FileTreeModel, String> {
+        let nested = match self {
+            OutlineFormat::Markdown => items_to_nested(parse_markdown(content), default_root_name)?,
+            OutlineFormat::Indented => items_to_nested(parse_indented(content), default_root_name)?,
+            OutlineFormat::Opml => parse_opml(content, default_root_name)?,
+        };
+        import_nested(nested)
+    }
+
+    pub fn serialize(
+        &self,
+        file_tree_model: &FileTreeModel,
+        with_algorithm: bool,
+    ) -> Result<String, String> {
+        let nested = export_nested(file_tree_model)?;
+        let mut content = String::new();
+        match self {
+            OutlineFormat::Markdown | OutlineFormat::Indented => {
+                write_lines(&mut content, *self, &nested.root, 0, with_algorithm);
+                for model in nested.detached.iter() {
+                    write_lines(&mut content, *self, model, 0, with_algorithm);
+                }
+            }
+            OutlineFormat::Opml => {
+                content.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                content.push_str("<opml version=\"2.0\">\n  <head>\n");
+                content.push_str(&format!(
+                    "    <title>{}</title>\n",
+                    escape_xml(&nested.header.title)
+                ));
+                content.push_str("  </head>\n  <body>\n");
+                write_opml(&mut content, &nested.root, 2, with_algorithm);
+                for model in nested.detached.iter() {
+                    write_opml(&mut content, model, 2, with_algorithm);
+                }
+                content.push_str("  </body>\n</opml>\n");
+            }
+        }
+        Ok(content)
+    }
+}
+
+struct OutlineItem {
+    depth: usize,
+    text: String,
+}
+
+// 拆分条目末尾的算法标注，返回名称和算法
+pub fn split_annotation(text: &str) -> (String, Option<String>) {
+    let text = text.trim();
+    for (open, close) in [('[', ']'), ('【', '】')] {
+        if !text.ends_with(close) {
+            continue;
+        }
+        let start = match text.rfind(open) {
+            Some(start) => start,
+            None => continue,
+        };
+        let annotation = text[start + open.len_utf8()..text.len() - close.len_utf8()].trim();
+        let name = text[..start].trim();
+        if name.is_empty() {
+            continue;
+        }
+        if let Some(raw) = annotation
+            .strip_prefix("算法：")
+            .or_else(|| annotation.strip_prefix("算法:"))
+        {
+            return (name.to_string(), Some(raw.trim().to_string()));
+        }
+        if let Ok(algorithm) = annotation.parse::<Algorithm>() {
+            if !matches!(algorithm, Algorithm::None) {
+                return (name.to_string(), Some(annotation.to_string()));
+            }
+        }
+    }
+    (text.to_string(), None)
+}
+
+fn annotation(algorithm: &Option<String>) -> String {
+    match algorithm.as_deref() {
+        None | Some("") => String::new(),
+        Some(algorithm) => match algorithm.parse::<Algorithm>() {
+            Ok(Algorithm::None) | Err(_) => format!(" [算法：{}]", algorithm),
+            Ok(_) => format!(" [{}]", algorithm),
+        },
+    }
+}
+
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+// 根据缩进宽度计算层级，缩进回退到之前没有出现过的宽度时按最接近的较浅层级处理
+fn indent_depth(indents: &mut Vec<usize>, width: usize) -> usize {
+    while let Some(&top) = indents.last() {
+        if top > width {
+            indents.pop();
+        } else {
+            break;
+        }
+    }
+    if indents.last() != Some(&width) {
+        indents.push(width);
+    }
+    indents.len() - 1
+}
+
+fn parse_indented(content: &str) -> Vec<OutlineItem> {
+    let mut indents = Vec::new();
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| OutlineItem {
+            depth: indent_depth(&mut indents, indent_width(line)),
+            text: line.trim().to_string(),
+        })
+        .collect()
+}
+
+// 去掉列表标记“-”、“*”、“+”或“1.”、“1)”，不是列表项时返回None
+fn strip_list_marker(text: &str) -> Option<&str> {
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(rest) = text.strip_prefix(marker) {
+            return Some(rest);
+        }
+    }
+    let digits = text.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &text[digits..];
+        return rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") "));
+    }
+    None
+}
+
+// 标题“#”也作为层级，标题下的列表从标题的下一层开始；其他段落文字忽略
+fn parse_markdown(content: &str) -> Vec<OutlineItem> {
+    let mut items = Vec::new();
+    let mut indents = Vec::new();
+    let mut base_depth = 0;
+    let mut last_heading_depth: Option<usize> = None;
+    for line in content.lines() {
+        let text = line.trim();
+        if text.starts_with('#') {
+            let level = text.chars().take_while(|c| *c == '#').count();
+            let heading = text[level..].trim();
+            if heading.is_empty() {
+                continue;
+            }
+            // 标题层级不能跳级，例如“#”之后直接出现“###”按“##”处理
+            let depth = match last_heading_depth {
+                Some(last) => (level - 1).min(last + 1),
+                None => 0,
+            };
+            last_heading_depth = Some(depth);
+            base_depth = depth + 1;
+            indents.clear();
+            items.push(OutlineItem {
+                depth,
+                text: heading.to_string(),
+            });
+            continue;
+        }
+        if let Some(rest) = strip_list_marker(text) {
+            // 任务列表的复选框不属于名称
+            let rest = ["[ ] ", "[x] ", "[X] "]
+                .iter()
+                .find_map(|checkbox| rest.strip_prefix(checkbox))
+                .unwrap_or(rest);
+            if rest.trim().is_empty() {
+                continue;
+            }
+            items.push(OutlineItem {
+                depth: base_depth + indent_depth(&mut indents, indent_width(line)),
+                text: rest.trim().to_string(),
+            });
+        }
+    }
+    items
+}
+
+// 标注了算法的条目即使没有子条目也是非叶节点
+pub fn new_model(text: &str) -> NestedModel {
+    let (name, algorithm) = split_annotation(text);
+    let children = algorithm.as_ref().map(|_| vec![]);
+    NestedModel {
+        name,
+        algorithm,
+        metadata: NodeMetadata::default(),
+        children,
+        extra: Map::new(),
+    }
+}
+
+pub fn attach(parent: &mut NestedModel, child: NestedModel) {
+    parent
+        .children
+        .get_or_insert_with(Vec::new)
+        .push(NestedNode::Node(Box::new(child)));
+}
+
+pub fn wrap_roots(
+    mut roots: Vec<NestedModel>,
+    default_root_name: &str,
+    header: ModelHeader,
+) -> Result<NestedTreeModel, String> {
+    let root = match roots.len() {
+        0 => Err("导入错误：大纲中没有条目".to_string())?,
+        1 => roots.pop().unwrap(),
+        _ => NestedModel {
+            name: default_root_name.to_string(),
+            algorithm: None,
+            metadata: NodeMetadata::default(),
+            children: Some(
+                roots
+                    .into_iter()
+                    .map(|root| NestedNode::Node(Box::new(root)))
+                    .collect(),
+            ),
+            extra: Map::new(),
+        },
+    };
+    Ok(NestedTreeModel {
+        header,
+        root,
+        detached: vec![],
+        extra: Map::new(),
+    })
+}
+
+fn items_to_nested(
+    items: Vec<OutlineItem>,
+    default_root_name: &str,
+) -> Result<NestedTreeModel, String> {
+    let mut roots = Vec::new();
+    // 当前条目的所有祖先，stack[i]的层级为i
+    let mut stack: Vec<NestedModel> = Vec::new();
+    for item in items.into_iter() {
+        let depth = item.depth.min(stack.len());
+        while stack.len() > depth {
+            let child = stack.pop().unwrap();
+            match stack.last_mut() {
+                Some(parent) => attach(parent, child),
+                None => roots.push(child),
+            }
+        }
+        stack.push(new_model(&item.text));
+    }
+    while let Some(child) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => attach(parent, child),
+            None => roots.push(child),
+        }
+    }
+    wrap_roots(roots, default_root_name, ModelHeader::default())
+}
+
+fn parse_opml_outline(node: roxmltree::Node) -> Result<NestedModel, String> {
+    let text = node
+        .attribute("text")
+        .or_else(|| node.attribute("title"))
+        .ok_or("导入错误：OPML中的outline缺少text属性".to_string())?;
+    let mut model = new_model(text);
+    if let Some(algorithm) = node.attribute("_algorithm") {
+        model.algorithm = Some(algorithm.to_string());
+        model.children.get_or_insert_with(Vec::new);
+    }
+    if let Some(note) = node.attribute("_note") {
+        model.metadata.description = note.to_string();
+    }
+    for child in node
+        .children()
+        .filter(|child| child.has_tag_name("outline"))
+    {
+        attach(&mut model, parse_opml_outline(child)?);
+    }
+    Ok(model)
+}
+
+fn parse_opml(content: &str, default_root_name: &str) -> Result<NestedTreeModel, String> {
+    let document =
+        roxmltree::Document::parse(content).map_err(|e| format!("解析OPML错误\n{}", e))?;
+    let opml = document.root_element();
+    let body = opml
+        .children()
+        .find(|node| node.has_tag_name("body"))
+        .ok_or("导入错误：OPML中没有body".to_string())?;
+    let mut header = ModelHeader::default();
+    if let Some(title) = opml
+        .children()
+        .find(|node| node.has_tag_name("head"))
+        .and_then(|head| head.children().find(|node| node.has_tag_name("title")))
+        .and_then(|title| title.text())
+    {
+        header.title = title.trim().to_string();
+    }
+    let roots = body
+        .children()
+        .filter(|node| node.has_tag_name("outline"))
+        .map(parse_opml_outline)
+        .collect::<Result<Vec<NestedModel>, String>>()?;
+    wrap_roots(roots, default_root_name, header)
+}
+
+fn push_line(content: &mut String, format: OutlineFormat, depth: usize, text: &str) {
+    match format {
+        OutlineFormat::Markdown => content.push_str(&format!("{}- {}\n", "  ".repeat(depth), text)),
+        _ => content.push_str(&format!("{}{}\n", "\t".repeat(depth), text)),
+    }
+}
+
+fn write_lines(
+    content: &mut String,
+    format: OutlineFormat,
+    model: &NestedModel,
+    depth: usize,
+    with_algorithm: bool,
+) {
+    if with_algorithm && model.children.is_some() {
+        let text = format!("{}{}", model.name, annotation(&model.algorithm));
+        push_line(content, format, depth, &text);
+    } else {
+        push_line(content, format, depth, &model.name);
+    }
+    for child in model.children.iter().flatten() {
+        match child {
+            NestedNode::Node(child) => {
+                write_lines(content, format, child, depth + 1, with_algorithm)
+            }
+            // 共享节点只写名称，导入时同名条目会合并为同一个节点
+            NestedNode::Ref(NestedRef { reference }) => {
+                push_line(content, format, depth + 1, reference)
+            }
+        }
+    }
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#10;")
+}
+
+fn write_opml(content: &mut String, model: &NestedModel, depth: usize, with_algorithm: bool) {
+    let indent = "  ".repeat(depth);
+    let mut attributes = format!("text=\"{}\"", escape_xml(&model.name));
+    if with_algorithm && model.children.is_some() {
+        if let Some(algorithm) = model.algorithm.as_ref().filter(|a| !a.is_empty()) {
+            attributes.push_str(&format!(" _algorithm=\"{}\"", escape_xml(algorithm)));
+        }
+    }
+    if !model.metadata.description.is_empty() {
+        attributes.push_str(&format!(
+            " _note=\"{}\"",
+            escape_xml(&model.metadata.description)
+        ));
+    }
+    let children = model.children.as_deref().unwrap_or_default();
+    if children.is_empty() {
+        content.push_str(&format!("{}<outline {}/>\n", indent, attributes));
+        return;
+    }
+    content.push_str(&format!("{}<outline {}>\n", indent, attributes));
+    for child in children.iter() {
+        match child {
+            NestedNode::Node(child) => write_opml(content, child, depth + 1, with_algorithm),
+            NestedNode::Ref(NestedRef { reference }) => content.push_str(&format!(
+                "{}  <outline text=\"{}\"/>\n",
+                indent,
+                escape_xml(reference)
+            )),
+        }
+    }
+    content.push_str(&format!("{}</outline>\n", indent));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::tests::{sample_model, structure};
+
+    // 大纲的每行都会去掉首尾空白，名称中的首尾空白无法保留
+    fn trimmed(mut file_tree_model: FileTreeModel) -> FileTreeModel {
+        for model in file_tree_model.data.iter_mut() {
+            model.name = model.name.trim().to_string();
+            for child in model.children.iter_mut().flatten() {
+                *child = child.trim().to_string();
+            }
+        }
+        file_tree_model
+    }
+
+    #[test]
+    fn round_trip() {
+        let model = trimmed(sample_model());
+        for format in [
+            OutlineFormat::Markdown,
+            OutlineFormat::Indented,
+            OutlineFormat::Opml,
+        ] {
+            let content = format.serialize(&model, true).unwrap();
+            let reparsed = format.parse(&content, "根").unwrap();
+            assert_eq!(reparsed.root_name, model.root_name, "{:?}", format);
+            assert_eq!(structure(&reparsed), structure(&model), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn annotations_and_multiple_roots() {
+        let content = "- A【求和】\n  - x\n  - y\n- B [算法：待定]\n  - x\n";
+        let model = OutlineFormat::Markdown.parse(content, "根").unwrap();
+        let structure = structure(&model);
+        assert_eq!(model.root_name, "根");
+        assert_eq!(structure["根"].0, ["A", "B"]);
+        assert_eq!(
+            structure["A"],
+            (vec!["x".into(), "y".into()], Some("求和".into()))
+        );
+        assert_eq!(structure["B"], (vec!["x".into()], Some("待定".into())));
+    }
+}
@@ -0,0 +1,338 @@
+use crate::exchange::graph::algorithm_text;
+use crate::exchange::levels::WEIGHT_FIELD;
+use crate::models::TreeModel;
+use rust_xlsxwriter::{Format, Formula, Workbook, Worksheet, XlsxError};
+use serde_json::Value;
+use shared::Algorithm;
+use std::collections::{HashMap, HashSet};
+
+// 导出带公式的Excel工作簿：“输入”表为叶节点的值，“汇总”表为非叶节点，
+// 其公式引用子节点所在的单元格，与Algorithm::calculate的结果一致，修改输入后Excel会重新计算
+const INPUT_SHEET: &str = "输入";
+const AGGREGATE_SHEET: &str = "汇总";
+
+// Excel函数最多255个参数，超过时分组嵌套
+const MAX_ARGUMENTS: usize = 255;
+
+// 输入表的列：名称、值、单位、说明、权重
+const INPUT_VALUE_COLUMN: u16 = 1;
+const INPUT_WEIGHT_COLUMN: u16 = 4;
+// 汇总表的列：名称、算法、值、权重、加权和、加权平均
+const AGGREGATE_VALUE_COLUMN: u16 = 2;
+const AGGREGATE_WEIGHT_COLUMN: u16 = 3;
+
+fn column_letter(column: u16) -> char {
+    (b'A' + column as u8) as char
+}
+
+fn cell_reference(sheet: &str, row: u32, column: u16) -> String {
+    format!("'{}'!${}${}", sheet, column_letter(column), row + 1)
+}
+
+fn excel_error(e: XlsxError) -> String {
+    format!("生成Excel文件错误\n{}", e)
+}
+
+// 节点在工作簿中的位置
+#[derive(Clone, Copy)]
+enum Cell {
+    Input(u32),
+    Aggregate(u32),
+}
+
+impl Cell {
+    fn value(&self) -> String {
+        match self {
+            Cell::Input(row) => cell_reference(INPUT_SHEET, *row, INPUT_VALUE_COLUMN),
+            Cell::Aggregate(row) => cell_reference(AGGREGATE_SHEET, *row, AGGREGATE_VALUE_COLUMN),
+        }
+    }
+
+    fn weight(&self) -> String {
+        match self {
+            Cell::Input(row) => cell_reference(INPUT_SHEET, *row, INPUT_WEIGHT_COLUMN),
+            Cell::Aggregate(row) => cell_reference(AGGREGATE_SHEET, *row, AGGREGATE_WEIGHT_COLUMN),
+        }
+    }
+}
+
+fn weight(tree_model: &TreeModel, id: u64) -> Option<f64> {
+    match tree_model.extras.node_fields.get(&id)?.get(WEIGHT_FIELD)? {
+        Value::Number(weight) => weight.as_f64(),
+        Value::String(weight) => weight.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn call(function: &str, arguments: &[String]) -> String {
+    if arguments.len() <= MAX_ARGUMENTS {
+        return format!("{}({})", function, arguments.join(","));
+    }
+    let groups = arguments
+        .chunks(MAX_ARGUMENTS)
+        .map(|chunk| call(function, chunk))
+        .collect::<Vec<String>>();
+    call(function, &groups)
+}
+
+// 与Algorithm::calculate保持一致：没有子节点时求和、平均、最大、最小为0，乘积为1；
+// 未设置算法时程序无法计算，公式返回#N/A
+fn aggregate_formula(algorithm: &Algorithm, references: &[String]) -> String {
+    if references.is_empty() {
+        return match algorithm {
+            Algorithm::None => "=NA()".to_string(),
+            Algorithm::Product => "=1".to_string(),
+            _ => "=0".to_string(),
+        };
+    }
+    match algorithm {
+        Algorithm::None => "=NA()".to_string(),
+        Algorithm::Sum => format!("={}", call("SUM", references)),
+        Algorithm::Product => format!("={}", call("PRODUCT", references)),
+        Algorithm::Average if references.len() <= MAX_ARGUMENTS => {
+            format!("={}", call("AVERAGE", references))
+        }
+        Algorithm::Average => format!("={}/{}", call("SUM", references), references.len()),
+        Algorithm::Max => format!("={}", call("MAX", references)),
+        Algorithm::Min => format!("={}", call("MIN", references)),
+    }
+}
+
+// 按树的先序遍历为每个节点分配一行，共享节点只占一行
+fn assign_cells(
+    tree_model: &TreeModel,
+    id: u64,
+    visited: &mut HashSet<u64>,
+    order: &mut Vec<u64>,
+    cells: &mut HashMap<u64, Cell>,
+    counts: &mut (u32, u32),
+) {
+    if !visited.insert(id) {
+        return;
+    }
+    let Some(model) = tree_model.models.get(&id) else {
+        return;
+    };
+    order.push(id);
+    match model.expand_info.as_ref() {
+        Some(expand_info) => {
+            counts.1 += 1;
+            cells.insert(id, Cell::Aggregate(counts.1));
+            for child in expand_info.children.iter() {
+                assign_cells(tree_model, *child, visited, order, cells, counts);
+            }
+        }
+        None => {
+            counts.0 += 1;
+            cells.insert(id, Cell::Input(counts.0));
+        }
+    }
+}
+
+fn write_headers(
+    worksheet: &mut Worksheet,
+    headers: &[&str],
+    bold: &Format,
+) -> Result<(), XlsxError> {
+    for (column, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(0, column as u16, *header, bold)?;
+    }
+    worksheet.set_freeze_panes(1, 0)?;
+    worksheet.set_column_width(0, 30)?;
+    Ok(())
+}
+
+// 导出从根节点可以到达的所有节点；未计算的叶节点值为0
+pub fn build_workbook(tree_model: &TreeModel) -> Result<Vec<u8>, String> {
+    let mut order = Vec::new();
+    let mut cells = HashMap::new();
+    assign_cells(
+        tree_model,
+        0,
+        &mut HashSet::new(),
+        &mut order,
+        &mut cells,
+        &mut (0, 0),
+    );
+    let with_weight = order.iter().any(|id| weight(tree_model, *id).is_some());
+
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    let inputs = workbook.add_worksheet();
+    inputs.set_name(INPUT_SHEET).map_err(excel_error)?;
+    let mut headers = vec!["名称", "值", "单位", "说明"];
+    if with_weight {
+        headers.push("权重");
+    }
+    write_headers(inputs, &headers, &bold).map_err(excel_error)?;
+    for id in order.iter() {
+        let (Some(Cell::Input(row)), Some(model)) = (cells.get(id), tree_model.models.get(id))
+        else {
+            continue;
+        };
+        inputs
+            .write_string(*row, 0, &model.name)
+            .map_err(excel_error)?;
+        inputs
+            .write_number(*row, INPUT_VALUE_COLUMN, model.value.unwrap_or(0.0))
+            .map_err(excel_error)?;
+        inputs
+            .write_string(*row, 2, &model.metadata.unit)
+            .map_err(excel_error)?;
+        inputs
+            .write_string(*row, 3, &model.metadata.description)
+            .map_err(excel_error)?;
+        if let Some(weight) = weight(tree_model, *id) {
+            inputs
+                .write_number(*row, INPUT_WEIGHT_COLUMN, weight)
+                .map_err(excel_error)?;
+        }
+    }
+
+    let aggregates = workbook.add_worksheet();
+    aggregates.set_name(AGGREGATE_SHEET).map_err(excel_error)?;
+    let mut headers = vec!["名称", "算法", "值"];
+    if with_weight {
+        headers.extend(["权重", "加权和（参考）", "加权平均（参考）"]);
+    }
+    write_headers(aggregates, &headers, &bold).map_err(excel_error)?;
+    for id in order.iter() {
+        let (Some(Cell::Aggregate(row)), Some(model)) = (cells.get(id), tree_model.models.get(id))
+        else {
+            continue;
+        };
+        let Some(expand_info) = model.expand_info.as_ref() else {
+            continue;
+        };
+        aggregates
+            .write_string(*row, 0, &model.name)
+            .map_err(excel_error)?;
+        aggregates
+            .write_string(
+                *row,
+                1,
+                algorithm_text(tree_model, *id, &expand_info.algorithm),
+            )
+            .map_err(excel_error)?;
+        let children = expand_info
+            .children
+            .iter()
+            .filter_map(|child| cells.get(child).map(|cell| (*child, *cell)))
+            .collect::<Vec<(u64, Cell)>>();
+        let references = children
+            .iter()
+            .map(|(_, cell)| cell.value())
+            .collect::<Vec<String>>();
+        let mut formula = Formula::new(aggregate_formula(&expand_info.algorithm, &references));
+        // 已计算的值作为缓存结果写入，不重新计算的查看器也能显示
+        if let Some(value) = model.value {
+            formula = formula.set_result(value.to_string());
+        }
+        aggregates
+            .write_formula(*row, AGGREGATE_VALUE_COLUMN, formula)
+            .map_err(excel_error)?;
+        if !with_weight {
+            continue;
+        }
+        if let Some(weight) = weight(tree_model, *id) {
+            aggregates
+                .write_number(*row, AGGREGATE_WEIGHT_COLUMN, weight)
+                .map_err(excel_error)?;
+        }
+        // 权重不参与模型的计算，加权和与加权平均只作为参考，只统计有权重的子节点
+        let weighted = children
+            .iter()
+            .filter(|(child, _)| weight(tree_model, *child).is_some())
+            .map(|(_, cell)| (cell.value(), cell.weight()))
+            .collect::<Vec<(String, String)>>();
+        if weighted.is_empty() {
+            continue;
+        }
+        let products = weighted
+            .iter()
+            .map(|(value, weight)| format!("{}*{}", value, weight))
+            .collect::<Vec<String>>()
+            .join("+");
+        let weights = weighted
+            .iter()
+            .map(|(_, weight)| weight.clone())
+            .collect::<Vec<String>>();
+        aggregates
+            .write_formula(*row, 4, Formula::new(format!("={}", products)))
+            .map_err(excel_error)?;
+        aggregates
+            .write_formula(
+                *row,
+                5,
+                Formula::new(format!("=({})/{}", products, call("SUM", &weights))),
+            )
+            .map_err(excel_error)?;
+    }
+    workbook.save_to_buffer().map_err(excel_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::build_tree_model;
+    use calamine::{open_workbook_auto_from_rs, Reader};
+    use std::io::Cursor;
+
+    #[test]
+    fn formulas_match_calculate() {
+        let references = ["A1".to_string(), "A2".to_string()];
+        assert_eq!(
+            aggregate_formula(&Algorithm::Sum, &references),
+            "=SUM(A1,A2)"
+        );
+        assert_eq!(
+            aggregate_formula(&Algorithm::Min, &references),
+            "=MIN(A1,A2)"
+        );
+        assert_eq!(aggregate_formula(&Algorithm::None, &references), "=NA()");
+        assert_eq!(aggregate_formula(&Algorithm::Product, &[]), "=1");
+        assert_eq!(aggregate_formula(&Algorithm::Average, &[]), "=0");
+        // 超过255个参数时分组嵌套，平均改为求和除以个数
+        let references = (1..=300)
+            .map(|row| format!("A{}", row))
+            .collect::<Vec<String>>();
+        let formula = aggregate_formula(&Algorithm::Average, &references);
+        assert!(formula.starts_with("=SUM(SUM(A1,"));
+        assert!(formula.ends_with(",A300))/300"));
+    }
+
+    // 共享的叶节点在输入表中只占一行，两个父节点引用同一个单元格
+    #[test]
+    fn shared_leaf_has_one_input_row() {
+        let file_tree_model = serde_json::from_str(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["A", "B"], "algorithm": "取最小值"},
+                    {"name": "A", "children": ["S", "x"], "algorithm": "求和", "weight": 0.6},
+                    {"name": "B", "children": ["S"], "algorithm": "取最大值", "weight": 0.4}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let tree_model = build_tree_model(file_tree_model, false).unwrap();
+        let content = build_workbook(&tree_model).unwrap();
+        let mut workbook = open_workbook_auto_from_rs(Cursor::new(content)).unwrap();
+        assert_eq!(workbook.sheet_names(), [INPUT_SHEET, AGGREGATE_SHEET]);
+        let inputs = workbook.worksheet_range(INPUT_SHEET).unwrap();
+        assert_eq!(inputs.height(), 3);
+        let formulas = workbook.worksheet_formula(AGGREGATE_SHEET).unwrap();
+        // 公式区域从第一个有公式的单元格开始，按工作表中的绝对位置读取
+        let formulas = (1..=3)
+            .map(|row| {
+                formulas
+                    .get_value((row, AGGREGATE_VALUE_COLUMN as u32))
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<String>>();
+        let s = cell_reference(INPUT_SHEET, 1, INPUT_VALUE_COLUMN);
+        assert!(formulas[1].contains(&s) && formulas[2].contains(&s));
+        assert!(formulas[0].starts_with("MIN("));
+    }
+}
@@ -0,0 +1,119 @@
+use crate::operations::descendants;
+use regex::Regex;
+use shared::{FindMode, FindReplacePreview, FindReplaceSettings, Model, NameChange, NameCollision};
+use std::collections::{BTreeMap, HashMap};
+
+// 替换后名称相同的一组节点：与重命名相同，叶节点合并到同名的节点。
+// 非叶节点只能作为保留的节点，且不能被改为已有节点的名称
+fn collision(
+    models: &BTreeMap<u64, Model>,
+    renamed: &HashMap<u64, &str>,
+    name: &str,
+    ids: Vec<u64>,
+) -> NameCollision {
+    let non_leaves = ids
+        .iter()
+        .filter(|id| models[id].expand_info.is_some())
+        .copied()
+        .collect::<Vec<u64>>();
+    let unchanged = ids.iter().find(|id| !renamed.contains_key(id)).copied();
+    let target = non_leaves.first().copied().or(unchanged).unwrap_or(ids[0]);
+    let merged = ids
+        .iter()
+        .filter(|id| **id != target)
+        .copied()
+        .collect::<Vec<u64>>();
+    let error = if non_leaves.len() > 1 {
+        Some(format!("多个非叶节点将重名为\"{}\"", name))
+    } else if unchanged.is_some_and(|id| id != target) {
+        Some(format!("非叶节点不能改为已有的名称\"{}\"", name))
+    } else if !non_leaves.is_empty() {
+        // 叶节点的父节点将改为引用target，父节点不能在target的子树中
+        let subtree = descendants(models, target);
+        let creates_cycle = subtree.iter().any(|id| {
+            models
+                .get(id)
+                .and_then(|model| model.expand_info.as_ref())
+                .is_some_and(|expand_info| {
+                    expand_info
+                        .children
+                        .iter()
+                        .any(|child| merged.contains(child))
+                })
+        });
+        creates_cycle.then(|| format!("合并到\"{}\"后该节点将成为自己的子孙节点", name))
+    } else {
+        None
+    };
+    let old_names = std::iter::once(target)
+        .chain(merged.iter().copied())
+        .map(|id| models[&id].name.clone())
+        .collect();
+    NameCollision {
+        name: name.to_string(),
+        target,
+        merged,
+        old_names,
+        error,
+    }
+}
+
+// 计算所有节点名称的替换结果和替换后的重名，不修改模型
+pub fn find_replace_preview(
+    models: &BTreeMap<u64, Model>,
+    settings: &FindReplaceSettings,
+) -> Result<FindReplacePreview, String> {
+    if settings.find.is_empty() {
+        Err("查找内容为空".to_string())?;
+    }
+    let regex = match settings.mode {
+        FindMode::Literal => None,
+        FindMode::Regex => {
+            Some(Regex::new(&settings.find).map_err(|e| format!("正则表达式无效：{}", e))?)
+        }
+    };
+    let mut changes = Vec::new();
+    for model in models.values() {
+        let new_name = match &regex {
+            Some(regex) => regex
+                .replace_all(&model.name, settings.replace.as_str())
+                .into_owned(),
+            None => model.name.replace(&settings.find, &settings.replace),
+        };
+        if new_name == model.name {
+            continue;
+        }
+        if new_name.is_empty() {
+            Err(format!("\"{}\"替换后名称为空", model.name))?;
+        }
+        changes.push(NameChange {
+            id: model.id,
+            old_name: model.name.clone(),
+            new_name,
+            is_leaf: model.expand_info.is_none(),
+        });
+    }
+
+    let renamed = changes
+        .iter()
+        .map(|change| (change.id, change.new_name.as_str()))
+        .collect::<HashMap<u64, &str>>();
+    let mut owners = BTreeMap::<&str, Vec<u64>>::new();
+    for model in models.values() {
+        let name = renamed
+            .get(&model.id)
+            .copied()
+            .unwrap_or(model.name.as_str());
+        owners.entry(name).or_default().push(model.id);
+    }
+    // 只关心因这次替换产生的重名
+    let collisions = owners
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1 && ids.iter().any(|id| renamed.contains_key(id)))
+        .map(|(name, ids)| collision(models, &renamed, name, ids))
+        .collect();
+    Ok(FindReplacePreview {
+        changes,
+        collisions,
+    })
+}
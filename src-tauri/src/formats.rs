@@ -0,0 +1,131 @@
+use crate::models::FileTreeModel;
+use serde_json::Value;
+use std::path::Path;
+
+// 模型文件的格式，根据文件扩展名决定，三种格式表示同一个FileTreeModel结构
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModelFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ModelFormat {
+    pub fn from_path(file_path: &str) -> ModelFormat {
+        let extension = Path::new(file_path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("yaml") | Some("yml") => ModelFormat::Yaml,
+            Some("toml") => ModelFormat::Toml,
+            _ => ModelFormat::Json,
+        }
+    }
+
+    pub fn parse(&self, content: &str) -> Result<FileTreeModel, String> {
+        match self {
+            ModelFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+            ModelFormat::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+            ModelFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+        }
+    }
+
+    pub fn serialize(&self, file_tree_model: &FileTreeModel) -> Result<String, String> {
+        match self {
+            ModelFormat::Json => {
+                serde_json::to_string_pretty(file_tree_model).map_err(|e| e.to_string())
+            }
+            ModelFormat::Yaml => serde_yaml::to_string(file_tree_model).map_err(|e| e.to_string()),
+            ModelFormat::Toml => {
+                let mut value = serde_json::to_value(file_tree_model).map_err(|e| e.to_string())?;
+                drop_nulls(&mut value, "")?;
+                toml::to_string_pretty(&value).map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+// TOML没有null：对象中值为null的字段（多为本程序不认识的字段）不写入，
+// 数组中的null无法表示，报告其所在的字段
+fn drop_nulls(value: &mut Value, path: &str) -> Result<(), String> {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            for (key, value) in map.iter_mut() {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                drop_nulls(value, &path)?;
+            }
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                let path = format!("{}[{}]", path, index);
+                if value.is_null() {
+                    Err(format!("TOML不支持空值，字段\"{}\"无法保存", path))?;
+                }
+                drop_nulls(value, &path)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// 打开和保存对话框中使用的扩展名
+pub const MODEL_EXTENSIONS: [&str; 4] = ["json", "yaml", "yml", "toml"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::tests::{sample_model, structure};
+
+    const FORMATS: [ModelFormat; 3] = [ModelFormat::Json, ModelFormat::Yaml, ModelFormat::Toml];
+
+    #[test]
+    fn round_trip() {
+        let model = sample_model();
+        for format in FORMATS {
+            let content = format.serialize(&model).unwrap();
+            let reparsed = format.parse(&content).unwrap();
+            assert_eq!(reparsed.root_name, model.root_name, "{:?}", format);
+            assert_eq!(structure(&reparsed), structure(&model), "{:?}", format);
+        }
+    }
+
+    // 本程序不认识的字段原样保留；TOML中值为null的字段不写入
+    #[test]
+    fn unknown_fields_with_nulls() {
+        let model = ModelFormat::Json
+            .parse(
+                r#"{
+                    "root_name": "Root",
+                    "source": null,
+                    "tags": ["a", "b"],
+                    "data": [
+                        {"name": "Root", "children": ["x"], "algorithm": "求和",
+                         "note": {"by": "甲", "checked": null}}
+                    ]
+                }"#,
+            )
+            .unwrap();
+        for format in FORMATS {
+            let reparsed = format.parse(&format.serialize(&model).unwrap()).unwrap();
+            assert_eq!(reparsed.extra["tags"], serde_json::json!(["a", "b"]));
+            assert_eq!(reparsed.data[0].extra["note"]["by"], "甲");
+            let kept = format != ModelFormat::Toml;
+            assert_eq!(reparsed.extra.contains_key("source"), kept, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn null_in_array_is_named_for_toml() {
+        let model = ModelFormat::Json
+            .parse(r#"{"root_name": "Root", "data": [], "tags": ["a", null]}"#)
+            .unwrap();
+        let error = ModelFormat::Toml.serialize(&model).unwrap_err();
+        assert!(error.contains("\"tags[1]\""), "{}", error);
+    }
+}
@@ -1,3 +1,4 @@
+use pinyin::ToPinyin;
 use shared::Model;
 use std::collections::{BTreeMap, HashMap, HashSet};
 
@@ -8,6 +9,27 @@ pub fn suggest_new_name_dupe(new_name: &str, models: &HashMap<String, Model>) ->
     }
     new_name
 }
+// 按拼音排序的键：每个汉字为带声调数字的拼音（如“zhong1”），其他字符转为小写，
+// 多音字取最常用的读音
+pub fn pinyin_sort_key(name: &str) -> Vec<String> {
+    name.chars()
+        .map(|c| match c.to_pinyin() {
+            Some(pinyin) => pinyin.with_tone_num_end().to_string(),
+            None => c.to_lowercase().collect(),
+        })
+        .collect()
+}
+
+// 名称中每个汉字的拼音（不带声调），其他字符转为小写，用于发现同音字造成的拼写错误
+pub fn pinyin_syllables(name: &str) -> Vec<String> {
+    name.chars()
+        .map(|c| match c.to_pinyin() {
+            Some(pinyin) => pinyin.plain().to_string(),
+            None => c.to_lowercase().collect(),
+        })
+        .collect()
+}
+
 pub fn suggest_new_name_add(models: &BTreeMap<u64, Model>) -> String {
     let mut new_name = "新节点".to_string();
     let mut i = 0;
@@ -21,3 +43,19 @@ pub fn suggest_new_name_add(models: &BTreeMap<u64, Model>) -> String {
     }
     new_name
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // GBK编码中二级汉字（丌、睾）排在所有一级汉字之后，按拼音应排在中间
+    #[test]
+    fn pinyin_order() {
+        let mut names = vec!["中", "丌", "北", "安"];
+        names.sort_by_cached_key(|name| pinyin_sort_key(name));
+        assert_eq!(names, ["安", "北", "丌", "中"]);
+        let mut names = vec!["骨骼", "睾酮", "肝脏", "Ab"];
+        names.sort_by_cached_key(|name| pinyin_sort_key(name));
+        assert_eq!(names, ["Ab", "肝脏", "睾酮", "骨骼"]);
+    }
+}
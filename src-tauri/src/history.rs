@@ -0,0 +1,324 @@
+use crate::journal::JournalEntry;
+use crate::models::{ModelSnapshot, TreeModel};
+use crate::operations;
+
+// 撤销历史最多保留的步数
+const MAX_HISTORY: usize = 100;
+
+// 撤销和重做的历史，每一步保存一次逻辑操作之前（或撤销之前）的整个模型，
+// 一次操作无论修改了多少节点（例如重命名时合并同名叶节点）都作为一步撤销
+#[derive(Default)]
+pub struct History {
+    undo_stack: Vec<(String, ModelSnapshot)>,
+    redo_stack: Vec<(String, ModelSnapshot)>,
+}
+
+impl History {
+    // 在一次修改成功后调用，before为修改之前的模型，新的修改使重做历史失效
+    pub fn push(&mut self, label: &str, before: ModelSnapshot) {
+        self.undo_stack.push((label.to_string(), before));
+        if self.undo_stack.len() > MAX_HISTORY {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
+    pub fn undo_len(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    pub fn redo_len(&self) -> usize {
+        self.redo_stack.len()
+    }
+
+    pub fn undo_label(&self) -> Option<String> {
+        self.undo_stack.last().map(|(label, _)| label.clone())
+    }
+
+    pub fn redo_label(&self) -> Option<String> {
+        self.redo_stack.last().map(|(label, _)| label.clone())
+    }
+
+    // 撤销历史中的模型状态，最近的在前
+    pub fn past_snapshots(&self) -> impl Iterator<Item = &ModelSnapshot> {
+        self.undo_stack.iter().rev().map(|(_, snapshot)| snapshot)
+    }
+
+    // 返回被撤销的操作名称
+    pub fn undo(&mut self, tree_model: &mut TreeModel) -> Result<String, String> {
+        let (label, snapshot) = self
+            .undo_stack
+            .pop()
+            .ok_or("没有可以撤销的修改".to_string())?;
+        self.redo_stack
+            .push((label.clone(), tree_model.restore(snapshot)));
+        Ok(label)
+    }
+
+    // 返回被重做的操作名称
+    pub fn redo(&mut self, tree_model: &mut TreeModel) -> Result<String, String> {
+        let (label, snapshot) = self
+            .redo_stack
+            .pop()
+            .ok_or("没有可以重做的修改".to_string())?;
+        self.undo_stack
+            .push((label.clone(), tree_model.restore(snapshot)));
+        Ok(label)
+    }
+}
+
+// 重放恢复日志中的一条记录，撤销和重做按记录时的顺序在重放时建立的历史上重做一遍；
+// 重放时的历史中没有的步骤（如撤销到保存之前）记录为Restore，作为普通的修改重放
+pub fn replay(
+    tree_model: &mut TreeModel,
+    history: &mut History,
+    entry: &JournalEntry,
+) -> Result<(), String> {
+    match entry {
+        JournalEntry::Undo => {
+            history.undo(tree_model)?;
+        }
+        JournalEntry::Redo => {
+            history.redo(tree_model)?;
+        }
+        entry => {
+            let before = tree_model.snapshot();
+            operations::apply(tree_model, entry)?;
+            history.push(entry.label(), before);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::{read_journal, JournalState};
+    use crate::loader::{build_tree_model, load_models};
+    use crate::models::{FileTreeModel, TauriState};
+    use crate::saver::{save_state, to_file_tree_model};
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    const MODEL: &str = r#"{
+        "root_name": "Root",
+        "data": [
+            {"name": "Root", "children": ["A", "B"], "algorithm": "取最小值"},
+            {"name": "A", "children": ["A1", "A2"], "algorithm": "取最大值"},
+            {"name": "B", "children": ["B1"], "algorithm": "取最大值"}
+        ]
+    }"#;
+
+    fn load(file_tree_model: FileTreeModel) -> TreeModel {
+        build_tree_model(file_tree_model, false).unwrap()
+    }
+
+    fn id_of(tree_model: &TreeModel, name: &str) -> u64 {
+        tree_model
+            .models
+            .values()
+            .find(|model| model.name == name)
+            .unwrap()
+            .id
+    }
+
+    // 每个节点的id、名称和子节点，用于比较两个模型
+    fn shape(tree_model: &TreeModel) -> BTreeMap<u64, (String, Option<Vec<u64>>)> {
+        tree_model
+            .models
+            .values()
+            .map(|model| {
+                let children = model
+                    .expand_info
+                    .as_ref()
+                    .map(|expand_info| expand_info.children.clone());
+                (model.id, (model.name.clone(), children))
+            })
+            .collect()
+    }
+
+    // 保存后继续编辑，再从保存的文件和日志恢复，应得到与编辑时相同的模型
+    #[test]
+    fn replay_after_save_matches_edits() {
+        let mut edited = load(serde_json::from_str(MODEL).unwrap());
+        let mut history = History::default();
+        // 保存之前新加的节点在重新加载时按名称分配到不同的id
+        let a = id_of(&edited, "A");
+        replay(&mut edited, &mut history, &JournalEntry::Add { id: a }).unwrap();
+        let journal_ids = edited.journal_ids();
+        let saved = to_file_tree_model(&edited).unwrap();
+
+        let entries = [
+            JournalEntry::Rename {
+                id: id_of(&edited, "B1"),
+                new_name: "B2".to_string(),
+            },
+            JournalEntry::Add {
+                id: id_of(&edited, "B"),
+            },
+        ];
+        for entry in entries.iter() {
+            replay(&mut edited, &mut history, entry).unwrap();
+        }
+
+        let mut recovered = load(saved);
+        assert_ne!(recovered.journal_ids(), journal_ids);
+        recovered.renumber(&journal_ids);
+        assert_eq!(recovered.journal_ids(), journal_ids);
+        let mut recovered_history = History::default();
+        for entry in entries.iter() {
+            replay(&mut recovered, &mut recovered_history, entry).unwrap();
+        }
+        assert_eq!(shape(&recovered), shape(&edited));
+    }
+
+    fn edit(state: &mut TauriState, entry: JournalEntry) {
+        let tree_model = state.curr_tree_model.as_mut().unwrap();
+        let before = tree_model.snapshot();
+        operations::apply(tree_model, &entry).unwrap();
+        state.record(entry, before);
+    }
+
+    // 日志写入文件再读回重放：撤销到保存之前的一步记为Restore，恢复后应与编辑时的模型相同
+    #[test]
+    fn journal_round_trip_with_undo_across_save() {
+        let dir = std::env::temp_dir().join(format!("journal-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("model.json").to_string_lossy().to_string();
+        let mut state = TauriState {
+            curr_tree_model: Some(load(serde_json::from_str(MODEL).unwrap())),
+            ..Default::default()
+        };
+        let id =
+            |state: &TauriState, name: &str| id_of(state.curr_tree_model.as_ref().unwrap(), name);
+        let a = id(&state, "A");
+        edit(&mut state, JournalEntry::Add { id: a });
+        save_state(&mut state, &file_path).unwrap();
+
+        state.undo().unwrap();
+        let b1 = id(&state, "B1");
+        edit(
+            &mut state,
+            JournalEntry::Rename {
+                id: b1,
+                new_name: "B2".to_string(),
+            },
+        );
+        let b = id(&state, "B");
+        edit(&mut state, JournalEntry::Add { id: b });
+        state.undo().unwrap();
+        state.redo().unwrap();
+        state.undo().unwrap();
+
+        let (journal_ids, entries) = match read_journal(&file_path).unwrap() {
+            JournalState::Unsaved(Some(journal_ids), entries) => (journal_ids, entries),
+            _ => panic!("日志中应有未保存的修改"),
+        };
+        assert!(matches!(entries[0], JournalEntry::Restore { .. }));
+        let mut recovered = load_models(&file_path, false).unwrap();
+        recovered.renumber(&journal_ids);
+        let mut history = History::default();
+        for entry in entries.iter() {
+            replay(&mut recovered, &mut history, entry).unwrap();
+        }
+        assert_eq!(
+            shape(&recovered),
+            shape(state.curr_tree_model.as_ref().unwrap())
+        );
+        assert_eq!(history.redo_label(), state.history.redo_label());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn counter(tree_model: &TreeModel) -> u64 {
+        tree_model
+            .counter
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    // 撤销删除后与删除前完全相同，重做后与删除后完全相同，id计数器不变
+    #[test]
+    fn undo_redo_delete_restores_identical_model() {
+        let mut state = TauriState {
+            curr_tree_model: Some(load(serde_json::from_str(MODEL).unwrap())),
+            ..Default::default()
+        };
+        let tree_model = state.curr_tree_model.as_ref().unwrap();
+        let (root, a) = (id_of(tree_model, "Root"), id_of(tree_model, "A"));
+        let before = serde_json::to_value(tree_model.snapshot()).unwrap();
+        let before_counter = counter(tree_model);
+        edit(
+            &mut state,
+            JournalEntry::Delete {
+                id: a,
+                parent: Some(root),
+            },
+        );
+        let tree_model = state.curr_tree_model.as_ref().unwrap();
+        assert!(!tree_model.models.contains_key(&a));
+        let after = serde_json::to_value(tree_model.snapshot()).unwrap();
+
+        assert_eq!(state.undo().unwrap(), "删除节点");
+        let tree_model = state.curr_tree_model.as_ref().unwrap();
+        assert_eq!(serde_json::to_value(tree_model.snapshot()).unwrap(), before);
+        assert_eq!(counter(tree_model), before_counter);
+        state.redo().unwrap();
+        let tree_model = state.curr_tree_model.as_ref().unwrap();
+        assert_eq!(serde_json::to_value(tree_model.snapshot()).unwrap(), after);
+        assert_eq!(counter(tree_model), before_counter);
+    }
+
+    // 撤销删除后继续编辑，从日志重放得到的模型和id计数器与编辑时相同
+    #[test]
+    fn journal_replay_after_undo_of_delete() {
+        let dir = std::env::temp_dir().join(format!("journal-test-undo-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("model.json").to_string_lossy().to_string();
+        let mut state = TauriState {
+            curr_tree_model: Some(load(serde_json::from_str(MODEL).unwrap())),
+            ..Default::default()
+        };
+        save_state(&mut state, &file_path).unwrap();
+        let id =
+            |state: &TauriState, name: &str| id_of(state.curr_tree_model.as_ref().unwrap(), name);
+        let (root, b) = (id(&state, "Root"), id(&state, "B"));
+        edit(
+            &mut state,
+            JournalEntry::DeleteSubtree {
+                id: b,
+                parent: root,
+            },
+        );
+        let a = id(&state, "A");
+        edit(&mut state, JournalEntry::Add { id: a });
+        state.undo().unwrap();
+        state.undo().unwrap();
+        let b1 = id(&state, "B1");
+        edit(
+            &mut state,
+            JournalEntry::Rename {
+                id: b1,
+                new_name: "B2".to_string(),
+            },
+        );
+
+        let (journal_ids, entries) = match read_journal(&file_path).unwrap() {
+            JournalState::Unsaved(Some(journal_ids), entries) => (journal_ids, entries),
+            _ => panic!("日志中应有未保存的修改"),
+        };
+        assert!(entries
+            .iter()
+            .all(|entry| !matches!(entry, JournalEntry::Restore { .. })));
+        let mut recovered = load_models(&file_path, false).unwrap();
+        recovered.renumber(&journal_ids);
+        let mut history = History::default();
+        for entry in entries.iter() {
+            replay(&mut recovered, &mut history, entry).unwrap();
+        }
+        let edited = state.curr_tree_model.as_ref().unwrap();
+        assert_eq!(shape(&recovered), shape(edited));
+        assert_eq!(counter(&recovered), counter(edited));
+        assert_eq!(history.undo_label(), state.history.undo_label());
+        assert!(history.redo_label().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+use crate::models::ModelSnapshot;
+use serde::{Deserialize, Serialize};
+use shared::{
+    Algorithm, CopySettings, FindReplaceSettings, MergeSettings, ModelHeader, NodeMetadata,
+};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+// 恢复日志：每次修改模型都会追加一行到模型文件旁的“文件名.journal”中
+// 保存成功后日志被清空；若打开模型时日志中仍有记录，说明上次的修改没有保存（例如程序崩溃）
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum JournalEntry {
+    Rename {
+        id: u64,
+        new_name: String,
+    },
+    Delete {
+        id: u64,
+        parent: Option<u64>,
+    },
+    DeleteSubtree {
+        id: u64,
+        parent: u64,
+    },
+    RemoveUnreachable {
+        ids: Vec<u64>,
+    },
+    PurgeUnreachable,
+    Move {
+        id: u64,
+        old_parent: u64,
+        new_parent: u64,
+        index: Option<u64>,
+    },
+    Add {
+        id: u64,
+    },
+    Unshare {
+        id: u64,
+        parent: u64,
+        new_name: String,
+        copy_settings: Option<CopySettings>,
+    },
+    Merge {
+        left: u64,
+        right: u64,
+        settings: MergeSettings,
+    },
+    FindReplace {
+        settings: FindReplaceSettings,
+    },
+    LinkChild {
+        id: u64,
+        parent: u64,
+    },
+    CopySubtree {
+        id: u64,
+        parent: u64,
+        settings: CopySettings,
+    },
+    // 记录调整后的顺序，重放时不依赖计算结果等不在日志中的状态
+    ReorderChildren {
+        id: u64,
+        children: Vec<u64>,
+    },
+    UpdateAlgorithm {
+        id: u64,
+        new_algorithm: Algorithm,
+    },
+    CanExpandToggling {
+        id: u64,
+    },
+    UpdateMetadata {
+        id: u64,
+        metadata: NodeMetadata,
+    },
+    UpdateHeader {
+        header: ModelHeader,
+    },
+    Undo,
+    Redo,
+    // 撤销或重做后的整个模型，用于重放时的撤销历史中没有对应步骤的撤销和重做
+    Restore {
+        snapshot: Box<ModelSnapshot>,
+    },
+}
+
+impl JournalEntry {
+    // 在撤销历史和界面上显示的操作名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            JournalEntry::Rename { .. } => "重命名",
+            JournalEntry::Delete { .. } => "删除节点",
+            JournalEntry::DeleteSubtree { .. } => "删除子树",
+            JournalEntry::RemoveUnreachable { .. } => "删除不可达节点",
+            JournalEntry::PurgeUnreachable => "清除不可达节点",
+            JournalEntry::Move { .. } => "移动节点",
+            JournalEntry::Add { .. } => "添加子节点",
+            JournalEntry::Unshare { .. } => "拆分共享节点",
+            JournalEntry::Merge { .. } => "合并节点",
+            JournalEntry::FindReplace { .. } => "查找替换名称",
+            JournalEntry::LinkChild { .. } => "添加已有节点",
+            JournalEntry::CopySubtree { .. } => "复制子树",
+            JournalEntry::ReorderChildren { .. } => "调整子节点顺序",
+            JournalEntry::UpdateAlgorithm { .. } => "修改算法",
+            JournalEntry::CanExpandToggling { .. } => "切换是否可展开",
+            JournalEntry::UpdateMetadata { .. } => "修改说明信息",
+            JournalEntry::UpdateHeader { .. } => "修改模型信息",
+            JournalEntry::Undo => "撤销",
+            JournalEntry::Redo => "重做",
+            JournalEntry::Restore { .. } => "撤销或重做",
+        }
+    }
+}
+
+// 日志开始时内存中节点名称到id的对应和下一个新节点的id。
+// 重新加载模型文件时id按名称重新分配，与编辑时的id不一定相同，恢复时先按此对应改回编辑时的id，
+// 日志中的记录才能作用到正确的节点上
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct JournalIds {
+    pub ids: BTreeMap<String, u64>,
+    pub counter: u64,
+}
+
+// 日志的第一行，记录日志开始时模型文件的摘要，用于判断日志是否仍然适用于该文件
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct JournalHeader {
+    model_digest: String,
+    // 旧版本的日志没有此项
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ids: Option<JournalIds>,
+}
+
+pub struct Journal {
+    file: File,
+}
+
+pub fn journal_path(model_path: &str) -> PathBuf {
+    let path = Path::new(model_path);
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".journal");
+    path.with_file_name(file_name)
+}
+
+// FNV-1a，保证不同版本的程序计算出的摘要一致
+fn digest(content: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in content {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}-{}", hash, content.len())
+}
+
+fn model_digest(model_path: &str) -> Result<String, String> {
+    let content =
+        fs::read(model_path).map_err(|e| format!("读取模型文件{:?}错误\n{}", model_path, e))?;
+    Ok(digest(&content))
+}
+
+impl Journal {
+    // 为当前的模型文件开始一个新的空日志，覆盖旧日志；ids为此时内存中模型的id
+    pub fn create(model_path: &str, ids: JournalIds) -> Result<Journal, String> {
+        let path = journal_path(model_path);
+        let header = JournalHeader {
+            model_digest: model_digest(model_path)?,
+            ids: Some(ids),
+        };
+        let mut file =
+            File::create(&path).map_err(|e| format!("创建恢复日志{:?}错误\n{}", path, e))?;
+        let line =
+            serde_json::to_string(&header).map_err(|e| format!("序列化恢复日志错误\n{}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("写入恢复日志错误\n{}", e))?;
+        Ok(Journal { file })
+    }
+
+    pub fn append(&mut self, entry: &JournalEntry) -> Result<(), String> {
+        let line =
+            serde_json::to_string(entry).map_err(|e| format!("序列化恢复日志错误\n{}", e))?;
+        writeln!(self.file, "{}", line).map_err(|e| format!("写入恢复日志错误\n{}", e))?;
+        self.file
+            .sync_data()
+            .map_err(|e| format!("写入恢复日志错误\n{}", e))?;
+        Ok(())
+    }
+}
+
+pub fn remove_journal(model_path: &str) {
+    let path = journal_path(model_path);
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            eprintln!("删除恢复日志{:?}错误：{}", path, e);
+        }
+    }
+}
+
+pub enum JournalState {
+    // 没有日志或日志为空
+    Clean,
+    // 日志中有未保存的修改，以及日志开始时的id（旧版本的日志没有）
+    Unsaved(Option<JournalIds>, Vec<JournalEntry>),
+    // 日志开始后模型文件被其他程序修改过，无法重放
+    Stale,
+}
+
+pub fn read_journal(model_path: &str) -> Result<JournalState, String> {
+    let path = journal_path(model_path);
+    let file = match OpenOptions::new().read(true).open(&path) {
+        Ok(file) => file,
+        Err(_) => return Ok(JournalState::Clean),
+    };
+    let mut lines = BufReader::new(file).lines();
+    let header = match lines.next() {
+        Some(line) => line.map_err(|e| format!("读取恢复日志{:?}错误\n{}", path, e))?,
+        None => return Ok(JournalState::Clean),
+    };
+    let header = serde_json::from_str::<JournalHeader>(&header)
+        .map_err(|e| format!("解析恢复日志{:?}错误\n{}", path, e))?;
+    let mut entries = Vec::new();
+    for line in lines {
+        let line = line.map_err(|e| format!("读取恢复日志{:?}错误\n{}", path, e))?;
+        // 崩溃时最后一行可能只写了一半，忽略之
+        match serde_json::from_str::<JournalEntry>(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => {
+                eprintln!("忽略无法解析的恢复日志记录：{}\n{}", line, e);
+                break;
+            }
+        }
+    }
+    if entries.is_empty() {
+        return Ok(JournalState::Clean);
+    }
+    if header.model_digest != model_digest(model_path)? {
+        return Ok(JournalState::Stale);
+    }
+    Ok(JournalState::Unsaved(header.ids, entries))
+}
@@ -1,11 +1,23 @@
 use commands::*;
 use models::TauriState;
 use std::sync::RwLock;
+pub mod autosave;
 pub mod commands;
+pub mod datafile;
+pub mod exchange;
+pub mod find_replace;
+pub mod formats;
 pub mod helper;
+pub mod history;
+pub mod journal;
 pub mod loader;
 pub mod models;
+pub mod operations;
+pub mod orphans;
 pub mod saver;
+pub mod search;
+pub mod template;
+pub mod validation;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -15,20 +27,62 @@ pub fn run() {
         .manage(tauri_state)
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            autosave::start_autosave(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             select_file,
             prepare_models,
             query_file_path,
+            query_model_header,
+            request_update_header,
             query_node,
             request_rename,
             request_delete,
             request_add,
+            request_move,
+            request_delete_subtree,
+            request_purge_unreachable,
+            query_unreachable_nodes,
+            request_remove_unreachable,
+            request_reorder_child,
+            request_copy_subtree,
+            request_link_child,
+            request_unshare,
+            query_merge_preview,
+            request_merge,
+            query_find_replace_preview,
+            request_find_replace,
+            query_search_nodes,
+            request_sort_children,
             request_update_algorithm,
             request_can_expand_toggling,
+            request_update_metadata,
+            query_history,
+            request_undo,
+            request_redo,
             request_save,
+            request_save_as,
+            query_save_settings,
+            request_update_save_settings,
+            query_data_file_settings,
+            request_update_data_file_settings,
+            request_convert_model,
+            request_import_nested_json,
+            request_export_nested_json,
+            request_import_outline,
+            request_export_outline,
+            request_import_mind_map,
+            request_import_levels,
+            request_export_levels,
+            request_export_graph,
+            request_export_xlsx,
             request_calculate,
             query_values,
             request_template_generation,
+            request_validate_data,
+            request_apply_data_renames,
             log
         ])
         .run(tauri::generate_context!())
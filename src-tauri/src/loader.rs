@@ -1,24 +1,86 @@
-use crate::models::{FileData, FileModel, FileTreeModel, TreeModel};
+use crate::datafile::{read_table, table_to_data, TABLE_EXTENSIONS};
+use crate::formats::ModelFormat;
+use crate::models::{FileData, FileExtras, FileModel, FileTreeModel, TreeModel};
 use rand::Rng;
-use shared::{Algorithm, ExpandInfo, Model};
+use shared::{Algorithm, DataFileSettings, ExpandInfo, Model};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
+use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
 
+// 按扩展名选择格式读取模型文件，不做内容上的检查
+pub fn read_file_tree_model(file_path: &str) -> Result<FileTreeModel, String> {
+    let content = fs::read_to_string(file_path)
+        .map_err(|e| format!("读取模型文件{:?}错误\n{}", file_path, e))?;
+    ModelFormat::from_path(file_path)
+        .parse(&content)
+        .map_err(|e| format!("解析模型文件{:?}错误\n{}", file_path, e))
+}
+
 pub fn load_models(file_path: &str, randomize_algorithm: bool) -> Result<TreeModel, String> {
+    let file_tree_model = read_file_tree_model(file_path)?;
+    build_tree_model(file_tree_model, randomize_algorithm)
+}
+
+// 导入的文件中同名节点会合并，可能形成环，计算和显示时会无限递归
+fn check_cycles(models: &BTreeMap<String, FileModel>) -> Result<(), String> {
+    // 0：未访问，1：在当前路径上，2：已检查
+    let mut states = HashMap::<&str, u8>::new();
+    fn visit<'a>(
+        models: &'a BTreeMap<String, FileModel>,
+        states: &mut HashMap<&'a str, u8>,
+        path: &mut Vec<&'a str>,
+        name: &'a str,
+    ) -> Result<(), String> {
+        match states.get(name) {
+            Some(2) => return Ok(()),
+            Some(1) => {
+                let start = path.iter().position(|n| *n == name).unwrap_or(0);
+                let mut cycle = path[start..].to_vec();
+                cycle.push(name);
+                Err(format!("模型中存在循环引用：{}", cycle.join(" → ")))?;
+            }
+            _ => {}
+        }
+        states.insert(name, 1);
+        path.push(name);
+        if let Some(model) = models.get(name) {
+            for child in model.children.iter().flatten() {
+                visit(models, states, path, child)?;
+            }
+        }
+        path.pop();
+        states.insert(name, 2);
+        Ok(())
+    }
+    for name in models.keys() {
+        visit(models, &mut states, &mut Vec::new(), name)?;
+    }
+    Ok(())
+}
+
+// 检查文件模型并为每个名字分配id，所有格式的导入最终都经过这里
+pub fn build_tree_model(
+    file_tree_model: FileTreeModel,
+    randomize_algorithm: bool,
+) -> Result<TreeModel, String> {
     let counter = AtomicU64::new(0);
     // Reset counter
     counter.store(0, Ordering::Relaxed);
-    let content = fs::read_to_string(file_path)
-        .map_err(|e| format!("读取模型文件{:?}错误\n{}", file_path, e))?;
-    let file_tree_model = serde_json::from_str::<FileTreeModel>(&content)
-        .map_err(|e| format!("解析模型文件{:?}错误\n{}", file_path, e))?;
     let models = file_tree_model.data;
     let root_name = file_tree_model.root_name;
+    // 记录节点在文件中的原始顺序，保存时按此顺序写回
+    let mut file_order = Vec::<String>::new();
+    for model in models.iter() {
+        if !file_order.contains(&model.name) {
+            file_order.push(model.name.clone());
+        }
+    }
     let models: BTreeMap<String, FileModel> = models
         .into_iter()
         .map(|model| (model.name.clone(), model))
         .collect();
+    check_cycles(&models)?;
     // 遍历所有模型及其children，将所有名字给一个独一无二的编号
     let mut name_to_id = HashMap::<String, u64>::new();
     // 确保root_name在模型中
@@ -33,48 +95,65 @@ pub fn load_models(file_path: &str, randomize_algorithm: bool) -> Result<TreeMod
         name_to_id
             .entry(model.name.clone())
             .or_insert(counter.fetch_add(1, Ordering::Relaxed));
-        model.children.iter().for_each(|child| {
+        model.children.iter().flatten().for_each(|child| {
             name_to_id
                 .entry(child.clone())
                 .or_insert(counter.fetch_add(1, Ordering::Relaxed));
         });
     });
     let mut rng = rand::rng();
+    let mut extras = FileExtras {
+        order: file_order
+            .iter()
+            .map(|name| *name_to_id.get(name).unwrap())
+            .collect(),
+        root_fields: file_tree_model.extra,
+        ..Default::default()
+    };
     // 转换模型，加入id
     let mut models: BTreeMap<u64, Model> = name_to_id
         .iter()
         .map(|(name, id)| match models.get(name) {
             Some(model) => {
-                let children = model
-                    .children
-                    .iter()
-                    .map(|child| {
-                        name_to_id
-                            .get(child)
-                            .expect(
-                                format!("child {} does not exist in name_to_id", child).as_str(),
-                            )
-                            .clone()
-                    })
-                    .collect();
-                let algorithm_str = model.algorithm.clone();
-                let mut algorithm_enum = algorithm_str.parse().unwrap_or(Algorithm::None);
-                if matches!(algorithm_enum, Algorithm::None) && randomize_algorithm {
-                    algorithm_enum = Algorithm::random(rng.random());
+                if !model.extra.is_empty() {
+                    extras.node_fields.insert(*id, model.extra.clone());
                 }
-                println!(
-                    "字符串算法：{}, 枚举算法：{:?}",
-                    algorithm_str, algorithm_enum
-                );
+                let expand_info = model.children.as_ref().map(|children| {
+                    let children = children
+                        .iter()
+                        .map(|child| {
+                            name_to_id
+                                .get(child)
+                                .expect(
+                                    format!("child {} does not exist in name_to_id", child)
+                                        .as_str(),
+                                )
+                                .clone()
+                        })
+                        .collect();
+                    let algorithm_str = model.algorithm.clone().unwrap_or_default();
+                    let mut algorithm_enum = algorithm_str.parse().unwrap_or(Algorithm::None);
+                    if matches!(algorithm_enum, Algorithm::None) && randomize_algorithm {
+                        algorithm_enum = Algorithm::random(rng.random());
+                    } else if matches!(algorithm_enum, Algorithm::None) {
+                        extras.raw_algorithms.insert(*id, algorithm_str.clone());
+                    }
+                    println!(
+                        "字符串算法：{}, 枚举算法：{:?}",
+                        algorithm_str, algorithm_enum
+                    );
+                    ExpandInfo {
+                        algorithm: algorithm_enum,
+                        children,
+                    }
+                });
                 let model = Model {
                     id: *id,
                     name: model.name.clone(),
                     ref_count: 0,
-                    expand_info: Some(ExpandInfo {
-                        algorithm: algorithm_enum,
-                        children,
-                    }),
+                    expand_info,
                     value: None,
+                    metadata: model.metadata.clone(),
                 };
                 (*id, model)
             }
@@ -85,6 +164,7 @@ pub fn load_models(file_path: &str, randomize_algorithm: bool) -> Result<TreeMod
                     ref_count: 0,
                     expand_info: None,
                     value: None,
+                    metadata: Default::default(),
                 };
                 (*id, model)
             }
@@ -117,13 +197,28 @@ pub fn load_models(file_path: &str, randomize_algorithm: bool) -> Result<TreeMod
         models,
         root_name,
         counter,
+        header: file_tree_model.header,
+        extras,
     })
 }
 
-pub fn load_data(file_path: &str) -> Result<FileData, String> {
+// JSON数据文件为名称到数值的对象，CSV和Excel文件按settings读取
+pub fn load_data(file_path: &str, settings: &DataFileSettings) -> Result<FileData, String> {
+    if has_extension(file_path, &TABLE_EXTENSIONS) {
+        let table = read_table(file_path, &settings.sheet)?;
+        return table_to_data(&table, settings)
+            .map_err(|e| format!("读取数据文件{:?}错误\n{}", file_path, e));
+    }
     let content = fs::read_to_string(file_path)
         .map_err(|e| format!("读取数据文件{:?}错误\n{}", file_path, e))?;
     let file_data = serde_json::from_str::<FileData>(&content)
         .map_err(|e| format!("解析数据文件{:?}错误\n{}", file_path, e))?;
     Ok(file_data)
 }
+
+fn has_extension(file_path: &str, extensions: &[&str]) -> bool {
+    Path::new(file_path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| extensions.contains(&extension.as_str()))
+}
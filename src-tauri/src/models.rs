@@ -1,26 +1,151 @@
+use crate::history::History;
+use crate::journal::{Journal, JournalEntry, JournalIds};
 use serde::{Deserialize, Serialize};
-use shared::{Algorithm, Model};
+use serde_json::{Map, Value};
+use shared::{Algorithm, DataFileSettings, Model, ModelHeader, NodeMetadata, SaveSettings};
 use std::collections::{BTreeMap, HashMap};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 
 // from files
 #[derive(Serialize, Deserialize, Clone)]
 pub struct FileModel {
     pub name: String,
-    pub children: Vec<String>,
-    pub algorithm: String,
+    // 叶节点没有children和algorithm，只有带说明信息的叶节点才会被写入文件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+    #[serde(default, skip_serializing_if = "NodeMetadata::is_empty")]
+    pub metadata: NodeMetadata,
+    // 文件中本程序不认识的字段，保存时原样写回
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct FileTreeModel {
+    #[serde(default, skip_serializing_if = "ModelHeader::is_empty")]
+    pub header: ModelHeader,
     pub root_name: String,
     pub data: Vec<FileModel>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// 加载时记录的、编辑过程中不会用到的文件信息，用于无损保存
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct FileExtras {
+    // 文件中定义的节点的原始顺序
+    pub order: Vec<u64>,
+    // 无法解析的算法字符串（如“待定”），算法未被修改时原样写回
+    pub raw_algorithms: HashMap<u64, String>,
+    pub node_fields: HashMap<u64, Map<String, Value>>,
+    pub root_fields: Map<String, Value>,
 }
 
 pub struct TreeModel {
     pub models: BTreeMap<u64, Model>,
     pub root_name: String,
     pub counter: AtomicU64,
+    pub header: ModelHeader,
+    pub extras: FileExtras,
+}
+
+// 撤销历史中保存的模型状态；counter不随撤销回退，撤销后新建的节点不会与重做历史中的节点id冲突
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ModelSnapshot {
+    models: BTreeMap<u64, Model>,
+    root_name: String,
+    header: ModelHeader,
+    extras: FileExtras,
+}
+
+impl ModelSnapshot {
+    pub fn models(&self) -> &BTreeMap<u64, Model> {
+        &self.models
+    }
+}
+
+impl TreeModel {
+    pub fn snapshot(&self) -> ModelSnapshot {
+        ModelSnapshot {
+            models: self.models.clone(),
+            root_name: self.root_name.clone(),
+            header: self.header.clone(),
+            extras: self.extras.clone(),
+        }
+    }
+
+    pub fn journal_ids(&self) -> JournalIds {
+        JournalIds {
+            ids: self
+                .models
+                .values()
+                .map(|model| (model.name.clone(), model.id))
+                .collect(),
+            counter: self.counter.load(Ordering::Relaxed),
+        }
+    }
+
+    // 按名称把重新加载得到的id改为journal_ids记录的id；记录中没有的名称分配新的id
+    pub fn renumber(&mut self, journal_ids: &JournalIds) {
+        let next = self
+            .models
+            .keys()
+            .chain(journal_ids.ids.values())
+            .map(|id| id + 1)
+            .max()
+            .unwrap_or_default()
+            .max(journal_ids.counter);
+        self.counter.store(next, Ordering::Relaxed);
+        let id_map = self
+            .models
+            .values()
+            .map(|model| {
+                let new_id = match journal_ids.ids.get(&model.name) {
+                    Some(id) => *id,
+                    None => self.counter.fetch_add(1, Ordering::Relaxed),
+                };
+                (model.id, new_id)
+            })
+            .collect::<HashMap<u64, u64>>();
+        let models = std::mem::take(&mut self.models);
+        self.models = models
+            .into_values()
+            .map(|mut model| {
+                model.id = id_map[&model.id];
+                if let Some(expand_info) = model.expand_info.as_mut() {
+                    for child in expand_info.children.iter_mut() {
+                        *child = id_map[child];
+                    }
+                }
+                (model.id, model)
+            })
+            .collect();
+        let extras = &mut self.extras;
+        for id in extras.order.iter_mut() {
+            *id = id_map[id];
+        }
+        extras.raw_algorithms = std::mem::take(&mut extras.raw_algorithms)
+            .into_iter()
+            .map(|(id, algorithm)| (id_map[&id], algorithm))
+            .collect();
+        extras.node_fields = std::mem::take(&mut extras.node_fields)
+            .into_iter()
+            .map(|(id, fields)| (id_map[&id], fields))
+            .collect();
+    }
+
+    // 恢复到snapshot，返回恢复之前的状态
+    pub fn restore(&mut self, snapshot: ModelSnapshot) -> ModelSnapshot {
+        let current = self.snapshot();
+        self.models = snapshot.models;
+        self.root_name = snapshot.root_name;
+        self.header = snapshot.header;
+        self.extras = snapshot.extras;
+        current
+    }
 }
 
 pub type FileData = BTreeMap<String, f64>;
@@ -30,4 +155,82 @@ pub type Data = BTreeMap<u64, f64>;
 pub struct TauriState {
     pub curr_tree_model: Option<TreeModel>,
     pub curr_file_path: Option<String>,
+    pub save_settings: SaveSettings,
+    pub data_file_settings: DataFileSettings,
+    pub journal: Option<Journal>,
+    // 第一次未保存的修改发生的时间，用于自动保存
+    pub unsaved_since: Option<Instant>,
+    pub history: History,
+    // 重放日志时建立的撤销历史与history的撤销栈和重做栈顶部有多少步相同，
+    // 这些步可以记为Undo、Redo；超出时（如撤销到保存之前）改为记录撤销或重做后的整个模型
+    pub journaled_undo: usize,
+    pub journaled_redo: usize,
+}
+
+impl TauriState {
+    // 每次修改模型后调用，before为修改之前的模型，记入撤销历史并将修改写入恢复日志
+    pub fn record(&mut self, entry: JournalEntry, before: ModelSnapshot) {
+        self.history.push(entry.label(), before);
+        self.journaled_undo = (self.journaled_undo + 1).min(self.history.undo_len());
+        self.journaled_redo = 0;
+        self.log(entry);
+    }
+
+    // 开始新的日志时调用，replayed为重放该日志中已有的记录时会建立的撤销历史
+    pub fn set_journal(&mut self, journal: Option<Journal>, replayed: &History) {
+        self.journal = journal;
+        self.journaled_undo = replayed.undo_len();
+        self.journaled_redo = replayed.redo_len();
+    }
+
+    pub fn undo(&mut self) -> Result<String, String> {
+        let tree_model = self
+            .curr_tree_model
+            .as_mut()
+            .ok_or("模型未加载".to_string())?;
+        let label = self.history.undo(tree_model)?;
+        let entry = if self.journaled_undo > 0 {
+            self.journaled_undo -= 1;
+            self.journaled_redo += 1;
+            JournalEntry::Undo
+        } else {
+            self.journaled_redo = 0;
+            JournalEntry::Restore {
+                snapshot: Box::new(tree_model.snapshot()),
+            }
+        };
+        self.log(entry);
+        Ok(label)
+    }
+
+    pub fn redo(&mut self) -> Result<String, String> {
+        let tree_model = self
+            .curr_tree_model
+            .as_mut()
+            .ok_or("模型未加载".to_string())?;
+        let label = self.history.redo(tree_model)?;
+        let entry = if self.journaled_redo > 0 {
+            self.journaled_redo -= 1;
+            self.journaled_undo += 1;
+            JournalEntry::Redo
+        } else {
+            self.journaled_undo = 0;
+            JournalEntry::Restore {
+                snapshot: Box::new(tree_model.snapshot()),
+            }
+        };
+        self.log(entry);
+        Ok(label)
+    }
+
+    fn log(&mut self, entry: JournalEntry) {
+        if let Some(journal) = self.journal.as_mut() {
+            if let Err(e) = journal.append(&entry) {
+                eprintln!("{}", e);
+            }
+        }
+        if self.unsaved_since.is_none() {
+            self.unsaved_since = Some(Instant::now());
+        }
+    }
 }
@@ -0,0 +1,1461 @@
+use crate::find_replace::find_replace_preview;
+use crate::helper::{pinyin_sort_key, suggest_new_name_add};
+use crate::journal::JournalEntry;
+use crate::models::TreeModel;
+use shared::{
+    Algorithm, ChildrenMerge, ChildrenOrder, CopySettings, DeleteResponse, ExpandInfo,
+    FindReplaceSettings, MergePreview, MergeSettings, MergeSide, Model, ModelHeader, NodeMetadata,
+    RemoveNodesResponse, RenameResponse, UnshareResponse,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::Ordering;
+
+// 对模型的所有修改操作，不依赖tauri的状态，以便在恢复日志时重放
+
+pub fn update_reference_count(models: &mut BTreeMap<u64, Model>) {
+    let mut reference_counts = models
+        .iter()
+        .map(|(id, _)| (*id, 0))
+        .collect::<HashMap<u64, u64>>();
+    println!("更新reference count");
+    for (_, model) in models.iter() {
+        if let Some(expand_info) = &model.expand_info {
+            for child_id in expand_info.children.iter() {
+                if let Some(reference_count) = reference_counts.get_mut(child_id) {
+                    *reference_count += 1;
+                } else {
+                    eprintln!(
+                        "错误：在更新reference count时在reference_counts中未找到子节点{}",
+                        child_id
+                    );
+                }
+            }
+        }
+    }
+    for (id, reference_count) in reference_counts.iter() {
+        println!("更新节点{}的reference count为{}", id, reference_count);
+        models.get_mut(id).unwrap().ref_count = *reference_count;
+    }
+}
+
+pub fn replace_node_and_update_children(
+    id: u64,
+    new_id: Option<u64>,
+    models: &mut BTreeMap<u64, Model>,
+) -> Result<HashSet<u64>, String> {
+    println!("删除节点：{}", id);
+    models
+        .remove(&id)
+        .ok_or(format!("未找到要删除的模型：{}", id))?;
+    let mut ids_to_update = HashSet::new();
+    for (_, model) in models.iter_mut() {
+        if let Some(expand_info) = model.expand_info.as_mut() {
+            if expand_info.children.contains(&id) {
+                if let Some(new_id) = new_id {
+                    expand_info.children.iter_mut().for_each(|child_id| {
+                        if *child_id == id {
+                            *child_id = new_id;
+                        }
+                    });
+                } else {
+                    expand_info.children.retain(|child_id| *child_id != id);
+                }
+                ids_to_update.insert(model.id);
+            }
+        }
+    }
+    update_reference_count(models);
+    Ok(ids_to_update)
+}
+
+pub fn rename(
+    tree_model: &mut TreeModel,
+    id: u64,
+    new_name: &str,
+) -> Result<RenameResponse, String> {
+    // 1. If has children, and name is duplicated, return error
+    // 2. If has no children, and name is duplicated, delete current model and replace other models' children -> (delete message, parent update messages)
+    // 3. If name is not duplicated, rename -> (rename message)
+    let models = &mut tree_model.models;
+    let new_name_owner = models.iter().find(|(_, model)| model.name == new_name);
+    let new_name_owner_id = new_name_owner.map(|(id, _)| *id);
+    let model = models.get_mut(&id).ok_or(format!("未找到模型{}", id))?;
+    if new_name == "" {
+        Err("重命名失败：新名称为空".to_string())?;
+    }
+    if model.name == new_name {
+        Err("重命名失败：新名称与旧名称相同".to_string())?;
+    }
+    if let Some(new_name_owner_id) = new_name_owner_id {
+        if model.expand_info.is_some() {
+            Err("重命名失败：新名称已存在".to_string())?;
+        }
+        let mut ids_to_update =
+            replace_node_and_update_children(id, Some(new_name_owner_id), models)?;
+        ids_to_update.insert(new_name_owner_id);
+        Ok(RenameResponse::RemoveSelfUpdateRelated {
+            id_to_remove: id,
+            ids_to_update: ids_to_update.into_iter().collect(),
+        })
+    } else {
+        model.name = new_name.to_string();
+        Ok(RenameResponse::RenameSelf(new_name.to_string()))
+    }
+}
+
+pub fn delete(
+    tree_model: &mut TreeModel,
+    id: u64,
+    parent: Option<u64>,
+) -> Result<DeleteResponse, String> {
+    if parent.is_none() {
+        assert_eq!(id, 0);
+        Err("根节点不可删除".to_string())?;
+    }
+    let models = &mut tree_model.models;
+
+    // if the reference count is 1, we can delete the model, unless it is root
+    let model = models.get_mut(&id).ok_or(format!("未找到模型{}", id))?;
+    if model.ref_count == 1 && id != 0 {
+        let ids_to_update = replace_node_and_update_children(id, None, models)?;
+        Ok(DeleteResponse {
+            id_to_remove: Some(id),
+            ids_to_update: ids_to_update.into_iter().collect(),
+        })
+    } else {
+        let parent_model = models
+            .get_mut(&parent.unwrap())
+            .ok_or(format!("未找到模型{}", parent.unwrap()))?;
+        let parent_children = &mut parent_model
+            .expand_info
+            .as_mut()
+            .ok_or("父节点无子节点".to_string())?
+            .children;
+        parent_children.remove(
+            parent_children
+                .iter()
+                .position(|x| *x == id)
+                .ok_or("未找到要删除的模型")?,
+        );
+        update_reference_count(models);
+        Ok(DeleteResponse {
+            id_to_remove: None,
+            ids_to_update: vec![parent.unwrap(), id],
+        })
+    }
+}
+
+// id及其所有子孙节点
+pub fn descendants(models: &BTreeMap<u64, Model>, id: u64) -> HashSet<u64> {
+    let mut visited = HashSet::new();
+    let mut stack = vec![id];
+    while let Some(id) = stack.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        if let Some(expand_info) = models.get(&id).and_then(|model| model.expand_info.as_ref()) {
+            stack.extend(expand_info.children.iter().copied());
+        }
+    }
+    visited
+}
+
+// 从根节点无法到达的节点，按id排列
+pub fn unreachable_nodes(models: &BTreeMap<u64, Model>) -> Vec<u64> {
+    let reachable = descendants(models, 0);
+    models
+        .keys()
+        .filter(|id| !reachable.contains(id))
+        .copied()
+        .collect()
+}
+
+// 删除parent到id的边后，id的子树中将无法从根节点到达的节点，即删除子树时会删除的节点
+pub fn subtree_deletion(
+    models: &BTreeMap<u64, Model>,
+    id: u64,
+    parent: u64,
+) -> Result<Vec<u64>, String> {
+    if id == 0 {
+        Err("根节点不可删除".to_string())?;
+    }
+    let mut models = models.clone();
+    let children = &mut models
+        .get_mut(&parent)
+        .ok_or(format!("未找到模型{}", parent))?
+        .expand_info
+        .as_mut()
+        .ok_or("父节点无子节点".to_string())?
+        .children;
+    let position = children
+        .iter()
+        .position(|child| *child == id)
+        .ok_or("父节点下没有该节点".to_string())?;
+    children.remove(position);
+    let reachable = descendants(&models, 0);
+    let mut removed = descendants(&models, id)
+        .into_iter()
+        .filter(|id| !reachable.contains(id))
+        .collect::<Vec<u64>>();
+    removed.sort();
+    Ok(removed)
+}
+
+// 从模型中删除这些节点，并从其余节点的子节点中去掉对它们的引用；返回子节点或引用计数有变化的节点
+fn remove_nodes(tree_model: &mut TreeModel, removed: &[u64]) -> RemoveNodesResponse {
+    let models = &mut tree_model.models;
+    let removed_set = removed.iter().copied().collect::<HashSet<u64>>();
+    let mut ids_to_update = HashSet::new();
+    for id in removed {
+        if let Some(expand_info) = models.remove(id).and_then(|model| model.expand_info) {
+            ids_to_update.extend(expand_info.children);
+        }
+    }
+    for model in models.values_mut() {
+        if let Some(expand_info) = model.expand_info.as_mut() {
+            let count = expand_info.children.len();
+            expand_info
+                .children
+                .retain(|child| !removed_set.contains(child));
+            if expand_info.children.len() != count {
+                ids_to_update.insert(model.id);
+            }
+        }
+    }
+    update_reference_count(models);
+    let mut ids_to_update = ids_to_update
+        .into_iter()
+        .filter(|id| !removed_set.contains(id))
+        .collect::<Vec<u64>>();
+    ids_to_update.sort();
+    RemoveNodesResponse {
+        removed: removed.to_vec(),
+        ids_to_update,
+    }
+}
+
+// 删除parent下的id，以及其子树中因此无法从根节点到达的所有节点；仍被其他节点引用的部分保留
+pub fn delete_subtree(
+    tree_model: &mut TreeModel,
+    id: u64,
+    parent: u64,
+) -> Result<RemoveNodesResponse, String> {
+    let removed = subtree_deletion(&tree_model.models, id, parent)?;
+    let children = &mut tree_model
+        .models
+        .get_mut(&parent)
+        .unwrap()
+        .expand_info
+        .as_mut()
+        .unwrap()
+        .children;
+    let position = children.iter().position(|child| *child == id).unwrap();
+    children.remove(position);
+    let mut response = remove_nodes(tree_model, &removed);
+    if !response.ids_to_update.contains(&parent) {
+        response.ids_to_update.push(parent);
+    }
+    Ok(response)
+}
+
+// 删除指定的不可达节点，其中仍可从根节点到达的节点不能删除
+pub fn remove_unreachable(
+    tree_model: &mut TreeModel,
+    ids: &[u64],
+) -> Result<RemoveNodesResponse, String> {
+    let reachable = descendants(&tree_model.models, 0);
+    for id in ids {
+        if !tree_model.models.contains_key(id) {
+            Err(format!("未找到模型{}", id))?;
+        }
+        if reachable.contains(id) {
+            Err(format!("删除失败：节点{}仍可从根节点到达", id))?;
+        }
+    }
+    Ok(remove_nodes(tree_model, ids))
+}
+
+// 删除所有从根节点无法到达的节点
+pub fn purge_unreachable(tree_model: &mut TreeModel) -> Result<RemoveNodesResponse, String> {
+    let removed = unreachable_nodes(&tree_model.models);
+    Ok(remove_nodes(tree_model, &removed))
+}
+
+// id及其所有祖先节点
+pub fn ancestors(models: &BTreeMap<u64, Model>, id: u64) -> HashSet<u64> {
+    let mut parents = HashMap::<u64, Vec<u64>>::new();
+    for model in models.values() {
+        if let Some(expand_info) = &model.expand_info {
+            for child in expand_info.children.iter() {
+                parents.entry(*child).or_default().push(model.id);
+            }
+        }
+    }
+    let mut visited = HashSet::new();
+    let mut stack = vec![id];
+    while let Some(id) = stack.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        if let Some(parents) = parents.get(&id) {
+            stack.extend(parents.iter().copied());
+        }
+    }
+    visited
+}
+
+// 将已有的节点加为parent的子节点，使其被共享；返回parent
+pub fn link_child(tree_model: &mut TreeModel, id: u64, parent: u64) -> Result<u64, String> {
+    let models = &mut tree_model.models;
+    if !models.contains_key(&id) {
+        Err(format!("未找到模型{}", id))?;
+    }
+    if descendants(models, id).contains(&parent) {
+        Err("添加失败：不能将自身或祖先节点添加为子节点".to_string())?;
+    }
+    let children = &mut models
+        .get_mut(&parent)
+        .ok_or(format!("未找到模型{}", parent))?
+        .expand_info
+        .as_mut()
+        .ok_or("添加失败：模型无子节点".to_string())?
+        .children;
+    if children.contains(&id) {
+        Err("添加失败：该节点已是子节点".to_string())?;
+    }
+    children.push(id);
+    update_reference_count(models);
+    Ok(parent)
+}
+
+// 将节点从old_parent下移到new_parent下，节点的id和子树不变；节点被多个父节点共享时只移动这一条边。
+// 返回需要更新的节点
+pub fn move_node(
+    tree_model: &mut TreeModel,
+    id: u64,
+    old_parent: u64,
+    new_parent: u64,
+    index: Option<u64>,
+) -> Result<Vec<u64>, String> {
+    let models = &mut tree_model.models;
+    if descendants(models, id).contains(&new_parent) {
+        Err("移动失败：不能移动到自身或其子孙节点下".to_string())?;
+    }
+    let new_parent_children = &models
+        .get(&new_parent)
+        .ok_or(format!("未找到模型{}", new_parent))?
+        .expand_info
+        .as_ref()
+        .ok_or("移动失败：目标节点无子节点".to_string())?
+        .children;
+    if old_parent != new_parent && new_parent_children.contains(&id) {
+        Err("移动失败：目标节点下已有该节点".to_string())?;
+    }
+    let old_parent_children = &mut models
+        .get_mut(&old_parent)
+        .ok_or(format!("未找到模型{}", old_parent))?
+        .expand_info
+        .as_mut()
+        .ok_or("移动失败：原父节点无子节点".to_string())?
+        .children;
+    let position = old_parent_children
+        .iter()
+        .position(|child| *child == id)
+        .ok_or("移动失败：原父节点下没有该节点".to_string())?;
+    old_parent_children.remove(position);
+    let new_parent_children = &mut models
+        .get_mut(&new_parent)
+        .unwrap()
+        .expand_info
+        .as_mut()
+        .unwrap()
+        .children;
+    let index = index
+        .map(|index| index as usize)
+        .unwrap_or(new_parent_children.len())
+        .min(new_parent_children.len());
+    new_parent_children.insert(index, id);
+    update_reference_count(models);
+    Ok(vec![old_parent, new_parent, id])
+}
+
+pub fn add(tree_model: &mut TreeModel, id: u64) -> Result<u64, String> {
+    let model = tree_model
+        .models
+        .get_mut(&id)
+        .ok_or(format!("添加错误：未找到模型{}", id))?;
+    let expand_info = model
+        .expand_info
+        .as_mut()
+        .ok_or("添加失败：模型无子节点".to_string())?;
+    let new_id = tree_model.counter.fetch_add(1, Ordering::Relaxed);
+    expand_info.children.push(new_id);
+    let new_name = suggest_new_name_add(&tree_model.models);
+    let new_model = Model {
+        id: new_id,
+        name: new_name,
+        ref_count: 1, // the only parent is the current model
+        expand_info: None,
+        value: None,
+        metadata: Default::default(),
+    };
+    tree_model.models.insert(new_id, new_model);
+    Ok(id)
+}
+
+// 复制id的子树并加到parent下，原节点也在parent下时副本紧跟在原节点之后，返回副本的根节点。
+// 子树内部共享的节点在副本中仍然共享；新名称已存在时，叶节点与已有节点合并（与重命名相同），
+// 非叶节点则复制失败
+pub fn copy_subtree(
+    tree_model: &mut TreeModel,
+    id: u64,
+    parent: u64,
+    settings: &CopySettings,
+) -> Result<u64, String> {
+    let counter = tree_model.counter.load(Ordering::Relaxed);
+    let result = copy_subtree_checked(tree_model, id, parent, settings);
+    restore_counter_on_err(tree_model, counter, result)
+}
+
+// 失败的操作不写入恢复日志，其间分配的id要退回，否则重放日志时之后新建节点的id会与记录的不同
+fn restore_counter_on_err<T>(
+    tree_model: &TreeModel,
+    counter: u64,
+    result: Result<T, String>,
+) -> Result<T, String> {
+    if result.is_err() {
+        tree_model.counter.store(counter, Ordering::Relaxed);
+    }
+    result
+}
+
+fn copy_subtree_checked(
+    tree_model: &mut TreeModel,
+    id: u64,
+    parent: u64,
+    settings: &CopySettings,
+) -> Result<u64, String> {
+    let parent_children = &tree_model
+        .models
+        .get(&parent)
+        .ok_or(format!("未找到模型{}", parent))?
+        .expand_info
+        .as_ref()
+        .ok_or("复制失败：目标节点无子节点".to_string())?
+        .children;
+    let mut names = tree_model
+        .models
+        .values()
+        .map(|model| (model.name.clone(), model.id))
+        .collect::<HashMap<String, u64>>();
+    let mut copied = HashMap::new();
+    let mut new_models = Vec::new();
+    let new_id = copy_node(
+        tree_model,
+        id,
+        settings,
+        &mut names,
+        &mut copied,
+        &mut new_models,
+    )?;
+    if parent_children.contains(&new_id) {
+        Err("复制失败：目标节点下已有该节点".to_string())?;
+    }
+    let position = parent_children.iter().position(|child| *child == id);
+    for model in new_models {
+        tree_model.models.insert(model.id, model);
+    }
+    let parent_children = &mut tree_model
+        .models
+        .get_mut(&parent)
+        .unwrap()
+        .expand_info
+        .as_mut()
+        .unwrap()
+        .children;
+    match position {
+        Some(position) => parent_children.insert(position + 1, new_id),
+        None => parent_children.push(new_id),
+    }
+    update_reference_count(&mut tree_model.models);
+    Ok(new_id)
+}
+
+// 在parent下将共享的节点换成名为new_name的副本，其他父节点仍然引用原节点。
+// copy_settings为None时副本直接引用原节点的子节点，否则按copy_settings复制整个子树
+pub fn unshare(
+    tree_model: &mut TreeModel,
+    id: u64,
+    parent: u64,
+    new_name: &str,
+    copy_settings: Option<&CopySettings>,
+) -> Result<UnshareResponse, String> {
+    let counter = tree_model.counter.load(Ordering::Relaxed);
+    let result = unshare_checked(tree_model, id, parent, new_name, copy_settings);
+    restore_counter_on_err(tree_model, counter, result)
+}
+
+fn unshare_checked(
+    tree_model: &mut TreeModel,
+    id: u64,
+    parent: u64,
+    new_name: &str,
+    copy_settings: Option<&CopySettings>,
+) -> Result<UnshareResponse, String> {
+    let new_name = new_name.trim();
+    let model = tree_model
+        .models
+        .get(&id)
+        .ok_or(format!("未找到模型{}", id))?;
+    if model.ref_count < 2 {
+        Err("拆分失败：该节点没有被共享".to_string())?;
+    }
+    if new_name.is_empty() {
+        Err("拆分失败：新名称为空".to_string())?;
+    }
+    let mut names = tree_model
+        .models
+        .values()
+        .map(|model| (model.name.clone(), model.id))
+        .collect::<HashMap<String, u64>>();
+    if names.contains_key(new_name) {
+        Err(format!("拆分失败：名称\"{}\"已存在", new_name))?;
+    }
+    let position = tree_model
+        .models
+        .get(&parent)
+        .ok_or(format!("未找到模型{}", parent))?
+        .expand_info
+        .as_ref()
+        .and_then(|expand_info| expand_info.children.iter().position(|child| *child == id))
+        .ok_or("拆分失败：父节点下没有该节点".to_string())?;
+    let new_id = tree_model.counter.fetch_add(1, Ordering::Relaxed);
+    names.insert(new_name.to_string(), new_id);
+    let mut copied = HashMap::from([(id, new_id)]);
+    let mut new_models = Vec::new();
+    let expand_info = match (&model.expand_info, copy_settings) {
+        (Some(expand_info), Some(settings)) => {
+            let mut children = Vec::new();
+            for child in expand_info.children.iter() {
+                children.push(copy_node(
+                    tree_model,
+                    *child,
+                    settings,
+                    &mut names,
+                    &mut copied,
+                    &mut new_models,
+                )?);
+            }
+            Some(ExpandInfo {
+                algorithm: expand_info.algorithm.clone(),
+                children,
+            })
+        }
+        (expand_info, _) => expand_info.clone(),
+    };
+    new_models.push(Model {
+        id: new_id,
+        name: new_name.to_string(),
+        ref_count: 0,
+        expand_info,
+        value: None,
+        metadata: model.metadata.clone(),
+    });
+    let created = new_models.len() as u64;
+    for model in new_models {
+        tree_model.models.insert(model.id, model);
+    }
+    tree_model
+        .models
+        .get_mut(&parent)
+        .unwrap()
+        .expand_info
+        .as_mut()
+        .unwrap()
+        .children[position] = new_id;
+    update_reference_count(&mut tree_model.models);
+    Ok(UnshareResponse {
+        new_id,
+        created,
+        ids_to_update: vec![parent, id],
+    })
+}
+
+// 新节点先放在new_models中，全部成功后才加入模型
+fn copy_node(
+    tree_model: &TreeModel,
+    id: u64,
+    settings: &CopySettings,
+    names: &mut HashMap<String, u64>,
+    copied: &mut HashMap<u64, u64>,
+    new_models: &mut Vec<Model>,
+) -> Result<u64, String> {
+    if let Some(new_id) = copied.get(&id) {
+        return Ok(*new_id);
+    }
+    let model = tree_model
+        .models
+        .get(&id)
+        .ok_or(format!("未找到模型{}", id))?;
+    if model.expand_info.is_none() && model.ref_count > 1 && !settings.duplicate_shared_leaves {
+        copied.insert(id, id);
+        return Ok(id);
+    }
+    let new_name = settings.rule.apply(&model.name);
+    if let Some(owner) = names.get(&new_name) {
+        if model.expand_info.is_some() {
+            Err(format!("复制失败：名称\"{}\"已存在", new_name))?;
+        }
+        copied.insert(id, *owner);
+        return Ok(*owner);
+    }
+    let new_id = tree_model.counter.fetch_add(1, Ordering::Relaxed);
+    names.insert(new_name.clone(), new_id);
+    copied.insert(id, new_id);
+    let expand_info = match &model.expand_info {
+        Some(expand_info) => {
+            let mut children = Vec::new();
+            for child in expand_info.children.iter() {
+                children.push(copy_node(
+                    tree_model, *child, settings, names, copied, new_models,
+                )?);
+            }
+            Some(ExpandInfo {
+                algorithm: expand_info.algorithm.clone(),
+                children,
+            })
+        }
+        None => None,
+    };
+    new_models.push(Model {
+        id: new_id,
+        name: new_name,
+        ref_count: 0,
+        expand_info,
+        value: None,
+        metadata: model.metadata.clone(),
+    });
+    Ok(new_id)
+}
+
+pub fn update_algorithm(
+    tree_model: &mut TreeModel,
+    id: u64,
+    new_algorithm: Algorithm,
+) -> Result<u64, String> {
+    let model = tree_model
+        .models
+        .get_mut(&id)
+        .ok_or(format!("未找到模型{}", id))?;
+    if let Some(expand_info) = model.expand_info.as_mut() {
+        expand_info.algorithm = new_algorithm;
+    } else {
+        Err("更新算法失败：模型无子节点".to_string())?;
+    }
+    Ok(id)
+}
+
+fn children_mut(tree_model: &mut TreeModel, id: u64) -> Result<&mut Vec<u64>, String> {
+    Ok(&mut tree_model
+        .models
+        .get_mut(&id)
+        .ok_or(format!("未找到模型{}", id))?
+        .expand_info
+        .as_mut()
+        .ok_or("调整顺序失败：模型无子节点".to_string())?
+        .children)
+}
+
+// 将第from个子节点移到第to个位置，返回新的子节点顺序
+pub fn reorder_child(
+    tree_model: &mut TreeModel,
+    id: u64,
+    from: usize,
+    to: usize,
+) -> Result<Vec<u64>, String> {
+    let children = children_mut(tree_model, id)?;
+    if from >= children.len() || to >= children.len() {
+        Err("调整顺序失败：位置超出范围".to_string())?;
+    }
+    let child = children.remove(from);
+    children.insert(to, child);
+    Ok(children.clone())
+}
+
+// 排序是稳定的，相同的键保持原有顺序；返回新的子节点顺序
+pub fn sort_children(
+    tree_model: &mut TreeModel,
+    id: u64,
+    order: ChildrenOrder,
+    descending: bool,
+) -> Result<Vec<u64>, String> {
+    let mut children = children_mut(tree_model, id)?.clone();
+    let models = &tree_model.models;
+    let name = |id: &u64| {
+        models
+            .get(id)
+            .map(|model| model.name.clone())
+            .unwrap_or_default()
+    };
+    match order {
+        ChildrenOrder::Name => children.sort_by_cached_key(name),
+        ChildrenOrder::Pinyin => {
+            children.sort_by_cached_key(|id| (pinyin_sort_key(&name(id)), name(id)))
+        }
+        ChildrenOrder::Value => {
+            let value = |id: &u64| models.get(id).and_then(|model| model.value);
+            children.sort_by(|a, b| match (value(a), value(b)) {
+                (Some(a), Some(b)) if descending => b.total_cmp(&a),
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            });
+        }
+    }
+    if descending && order != ChildrenOrder::Value {
+        children.reverse();
+    }
+    set_children_order(tree_model, id, children)
+}
+
+// 子节点必须与原来的相同，只是顺序不同
+pub fn set_children_order(
+    tree_model: &mut TreeModel,
+    id: u64,
+    new_children: Vec<u64>,
+) -> Result<Vec<u64>, String> {
+    let children = children_mut(tree_model, id)?;
+    let mut old_sorted = children.clone();
+    let mut new_sorted = new_children.clone();
+    old_sorted.sort();
+    new_sorted.sort();
+    if old_sorted != new_sorted {
+        Err("调整顺序失败：子节点已变化".to_string())?;
+    }
+    *children = new_children.clone();
+    Ok(new_children)
+}
+
+fn check_merge(models: &BTreeMap<u64, Model>, left: u64, right: u64) -> Result<(), String> {
+    if !models.contains_key(&left) {
+        Err(format!("未找到模型{}", left))?;
+    }
+    if !models.contains_key(&right) {
+        Err(format!("未找到模型{}", right))?;
+    }
+    if left == right {
+        Err("合并失败：不能与自身合并".to_string())?;
+    }
+    if right == 0 {
+        Err("合并失败：根节点只能作为保留的节点".to_string())?;
+    }
+    if descendants(models, left).contains(&right) || descendants(models, right).contains(&left) {
+        Err("合并失败：两个节点之间有祖先与子孙关系".to_string())?;
+    }
+    Ok(())
+}
+
+pub fn merge_preview(
+    models: &BTreeMap<u64, Model>,
+    left: u64,
+    right: u64,
+) -> Result<MergePreview, String> {
+    check_merge(models, left, right)?;
+    let children = |id: u64| {
+        models[&id]
+            .expand_info
+            .as_ref()
+            .map(|expand_info| expand_info.children.clone())
+            .unwrap_or_default()
+    };
+    let left_children = children(left);
+    let right_children = children(right);
+    let mut rewritten_parents = Vec::new();
+    let mut shared_parents = Vec::new();
+    for model in models.values() {
+        if let Some(expand_info) = &model.expand_info {
+            if expand_info.children.contains(&right) {
+                if expand_info.children.contains(&left) {
+                    shared_parents.push(model.name.clone());
+                } else {
+                    rewritten_parents.push(model.name.clone());
+                }
+            }
+        }
+    }
+    let algorithm = |id: u64| {
+        models[&id]
+            .expand_info
+            .as_ref()
+            .map(|expand_info| expand_info.algorithm.clone())
+    };
+    Ok(MergePreview {
+        left_name: models[&left].name.clone(),
+        right_name: models[&right].name.clone(),
+        rewritten_parents,
+        shared_parents,
+        left_algorithm: algorithm(left),
+        right_algorithm: algorithm(right),
+        left_children: left_children.len() as u64,
+        right_children: right_children.len() as u64,
+        common_children: right_children
+            .iter()
+            .filter(|child| left_children.contains(child))
+            .count() as u64,
+    })
+}
+
+// 将right合并到left，所有对right的引用改为引用left（已引用left的节点只去掉对right的引用），
+// 说明信息为空的字段用right的补充
+pub fn merge_nodes(
+    tree_model: &mut TreeModel,
+    left: u64,
+    right: u64,
+    settings: &MergeSettings,
+) -> Result<RemoveNodesResponse, String> {
+    let models = &mut tree_model.models;
+    check_merge(models, left, right)?;
+    let right_model = models.remove(&right).unwrap();
+    // 子节点被丢弃时，它们的引用计数也会变化
+    let mut ids_to_update: HashSet<u64> = right_model
+        .expand_info
+        .as_ref()
+        .map(|expand_info| expand_info.children.iter().copied().collect())
+        .unwrap_or_default();
+    ids_to_update.insert(left);
+    let left_model = models.get_mut(&left).unwrap();
+    let expand_info = match (left_model.expand_info.take(), right_model.expand_info) {
+        (Some(mut left_info), Some(right_info)) => {
+            if settings.algorithm == MergeSide::Right {
+                left_info.algorithm = right_info.algorithm;
+            }
+            match settings.children {
+                ChildrenMerge::Union => {
+                    for child in right_info.children {
+                        if !left_info.children.contains(&child) {
+                            left_info.children.push(child);
+                        }
+                    }
+                }
+                ChildrenMerge::KeepLeft => {}
+                ChildrenMerge::KeepRight => left_info.children = right_info.children,
+            }
+            Some(left_info)
+        }
+        (left_info, right_info) => match settings.children {
+            ChildrenMerge::KeepLeft => left_info,
+            ChildrenMerge::KeepRight => right_info,
+            ChildrenMerge::Union => left_info.or(right_info),
+        },
+    };
+    left_model.expand_info = expand_info;
+    if let Some(expand_info) = left_model.expand_info.as_mut() {
+        dedup_children(&mut expand_info.children);
+    }
+    let metadata = &mut left_model.metadata;
+    let right_metadata = right_model.metadata;
+    for (field, right_field) in [
+        (&mut metadata.description, right_metadata.description),
+        (&mut metadata.unit, right_metadata.unit),
+        (&mut metadata.expected_range, right_metadata.expected_range),
+        (&mut metadata.notes, right_metadata.notes),
+    ] {
+        if field.is_empty() {
+            *field = right_field;
+        }
+    }
+    if metadata.references.is_empty() {
+        metadata.references = right_metadata.references;
+    }
+    for tag in right_metadata.tags {
+        if !metadata.tags.contains(&tag) {
+            metadata.tags.push(tag);
+        }
+    }
+    for model in models.values_mut() {
+        if let Some(expand_info) = model.expand_info.as_mut() {
+            if !expand_info.children.contains(&right) {
+                continue;
+            }
+            for child in expand_info.children.iter_mut() {
+                if *child == right {
+                    *child = left;
+                }
+            }
+            // 同时引用两个节点的父节点只保留left第一次出现的位置
+            dedup_children(&mut expand_info.children);
+            ids_to_update.insert(model.id);
+        }
+    }
+    update_reference_count(models);
+    Ok(RemoveNodesResponse {
+        removed: vec![right],
+        ids_to_update: ids_to_update.into_iter().collect(),
+    })
+}
+
+// 去掉重复的子节点，保留第一次出现的位置
+fn dedup_children(children: &mut Vec<u64>) {
+    let mut seen = HashSet::new();
+    children.retain(|child| seen.insert(*child));
+}
+
+// 批量替换节点名称：与逐个重命名相同，叶节点改为已有的名称时合并到同名的节点。
+// 先检查全部重名，有任何一处无法合并时不做修改
+pub fn find_replace(
+    tree_model: &mut TreeModel,
+    settings: &FindReplaceSettings,
+) -> Result<RemoveNodesResponse, String> {
+    let preview = find_replace_preview(&tree_model.models, settings)?;
+    if preview.changes.is_empty() {
+        Err("没有名称包含要查找的内容".to_string())?;
+    }
+    if let Some(error) = preview
+        .collisions
+        .iter()
+        .find_map(|collision| collision.error.clone())
+    {
+        Err(format!("替换失败：{}", error))?;
+    }
+    let models = &mut tree_model.models;
+    let merged = preview
+        .collisions
+        .iter()
+        .flat_map(|collision| collision.merged.iter().map(|id| (*id, collision.target)))
+        .collect::<Vec<(u64, u64)>>();
+    let mut ids_to_update = HashSet::new();
+    for change in preview.changes {
+        if merged.iter().all(|(id, _)| *id != change.id) {
+            models.get_mut(&change.id).unwrap().name = change.new_name;
+            ids_to_update.insert(change.id);
+        }
+    }
+    let mut removed = Vec::new();
+    for (id, target) in merged {
+        let parents = replace_node_and_update_children(id, Some(target), models)?;
+        // 同时引用两个节点的父节点只保留一个引用
+        for parent in parents.iter() {
+            if let Some(expand_info) = models
+                .get_mut(parent)
+                .and_then(|model| model.expand_info.as_mut())
+            {
+                let mut seen = HashSet::new();
+                expand_info.children.retain(|child| seen.insert(*child));
+            }
+        }
+        ids_to_update.extend(parents);
+        ids_to_update.insert(target);
+        removed.push(id);
+    }
+    update_reference_count(models);
+    ids_to_update.retain(|id| !removed.contains(id));
+    Ok(RemoveNodesResponse {
+        removed,
+        ids_to_update: ids_to_update.into_iter().collect(),
+    })
+}
+
+// 有子节点时清空子节点，没有时使其可以添加子节点；是否需要用户确认由调用方决定
+pub fn can_expand_toggling(tree_model: &mut TreeModel, id: u64) -> Result<u64, String> {
+    let model = tree_model
+        .models
+        .get_mut(&id)
+        .ok_or(format!("未找到模型{}", id))?;
+    if model.expand_info.is_some() {
+        model.expand_info = None;
+        update_reference_count(&mut tree_model.models);
+    } else {
+        model.expand_info = Some(ExpandInfo {
+            algorithm: Algorithm::None,
+            children: vec![],
+        });
+    }
+    Ok(id)
+}
+
+pub fn update_metadata(
+    tree_model: &mut TreeModel,
+    id: u64,
+    metadata: NodeMetadata,
+) -> Result<u64, String> {
+    let model = tree_model
+        .models
+        .get_mut(&id)
+        .ok_or(format!("未找到模型{}", id))?;
+    model.metadata = metadata;
+    Ok(id)
+}
+
+// 创建和修改时间只由保存时维护，不接受手动修改
+pub fn update_header(tree_model: &mut TreeModel, header: ModelHeader) {
+    let ModelHeader {
+        created, modified, ..
+    } = tree_model.header.clone();
+    tree_model.header = ModelHeader {
+        created,
+        modified,
+        ..header
+    };
+}
+
+pub fn apply(tree_model: &mut TreeModel, entry: &JournalEntry) -> Result<(), String> {
+    match entry {
+        JournalEntry::Rename { id, new_name } => {
+            rename(tree_model, *id, new_name)?;
+        }
+        JournalEntry::Delete { id, parent } => {
+            delete(tree_model, *id, *parent)?;
+        }
+        JournalEntry::DeleteSubtree { id, parent } => {
+            delete_subtree(tree_model, *id, *parent)?;
+        }
+        JournalEntry::RemoveUnreachable { ids } => {
+            remove_unreachable(tree_model, ids)?;
+        }
+        JournalEntry::PurgeUnreachable => {
+            purge_unreachable(tree_model)?;
+        }
+        JournalEntry::Move {
+            id,
+            old_parent,
+            new_parent,
+            index,
+        } => {
+            move_node(tree_model, *id, *old_parent, *new_parent, *index)?;
+        }
+        JournalEntry::Unshare {
+            id,
+            parent,
+            new_name,
+            copy_settings,
+        } => {
+            unshare(tree_model, *id, *parent, new_name, copy_settings.as_ref())?;
+        }
+        JournalEntry::Merge {
+            left,
+            right,
+            settings,
+        } => {
+            merge_nodes(tree_model, *left, *right, settings)?;
+        }
+        JournalEntry::FindReplace { settings } => {
+            find_replace(tree_model, settings)?;
+        }
+        JournalEntry::LinkChild { id, parent } => {
+            link_child(tree_model, *id, *parent)?;
+        }
+        JournalEntry::Add { id } => {
+            add(tree_model, *id)?;
+        }
+        JournalEntry::CopySubtree {
+            id,
+            parent,
+            settings,
+        } => {
+            copy_subtree(tree_model, *id, *parent, settings)?;
+        }
+        JournalEntry::ReorderChildren { id, children } => {
+            set_children_order(tree_model, *id, children.clone())?;
+        }
+        JournalEntry::UpdateAlgorithm { id, new_algorithm } => {
+            update_algorithm(tree_model, *id, new_algorithm.clone())?;
+        }
+        JournalEntry::CanExpandToggling { id } => {
+            can_expand_toggling(tree_model, *id)?;
+        }
+        JournalEntry::UpdateMetadata { id, metadata } => {
+            update_metadata(tree_model, *id, metadata.clone())?;
+        }
+        JournalEntry::UpdateHeader { header } => {
+            update_header(tree_model, header.clone());
+        }
+        JournalEntry::Restore { snapshot } => {
+            tree_model.restore(snapshot.as_ref().clone());
+        }
+        // 撤销和重做依赖撤销历史，由history::replay处理
+        JournalEntry::Undo | JournalEntry::Redo => {
+            Err("撤销和重做无法单独重放".to_string())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::build_tree_model;
+    use shared::{FindMode, RenameRule};
+
+    fn load(json: &str) -> TreeModel {
+        build_tree_model(serde_json::from_str(json).unwrap(), false).unwrap()
+    }
+
+    fn id_of(tree_model: &TreeModel, name: &str) -> u64 {
+        tree_model
+            .models
+            .values()
+            .find(|model| model.name == name)
+            .unwrap()
+            .id
+    }
+
+    fn counter(tree_model: &TreeModel) -> u64 {
+        tree_model.counter.load(Ordering::Relaxed)
+    }
+
+    // 子树中的非叶节点Y的副本名称已存在，复制在分配了X副本的id之后失败
+    #[test]
+    fn failed_copy_keeps_counter() {
+        let mut tree_model = load(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["X", "Y副本"], "algorithm": "取最小值"},
+                    {"name": "X", "children": ["Y", "Z"], "algorithm": "取最大值"},
+                    {"name": "Y", "children": ["y1"], "algorithm": "取最大值"},
+                    {"name": "Y副本", "children": ["y2"], "algorithm": "取最大值"}
+                ]
+            }"#,
+        );
+        let settings = CopySettings {
+            rule: RenameRule::Suffix("副本".to_string()),
+            duplicate_shared_leaves: false,
+        };
+        let before = counter(&tree_model);
+        let count = tree_model.models.len();
+        let (x, root) = (id_of(&tree_model, "X"), id_of(&tree_model, "Root"));
+        assert!(copy_subtree(&mut tree_model, x, root, &settings).is_err());
+        assert_eq!(counter(&tree_model), before);
+        assert_eq!(tree_model.models.len(), count);
+    }
+
+    // 共享节点S拆分时复制子树，子节点T的副本名称已存在，拆分在分配了新id之后失败
+    #[test]
+    fn failed_unshare_keeps_counter() {
+        let mut tree_model = load(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["A", "B", "T副本"], "algorithm": "取最小值"},
+                    {"name": "A", "children": ["S"], "algorithm": "取最大值"},
+                    {"name": "B", "children": ["S"], "algorithm": "取最大值"},
+                    {"name": "S", "children": ["T"], "algorithm": "取最大值"},
+                    {"name": "T", "children": ["t1"], "algorithm": "取最大值"},
+                    {"name": "T副本", "children": ["t2"], "algorithm": "取最大值"}
+                ]
+            }"#,
+        );
+        let settings = CopySettings {
+            rule: RenameRule::Suffix("副本".to_string()),
+            duplicate_shared_leaves: false,
+        };
+        let before = counter(&tree_model);
+        let (s, a) = (id_of(&tree_model, "S"), id_of(&tree_model, "A"));
+        assert!(unshare(&mut tree_model, s, a, "S2", Some(&settings)).is_err());
+        assert_eq!(counter(&tree_model), before);
+        let response = unshare(&mut tree_model, s, a, "S2", None).unwrap();
+        assert_eq!(response.new_id, before);
+    }
+
+    // P同时引用L和R，合并后只引用一次L，引用计数按去重后的子节点计算
+    #[test]
+    fn merge_collapses_shared_parent() {
+        let mut tree_model = load(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["P", "Q"], "algorithm": "取最小值"},
+                    {"name": "P", "children": ["R", "L", "R"], "algorithm": "取最大值"},
+                    {"name": "Q", "children": ["R"], "algorithm": "取最大值"}
+                ]
+            }"#,
+        );
+        let settings = MergeSettings {
+            children: ChildrenMerge::Union,
+            algorithm: MergeSide::Left,
+        };
+        let [p, q, l, r] = ["P", "Q", "L", "R"].map(|name| id_of(&tree_model, name));
+        let response = merge_nodes(&mut tree_model, l, r, &settings).unwrap();
+        assert_eq!(response.removed, [r]);
+        let mut ids_to_update = response.ids_to_update.clone();
+        ids_to_update.sort();
+        ids_to_update.dedup();
+        assert_eq!(ids_to_update.len(), response.ids_to_update.len());
+        let children = |id: u64| tree_model.models[&id].expand_info.clone().unwrap().children;
+        assert_eq!(children(p), [l]);
+        assert_eq!(children(q), [l]);
+        assert_eq!(tree_model.models[&l].ref_count, 2);
+    }
+
+    fn find_replace_settings(find: &str, replace: &str, mode: FindMode) -> FindReplaceSettings {
+        FindReplaceSettings {
+            find: find.to_string(),
+            replace: replace.to_string(),
+            mode,
+        }
+    }
+
+    // 叶节点“旧x”改名后与“x”重名，合并到“x”，P只保留一个引用
+    #[test]
+    fn find_replace_merges_leaves() {
+        let mut tree_model = load(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["P", "旧A"], "algorithm": "取最小值"},
+                    {"name": "P", "children": ["x", "旧x"], "algorithm": "取最大值"},
+                    {"name": "旧A", "children": ["y"], "algorithm": "取最大值"}
+                ]
+            }"#,
+        );
+        let [p, a, x, old_x] = ["P", "旧A", "x", "旧x"].map(|name| id_of(&tree_model, name));
+        let settings = find_replace_settings("旧", "", FindMode::Literal);
+        let response = find_replace(&mut tree_model, &settings).unwrap();
+        assert_eq!(response.removed, [old_x]);
+        assert!(!tree_model.models.contains_key(&old_x));
+        assert_eq!(tree_model.models[&a].name, "A");
+        assert_eq!(
+            tree_model.models[&p].expand_info.as_ref().unwrap().children,
+            [x]
+        );
+    }
+
+    // 非叶节点改为已有的名称时整批替换失败，模型保持不变
+    #[test]
+    fn find_replace_rejects_non_leaf_collision() {
+        let mut tree_model = load(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["A1", "A2", "B1"], "algorithm": "取最小值"},
+                    {"name": "A1", "children": ["x"], "algorithm": "取最大值"},
+                    {"name": "A2", "children": ["y"], "algorithm": "取最大值"},
+                    {"name": "B1", "children": ["z"], "algorithm": "取最大值"}
+                ]
+            }"#,
+        );
+        let before = tree_model.snapshot();
+        let settings = find_replace_settings("^[AB]", "A", FindMode::Regex);
+        let preview = find_replace_preview(&tree_model.models, &settings).unwrap();
+        assert_eq!(preview.changes.len(), 1);
+        assert!(preview.collisions[0].error.is_some());
+        assert!(find_replace(&mut tree_model, &settings).is_err());
+        assert_eq!(
+            serde_json::to_value(tree_model.snapshot()).unwrap(),
+            serde_json::to_value(before).unwrap()
+        );
+    }
+
+    fn children_of(tree_model: &TreeModel, name: &str) -> Vec<String> {
+        let model = &tree_model.models[&id_of(tree_model, name)];
+        model
+            .expand_info
+            .as_ref()
+            .unwrap()
+            .children
+            .iter()
+            .map(|id| tree_model.models[id].name.clone())
+            .collect()
+    }
+
+    fn ref_count(tree_model: &TreeModel, name: &str) -> u64 {
+        tree_model.models[&id_of(tree_model, name)].ref_count
+    }
+
+    fn move_sample() -> TreeModel {
+        load(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["A", "B", "C"], "algorithm": "求和"},
+                    {"name": "A", "children": ["X", "a", "S"], "algorithm": "求和"},
+                    {"name": "X", "children": ["x1", "x2"], "algorithm": "求和"},
+                    {"name": "B", "children": ["b1", "b2", "S"], "algorithm": "求和"},
+                    {"name": "C", "children": [], "algorithm": "求和"}
+                ]
+            }"#,
+        )
+    }
+
+    // 移动后节点的id和子树不变，按index插入，超出范围时放在最后
+    #[test]
+    fn move_keeps_id_and_subtree() {
+        let mut tree_model = move_sample();
+        let (x, a, b) = (
+            id_of(&tree_model, "X"),
+            id_of(&tree_model, "A"),
+            id_of(&tree_model, "B"),
+        );
+        let subtree = tree_model.models[&x]
+            .expand_info
+            .as_ref()
+            .unwrap()
+            .children
+            .clone();
+        let updated = move_node(&mut tree_model, x, a, b, Some(1)).unwrap();
+        assert_eq!(updated, [a, b, x]);
+        assert_eq!(children_of(&tree_model, "A"), ["a", "S"]);
+        assert_eq!(children_of(&tree_model, "B"), ["b1", "X", "b2", "S"]);
+        assert_eq!(id_of(&tree_model, "X"), x);
+        assert_eq!(
+            tree_model.models[&x].expand_info.as_ref().unwrap().children,
+            subtree
+        );
+        assert_eq!(ref_count(&tree_model, "X"), 1);
+        move_node(&mut tree_model, x, b, a, Some(10)).unwrap();
+        assert_eq!(children_of(&tree_model, "A"), ["a", "S", "X"]);
+        move_node(&mut tree_model, x, a, a, Some(0)).unwrap();
+        assert_eq!(children_of(&tree_model, "A"), ["X", "a", "S"]);
+        let c = id_of(&tree_model, "C");
+        move_node(&mut tree_model, x, a, c, None).unwrap();
+        assert_eq!(children_of(&tree_model, "C"), ["X"]);
+    }
+
+    // 共享节点只移动这一条边，引用计数不变；移到已有该节点的父节点下时报错
+    #[test]
+    fn move_shared_node_moves_one_edge() {
+        let mut tree_model = move_sample();
+        let (s, a, b, c) = (
+            id_of(&tree_model, "S"),
+            id_of(&tree_model, "A"),
+            id_of(&tree_model, "B"),
+            id_of(&tree_model, "C"),
+        );
+        assert_eq!(ref_count(&tree_model, "S"), 2);
+        move_node(&mut tree_model, s, a, c, None).unwrap();
+        assert_eq!(children_of(&tree_model, "A"), ["X", "a"]);
+        assert_eq!(children_of(&tree_model, "B"), ["b1", "b2", "S"]);
+        assert_eq!(children_of(&tree_model, "C"), ["S"]);
+        assert_eq!(ref_count(&tree_model, "S"), 2);
+        assert!(move_node(&mut tree_model, s, c, b, None).is_err());
+    }
+
+    // 移到自身或子孙节点下会形成环，模型保持不变
+    #[test]
+    fn cyclic_move_is_rejected() {
+        let mut tree_model = move_sample();
+        let before = tree_model.snapshot();
+        let (root, a, x) = (
+            id_of(&tree_model, "Root"),
+            id_of(&tree_model, "A"),
+            id_of(&tree_model, "X"),
+        );
+        assert!(move_node(&mut tree_model, a, root, x, None).is_err());
+        assert!(move_node(&mut tree_model, a, root, a, None).is_err());
+        assert_eq!(
+            serde_json::to_value(tree_model.snapshot()).unwrap(),
+            serde_json::to_value(before).unwrap()
+        );
+    }
+
+    // 加到第二个父节点下后被共享，引用计数为2
+    #[test]
+    fn link_child_shares_node() {
+        let mut tree_model = move_sample();
+        let (x, c) = (id_of(&tree_model, "X"), id_of(&tree_model, "C"));
+        assert_eq!(link_child(&mut tree_model, x, c).unwrap(), c);
+        assert_eq!(children_of(&tree_model, "A"), ["X", "a", "S"]);
+        assert_eq!(children_of(&tree_model, "C"), ["X"]);
+        assert_eq!(ref_count(&tree_model, "X"), 2);
+        assert!(link_child(&mut tree_model, x, c).is_err());
+        let leaf = id_of(&tree_model, "a");
+        assert!(link_child(&mut tree_model, x, leaf).is_err());
+    }
+
+    // 把自身或祖先节点加为子节点会形成环，模型保持不变
+    #[test]
+    fn link_cycle_is_rejected() {
+        let mut tree_model = move_sample();
+        let before = tree_model.snapshot();
+        let (root, a, x) = (
+            id_of(&tree_model, "Root"),
+            id_of(&tree_model, "A"),
+            id_of(&tree_model, "X"),
+        );
+        assert!(link_child(&mut tree_model, a, x).is_err());
+        assert!(link_child(&mut tree_model, root, a).is_err());
+        assert!(link_child(&mut tree_model, x, x).is_err());
+        assert_eq!(
+            serde_json::to_value(tree_model.snapshot()).unwrap(),
+            serde_json::to_value(before).unwrap()
+        );
+    }
+
+    fn ids_of(tree_model: &TreeModel, names: &[&str]) -> Vec<u64> {
+        let mut ids = names
+            .iter()
+            .map(|name| id_of(tree_model, name))
+            .collect::<Vec<u64>>();
+        ids.sort();
+        ids
+    }
+
+    // T同时在A的子树中和B下，删除A的子树时保留；子树内部共享的S一并删除
+    #[test]
+    fn delete_subtree_keeps_shared_descendants() {
+        let mut tree_model = load(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["A", "B"], "algorithm": "求和"},
+                    {"name": "A", "children": ["X", "S"], "algorithm": "求和"},
+                    {"name": "X", "children": ["x1", "T", "S"], "algorithm": "求和"},
+                    {"name": "B", "children": ["T", "b"], "algorithm": "求和"},
+                    {"name": "T", "children": ["t"], "algorithm": "求和"}
+                ]
+            }"#,
+        );
+        let (root, a, b) = (
+            id_of(&tree_model, "Root"),
+            id_of(&tree_model, "A"),
+            id_of(&tree_model, "B"),
+        );
+        let removed = ids_of(&tree_model, &["A", "X", "S", "x1"]);
+        assert_eq!(
+            subtree_deletion(&tree_model.models, a, root).unwrap(),
+            removed
+        );
+        let response = delete_subtree(&mut tree_model, a, root).unwrap();
+        assert_eq!(response.removed, removed);
+        assert!(response.ids_to_update.contains(&root));
+        assert!(response.ids_to_update.contains(&id_of(&tree_model, "T")));
+        assert_eq!(children_of(&tree_model, "Root"), ["B"]);
+        assert_eq!(children_of(&tree_model, "B"), ["T", "b"]);
+        assert_eq!(children_of(&tree_model, "T"), ["t"]);
+        assert_eq!(ref_count(&tree_model, "T"), 1);
+        assert!(removed.iter().all(|id| !tree_model.models.contains_key(id)));
+        assert!(subtree_deletion(&tree_model.models, b, a).is_err());
+        assert!(subtree_deletion(&tree_model.models, root, root).is_err());
+    }
+
+    // 清理只删除不可达的节点，不可达节点引用的可达节点保留并更新引用计数
+    #[test]
+    fn purge_removes_only_unreachable() {
+        let mut tree_model = load(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["A"], "algorithm": "求和"},
+                    {"name": "A", "children": ["T"], "algorithm": "求和"},
+                    {"name": "Old", "children": ["u", "T"], "algorithm": "求和"}
+                ]
+            }"#,
+        );
+        let unreachable = ids_of(&tree_model, &["Old", "u"]);
+        assert_eq!(unreachable_nodes(&tree_model.models), unreachable);
+        assert_eq!(ref_count(&tree_model, "T"), 2);
+        let a = id_of(&tree_model, "A");
+        assert!(remove_unreachable(&mut tree_model, &[a]).is_err());
+        let response = purge_unreachable(&mut tree_model).unwrap();
+        assert_eq!(response.removed, unreachable);
+        assert_eq!(response.ids_to_update, [id_of(&tree_model, "T")]);
+        let mut names = tree_model
+            .models
+            .values()
+            .map(|model| model.name.as_str())
+            .collect::<Vec<&str>>();
+        names.sort();
+        assert_eq!(names, ["A", "Root", "T"]);
+        assert_eq!(ref_count(&tree_model, "T"), 1);
+        assert!(unreachable_nodes(&tree_model.models).is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+use crate::history::History;
+use crate::models::TreeModel;
+use crate::operations::unreachable_nodes;
+use shared::{Model, UnreachableNode};
+use std::collections::BTreeMap;
+
+// 在models中引用id的节点
+fn parents_in(models: &BTreeMap<u64, Model>, id: u64) -> Vec<u64> {
+    models
+        .values()
+        .filter(|model| {
+            model
+                .expand_info
+                .as_ref()
+                .is_some_and(|expand_info| expand_info.children.contains(&id))
+        })
+        .map(|model| model.id)
+        .collect()
+}
+
+// 列出所有从根节点无法到达的节点及其原来的父节点：优先取当前模型中仍引用它的节点，
+// 没有时从撤销历史中找最近一次引用它的节点；都找不到时（例如文件中本来就有的孤立节点）为空。
+// 撤销历史只在内存中，重新打开文件后从历史中找到的原父节点不再可知
+pub fn list_unreachable(tree_model: &TreeModel, history: &History) -> Vec<UnreachableNode> {
+    unreachable_nodes(&tree_model.models)
+        .into_iter()
+        .map(|id| {
+            let model = &tree_model.models[&id];
+            let mut former_parents = parents_in(&tree_model.models, id)
+                .into_iter()
+                .map(|parent| tree_model.models[&parent].name.clone())
+                .collect::<Vec<String>>();
+            if former_parents.is_empty() {
+                for snapshot in history.past_snapshots() {
+                    let parents = parents_in(snapshot.models(), id);
+                    if parents.is_empty() {
+                        continue;
+                    }
+                    former_parents = parents
+                        .into_iter()
+                        .map(|parent| match tree_model.models.get(&parent) {
+                            Some(parent) => parent.name.clone(),
+                            None => format!("{}（已删除）", snapshot.models()[&parent].name),
+                        })
+                        .collect();
+                    break;
+                }
+            }
+            UnreachableNode {
+                id,
+                name: model.name.clone(),
+                is_leaf: model.expand_info.is_none(),
+                former_parents,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::build_tree_model;
+    use crate::operations;
+
+    fn load(json: &str) -> TreeModel {
+        build_tree_model(serde_json::from_str(json).unwrap(), false).unwrap()
+    }
+
+    fn id_of(tree_model: &TreeModel, name: &str) -> u64 {
+        tree_model
+            .models
+            .values()
+            .find(|model| model.name == name)
+            .unwrap()
+            .id
+    }
+
+    fn former_parents(nodes: &[UnreachableNode]) -> Vec<(&str, Vec<&str>)> {
+        nodes
+            .iter()
+            .map(|node| {
+                let parents = node.former_parents.iter().map(String::as_str).collect();
+                (node.name.as_str(), parents)
+            })
+            .collect()
+    }
+
+    // 仍被不可达节点引用的节点从当前模型中找到父节点，文件中本来孤立的节点没有父节点
+    #[test]
+    fn parents_from_current_model() {
+        let tree_model = load(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["A"], "algorithm": "求和"},
+                    {"name": "Old", "children": ["u"], "algorithm": "求和"}
+                ]
+            }"#,
+        );
+        let nodes = list_unreachable(&tree_model, &History::default());
+        assert_eq!(
+            former_parents(&nodes),
+            [("Old", vec![]), ("u", vec!["Old"])]
+        );
+        assert!(!nodes[0].is_leaf);
+        assert!(nodes[1].is_leaf);
+    }
+
+    // 删除A后其子节点不可达，从撤销历史中找到A，A已被删除时加上标注；
+    // 没有撤销历史（如重新打开文件后）时原父节点未知
+    #[test]
+    fn parents_from_history() {
+        let mut tree_model = load(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["A", "B"], "algorithm": "求和"},
+                    {"name": "A", "children": ["a", "S"], "algorithm": "求和"},
+                    {"name": "B", "children": ["S"], "algorithm": "求和"}
+                ]
+            }"#,
+        );
+        let mut history = History::default();
+        let root = id_of(&tree_model, "Root");
+        for name in ["A", "B"] {
+            let id = id_of(&tree_model, name);
+            let before = tree_model.snapshot();
+            operations::delete(&mut tree_model, id, Some(root)).unwrap();
+            history.push("删除", before);
+        }
+        // S在较早的历史中被A和B共同引用，只取最近一次的B
+        let nodes = list_unreachable(&tree_model, &history);
+        assert_eq!(
+            former_parents(&nodes),
+            [("a", vec!["A（已删除）"]), ("S", vec!["B（已删除）"])]
+        );
+        assert_eq!(
+            former_parents(&list_unreachable(&tree_model, &History::default())),
+            [("a", vec![]), ("S", vec![])]
+        );
+    }
+}
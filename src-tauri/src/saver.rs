@@ -1,10 +1,391 @@
+use std::collections::{HashSet, VecDeque};
 use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use crate::models::FileTreeModel;
+use crate::formats::ModelFormat;
+use crate::history::History;
+use crate::journal::Journal;
+use crate::models::{FileModel, FileTreeModel, TauriState, TreeModel};
+use shared::{Algorithm, ModelHeader};
 
-pub fn save_models(file_path: &str, file_tree_model: FileTreeModel) -> Result<(), String> {
-    let content = serde_json::to_string(&file_tree_model)
+// 将内存中的模型转换为文件模型
+// 顺序：根节点，文件中原有节点（按原始顺序），新增的可达节点（按广度优先顺序），其余不可达节点（按id）
+pub fn to_file_tree_model(tree_model: &TreeModel) -> Result<FileTreeModel, String> {
+    let models = &tree_model.models;
+    let extras = &tree_model.extras;
+    let root_name = models
+        .get(&0)
+        .ok_or("在保存时遇到错误：未找到根节点".to_string())?
+        .name
+        .clone();
+    let mut order = Vec::<u64>::new();
+    let mut met = HashSet::<u64>::new();
+    let mut push = |id: u64, order: &mut Vec<u64>| {
+        if met.insert(id) {
+            order.push(id);
+        }
+    };
+    push(0, &mut order);
+    for id in extras.order.iter() {
+        if models.contains_key(id) {
+            push(*id, &mut order);
+        }
+    }
+    let mut queue = VecDeque::<u64>::new();
+    let mut visited = HashSet::<u64>::new();
+    queue.push_back(0);
+    while let Some(id) = queue.pop_front() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let model = models
+            .get(&id)
+            .ok_or(format!("在保存时遇到错误：未找到模型{}", id))?;
+        push(id, &mut order);
+        if let Some(expand_info) = &model.expand_info {
+            queue.extend(expand_info.children.iter());
+        }
+    }
+    for id in models.keys() {
+        push(*id, &mut order);
+    }
+
+    let mut data = Vec::<FileModel>::new();
+    for id in order {
+        let model = models
+            .get(&id)
+            .ok_or(format!("在保存时遇到错误：未找到模型{}", id))?;
+        let extra = extras.node_fields.get(&id).cloned().unwrap_or_default();
+        let expand_info = match &model.expand_info {
+            Some(expand_info) => expand_info,
+            // 只保存带有说明信息的叶节点
+            None if model.metadata.is_empty() && extra.is_empty() => continue,
+            None => {
+                data.push(FileModel {
+                    name: model.name.clone(),
+                    children: None,
+                    algorithm: None,
+                    metadata: model.metadata.clone(),
+                    extra,
+                });
+                continue;
+            }
+        };
+        let children = expand_info
+            .children
+            .iter()
+            .map(|child_id| {
+                let model = models.get(child_id).ok_or(format!(
+                    "保存时遇到错误：取children_names时遇到未知模型：{}",
+                    child_id
+                ))?;
+                Ok(model.name.clone())
+            })
+            .collect::<Result<Vec<String>, String>>()?;
+        let algorithm = match (&expand_info.algorithm, extras.raw_algorithms.get(&id)) {
+            (Algorithm::None, Some(raw_algorithm)) => raw_algorithm.clone(),
+            (algorithm, _) => algorithm.to_string(),
+        };
+        data.push(FileModel {
+            name: model.name.clone(),
+            children: Some(children),
+            algorithm: Some(algorithm),
+            metadata: model.metadata.clone(),
+            extra,
+        });
+    }
+    Ok(FileTreeModel {
+        header: tree_model.header.clone(),
+        root_name,
+        data,
+        extra: extras.root_fields.clone(),
+    })
+}
+
+// 备份文件与模型文件位于同一目录，命名为“文件名.bak1”（最新）到“文件名.bakN”（最旧）
+fn backup_path(path: &Path, index: u64) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(format!(".bak{}", index));
+    path.with_file_name(file_name)
+}
+
+fn rotate_backups(path: &Path, backup_count: u64) -> Result<(), String> {
+    if backup_count == 0 || !path.exists() {
+        return Ok(());
+    }
+    // 删除超出数量的最旧备份，其余依次后移
+    let oldest = backup_path(path, backup_count);
+    if oldest.exists() {
+        fs::remove_file(&oldest).map_err(|e| format!("删除旧备份{:?}错误\n{}", oldest, e))?;
+    }
+    for index in (1..backup_count).rev() {
+        let from = backup_path(path, index);
+        if from.exists() {
+            let to = backup_path(path, index + 1);
+            fs::rename(&from, &to).map_err(|e| format!("轮换备份{:?}错误\n{}", from, e))?;
+        }
+    }
+    let newest = backup_path(path, 1);
+    fs::copy(path, &newest).map_err(|e| format!("创建备份{:?}错误\n{}", newest, e))?;
+    Ok(())
+}
+
+// 先写入同目录下的临时文件再重命名，保证崩溃时不会留下写了一半的模型文件
+pub fn write_atomically(file_path: &str, content: &[u8]) -> Result<(), String> {
+    let path = Path::new(file_path);
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(path.file_name().unwrap_or_default());
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    let result = (|| {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp_path);
+        Err(format!("写入文件{:?}错误\n{}", file_path, e))?;
+    }
+    Ok(())
+}
+
+pub fn save_models(
+    file_path: &str,
+    file_tree_model: FileTreeModel,
+    backup_count: u64,
+) -> Result<(), String> {
+    let content = ModelFormat::from_path(file_path)
+        .serialize(&file_tree_model)
         .map_err(|e| format!("序列化模型文件错误\n{}", e))?;
-    fs::write(file_path, content).map_err(|e| format!("写入模型文件错误\n{}", e))?;
+    rotate_backups(Path::new(file_path), backup_count)?;
+    write_atomically(file_path, content.as_bytes())?;
+    Ok(())
+}
+
+// 递增版本号的最后一段数字，如“1.0.9”变为“1.0.10”；没有数字的版本号保持不变
+fn bump_version(version: &str) -> String {
+    if version.is_empty() {
+        return "1".to_string();
+    }
+    let end = match version.rfind(|c: char| c.is_ascii_digit()) {
+        Some(index) => index + 1,
+        None => return version.to_string(),
+    };
+    let start = version[..end]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |index| index + 1);
+    match version[start..end].parse::<u64>() {
+        Ok(number) => format!("{}{}{}", &version[..start], number + 1, &version[end..]),
+        Err(_) => version.to_string(),
+    }
+}
+
+// 保存时自动更新头部的时间和版本，只有模型有修改时才递增版本
+fn stamp_header(header: &ModelHeader, changed: bool) -> ModelHeader {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut header = header.clone();
+    if header.created.is_empty() {
+        header.created = now.clone();
+    }
+    header.modified = now;
+    if changed || header.version.is_empty() {
+        header.version = bump_version(&header.version);
+    }
+    header
+}
+
+// 将当前模型保存到file_path，并为该文件开始新的恢复日志
+pub fn save_state(state: &mut TauriState, file_path: &str) -> Result<(), String> {
+    let changed = state.unsaved_since.is_some();
+    let tree_model = state
+        .curr_tree_model
+        .as_mut()
+        .ok_or("保存错误：模型未加载".to_string())?;
+    let header = stamp_header(&tree_model.header, changed);
+    let mut file_tree_model = to_file_tree_model(tree_model)?;
+    file_tree_model.header = header.clone();
+    save_models(file_path, file_tree_model, state.save_settings.backup_count)?;
+    tree_model.header = header;
+    let journal_ids = tree_model.journal_ids();
+    state.unsaved_since = None;
+    let journal = match Journal::create(file_path, journal_ids) {
+        Ok(journal) => Some(journal),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    };
+    // 新的日志从空的撤销历史开始重放
+    state.set_journal(journal, &History::default());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::{build_tree_model, load_models};
+    use shared::NodeMetadata;
+
+    fn load(json: &str) -> TreeModel {
+        build_tree_model(serde_json::from_str(json).unwrap(), false).unwrap()
+    }
+
+    fn names(file_tree_model: &FileTreeModel) -> Vec<&str> {
+        file_tree_model
+            .data
+            .iter()
+            .map(|model| model.name.as_str())
+            .collect()
+    }
+
+    // 文件中的节点顺序、不认识的字段和无法解析的算法原样写回
+    #[test]
+    fn save_keeps_unknown_fields_and_order() {
+        let tree_model = load(
+            r#"{
+                "root_name": "Root",
+                "source": {"by": "甲"},
+                "data": [
+                    {"name": "B", "children": ["b"], "algorithm": "待定", "color": "red"},
+                    {"name": "Root", "children": ["A", "B"], "algorithm": "取最小值"},
+                    {"name": "A", "children": ["a"], "algorithm": "求和"}
+                ]
+            }"#,
+        );
+        let saved = to_file_tree_model(&tree_model).unwrap();
+        assert_eq!(names(&saved), ["Root", "B", "A"]);
+        assert_eq!(saved.extra["source"]["by"], "甲");
+        let b = &saved.data[1];
+        assert_eq!(b.extra["color"], "red");
+        assert_eq!(b.algorithm.as_deref(), Some("待定"));
+    }
+
+    // 叶节点只有带说明信息或不认识的字段时才写入
+    #[test]
+    fn leaves_written_only_with_metadata_or_extras() {
+        let tree_model = load(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["x", "y", "z"], "algorithm": "求和"},
+                    {"name": "x", "metadata": {"unit": "分"}},
+                    {"name": "y", "color": "red"},
+                    {"name": "z"}
+                ]
+            }"#,
+        );
+        let saved = to_file_tree_model(&tree_model).unwrap();
+        assert_eq!(names(&saved), ["Root", "x", "y"]);
+        assert!(saved.data[1].children.is_none());
+        assert_eq!(saved.data[1].metadata.unit, "分");
+        assert_eq!(saved.data[2].extra["color"], "red");
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("saver-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // 每次保存前把原文件复制为bak1，较旧的备份依次后移，超出数量的被删除
+    #[test]
+    fn rotate_backups_keeps_backup_count() {
+        let dir = temp_dir("rotate");
+        let path = dir.join("model.json");
+        for version in 1..=4 {
+            write_atomically(path.to_str().unwrap(), format!("v{}", version).as_bytes()).unwrap();
+            if version < 4 {
+                rotate_backups(&path, 2).unwrap();
+            }
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "v4");
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "v3");
+        assert_eq!(fs::read_to_string(backup_path(&path, 2)).unwrap(), "v2");
+        assert!(!backup_path(&path, 3).exists());
+        rotate_backups(&path, 0).unwrap();
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "v3");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // 重命名失败时原来的内容保持不变，也不留下临时文件
+    #[test]
+    fn failed_rename_leaves_original_intact() {
+        let dir = temp_dir("rename");
+        // 目标是非空目录，临时文件无法重命名过去
+        let path = dir.join("model.json");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("keep.txt"), "原内容").unwrap();
+        let result = write_atomically(path.to_str().unwrap(), b"new");
+        assert!(result.unwrap_err().starts_with("写入文件"));
+        assert_eq!(fs::read_to_string(path.join("keep.txt")).unwrap(), "原内容");
+        assert!(!dir.join(".model.json.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // 叶节点上修改的说明信息保存后重新打开仍然存在
+    #[test]
+    fn leaf_metadata_survives_reload() {
+        let dir = temp_dir("metadata");
+        let path = dir.join("model.json");
+        let mut tree_model = load(
+            r#"{
+                "root_name": "Root",
+                "data": [{"name": "Root", "children": ["x", "y"], "algorithm": "求和"}]
+            }"#,
+        );
+        let leaf = |tree_model: &TreeModel| {
+            tree_model
+                .models
+                .values()
+                .find(|model| model.name == "x")
+                .unwrap()
+                .clone()
+        };
+        let metadata = NodeMetadata {
+            description: "每分钟心跳次数".to_string(),
+            unit: "次/分".to_string(),
+            expected_range: "60～100".to_string(),
+            references: vec!["指南".to_string()],
+            tags: vec!["生命体征".to_string()],
+            notes: "静息时测量".to_string(),
+        };
+        let id = leaf(&tree_model).id;
+        crate::operations::update_metadata(&mut tree_model, id, metadata.clone()).unwrap();
+        let file_path = path.to_str().unwrap();
+        save_models(file_path, to_file_tree_model(&tree_model).unwrap(), 0).unwrap();
+        let reloaded = load_models(file_path, false).unwrap();
+        assert_eq!(leaf(&reloaded).metadata, metadata);
+        assert!(leaf(&reloaded).expand_info.is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn bump_last_number() {
+        assert_eq!(bump_version(""), "1");
+        assert_eq!(bump_version("1.0.9"), "1.0.10");
+        assert_eq!(bump_version("v2"), "v3");
+        assert_eq!(bump_version("1.2-beta"), "1.3-beta");
+        assert_eq!(bump_version("草稿"), "草稿");
+    }
+
+    // 未修改的模型只补上缺少的创建时间和版本号
+    #[test]
+    fn stamp_unchanged_header() {
+        let header = ModelHeader {
+            version: "1.0".to_string(),
+            created: "2024-01-01 00:00:00".to_string(),
+            ..Default::default()
+        };
+        let stamped = stamp_header(&header, false);
+        assert_eq!(stamped.version, "1.0");
+        assert_eq!(stamped.created, "2024-01-01 00:00:00");
+        assert!(!stamped.modified.is_empty());
+        assert_eq!(stamp_header(&header, true).version, "1.1");
+        let stamped = stamp_header(&ModelHeader::default(), false);
+        assert_eq!(stamped.version, "1");
+        assert_eq!(stamped.created, stamped.modified);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::models::TreeModel;
+use crate::operations::ancestors;
+use shared::NodeSearchResult;
+use std::collections::HashSet;
+
+// 搜索结果最多返回的数量
+const MAX_SEARCH_RESULTS: usize = 50;
+
+// 名称包含query（不区分大小写）的节点，名称完全相同的排在最前，其余按名称长度排列；
+// parent不为空时排除parent自身、祖先节点和已有的子节点，因为它们不能再加为parent的子节点
+pub fn search_nodes(
+    tree_model: &TreeModel,
+    query: &str,
+    parent: Option<u64>,
+) -> Vec<NodeSearchResult> {
+    let query = query.trim().to_lowercase();
+    let excluded = match parent {
+        Some(parent) => {
+            let mut excluded = ancestors(&tree_model.models, parent);
+            if let Some(expand_info) = tree_model
+                .models
+                .get(&parent)
+                .and_then(|model| model.expand_info.as_ref())
+            {
+                excluded.extend(expand_info.children.iter().copied());
+            }
+            excluded
+        }
+        None => HashSet::new(),
+    };
+    let mut results = tree_model
+        .models
+        .values()
+        .filter(|model| !excluded.contains(&model.id))
+        .filter(|model| model.name.to_lowercase().contains(&query))
+        .collect::<Vec<_>>();
+    results.sort_by_key(|model| {
+        (
+            model.name.to_lowercase() != query,
+            model.name.chars().count(),
+            model.id,
+        )
+    });
+    results
+        .into_iter()
+        .take(MAX_SEARCH_RESULTS)
+        .map(|model| NodeSearchResult {
+            id: model.id,
+            name: model.name.clone(),
+            is_leaf: model.expand_info.is_none(),
+            ref_count: model.ref_count,
+        })
+        .collect()
+}
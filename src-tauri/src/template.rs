@@ -0,0 +1,266 @@
+use crate::datafile::{write_csv_table, write_workbook_table, Table, TableCell};
+use crate::models::TreeModel;
+use serde_json::{Map, Value};
+use shared::{DataFileSettings, DataLayout, TemplateFormat, TemplateSettings};
+use std::collections::HashSet;
+
+// 数据模板中的一个叶节点
+pub struct TemplateRow {
+    pub name: String,
+    // 从模板的根节点到该节点的名称，用“ / ”连接
+    pub path: String,
+    pub unit: String,
+    pub expected_range: String,
+    pub description: String,
+}
+
+// 按树的顺序（深度优先，子节点按定义的顺序）列出root子树中的叶节点，
+// 共享的节点只列出一次，路径为第一次出现的位置
+pub fn template_rows(tree_model: &TreeModel, root: u64) -> Result<Vec<TemplateRow>, String> {
+    fn visit(
+        tree_model: &TreeModel,
+        id: u64,
+        path: &mut Vec<String>,
+        visited: &mut HashSet<u64>,
+        rows: &mut Vec<TemplateRow>,
+    ) -> Result<(), String> {
+        if !visited.insert(id) {
+            return Ok(());
+        }
+        let model = tree_model
+            .models
+            .get(&id)
+            .ok_or(format!("未找到模型{}", id))?;
+        path.push(model.name.clone());
+        match model.expand_info.as_ref() {
+            Some(expand_info) => {
+                for child in expand_info.children.iter() {
+                    visit(tree_model, *child, path, visited, rows)?;
+                }
+            }
+            None => rows.push(TemplateRow {
+                name: model.name.clone(),
+                path: path.join(" / "),
+                unit: model.metadata.unit.clone(),
+                expected_range: model.metadata.expected_range.clone(),
+                description: model.metadata.description.clone(),
+            }),
+        }
+        path.pop();
+        Ok(())
+    }
+    let mut rows = Vec::new();
+    visit(
+        tree_model,
+        root,
+        &mut Vec::new(),
+        &mut HashSet::new(),
+        &mut rows,
+    )?;
+    Ok(rows)
+}
+
+pub fn template_extension(format: &TemplateFormat) -> &'static str {
+    match format {
+        TemplateFormat::Json => "json",
+        TemplateFormat::Csv => "csv",
+        TemplateFormat::Xlsx => "xlsx",
+    }
+}
+
+// 列的设置为空时使用默认的表头
+fn header(spec: &str, default: &str) -> TableCell {
+    match spec.trim() {
+        "" => TableCell::Text(default.to_string()),
+        spec => TableCell::Text(spec.to_string()),
+    }
+}
+
+fn value_cell(value: Option<f64>) -> TableCell {
+    value.map_or(TableCell::Empty, TableCell::Number)
+}
+
+// 表格按数据文件设置排列，生成的模板可以直接用同样的设置读取；
+// 附加的说明列只在每行一个指标时加入，读取时会被忽略
+fn template_table(
+    rows: &[TemplateRow],
+    values: &[Option<f64>],
+    settings: &TemplateSettings,
+    data_settings: &DataFileSettings,
+) -> Table {
+    match data_settings.layout {
+        DataLayout::KeyValue => {
+            let mut headers = vec![
+                header(&data_settings.name_column, "名称"),
+                header(&data_settings.value_column, "值"),
+            ];
+            let extra_columns = [
+                (settings.include_path, "节点路径"),
+                (settings.include_unit, "单位"),
+                (settings.include_expected_range, "预期范围"),
+                (settings.include_description, "说明"),
+            ];
+            for (included, title) in extra_columns.iter() {
+                if *included {
+                    headers.push(TableCell::Text(title.to_string()));
+                }
+            }
+            let mut table = vec![headers];
+            for (row, value) in rows.iter().zip(values.iter()) {
+                let mut cells = vec![TableCell::Text(row.name.clone()), value_cell(*value)];
+                let extra_cells = [
+                    (settings.include_path, &row.path),
+                    (settings.include_unit, &row.unit),
+                    (settings.include_expected_range, &row.expected_range),
+                    (settings.include_description, &row.description),
+                ];
+                for (included, text) in extra_cells.iter() {
+                    if *included {
+                        cells.push(TableCell::Text(text.to_string()));
+                    }
+                }
+                table.push(cells);
+            }
+            table
+        }
+        DataLayout::Wide => {
+            let mut headers = Vec::new();
+            let mut cells = Vec::new();
+            if !data_settings.subject_column.trim().is_empty() {
+                headers.push(header(&data_settings.subject_column, ""));
+                cells.push(TableCell::Text(data_settings.subject.trim().to_string()));
+            }
+            headers.extend(rows.iter().map(|row| TableCell::Text(row.name.clone())));
+            cells.extend(values.iter().map(|value| value_cell(*value)));
+            vec![headers, cells]
+        }
+    }
+}
+
+// JSON模板为名称到数值的对象，没有数值的指标写为0
+pub fn build_template(
+    rows: &[TemplateRow],
+    values: &[Option<f64>],
+    settings: &TemplateSettings,
+    data_settings: &DataFileSettings,
+) -> Result<Vec<u8>, String> {
+    match settings.format {
+        TemplateFormat::Json => {
+            let file_data = rows
+                .iter()
+                .zip(values.iter())
+                .map(|(row, value)| (row.name.clone(), Value::from(value.unwrap_or(0.0))))
+                .collect::<Map<String, Value>>();
+            serde_json::to_string_pretty(&file_data)
+                .map(String::into_bytes)
+                .map_err(|e| format!("生成JSON错误\n{}", e))
+        }
+        TemplateFormat::Csv => {
+            write_csv_table(&template_table(rows, values, settings, data_settings), b',')
+        }
+        TemplateFormat::Xlsx => {
+            let sheet = match data_settings.sheet.trim() {
+                "" => "数据",
+                sheet => sheet,
+            };
+            write_workbook_table(
+                &template_table(rows, values, settings, data_settings),
+                sheet,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datafile::{read_csv_table, table_to_data};
+    use crate::loader::build_tree_model;
+
+    fn load(json: &str) -> TreeModel {
+        build_tree_model(serde_json::from_str(json).unwrap(), false).unwrap()
+    }
+
+    fn id_of(tree_model: &TreeModel, name: &str) -> u64 {
+        tree_model
+            .models
+            .values()
+            .find(|model| model.name == name)
+            .unwrap()
+            .id
+    }
+
+    // Old不在根节点的子树中，它的叶节点u不可达
+    fn sample() -> TreeModel {
+        load(
+            r#"{
+                "root_name": "Root",
+                "data": [
+                    {"name": "Root", "children": ["A", "B"], "algorithm": "求和"},
+                    {"name": "A", "children": ["s", "a"], "algorithm": "求和"},
+                    {"name": "B", "children": ["b", "s"], "algorithm": "求和"},
+                    {"name": "s", "metadata": {"unit": "分"}},
+                    {"name": "Old", "children": ["u"], "algorithm": "求和"}
+                ]
+            }"#,
+        )
+    }
+
+    fn names(rows: &[TemplateRow]) -> Vec<&str> {
+        rows.iter().map(|row| row.name.as_str()).collect()
+    }
+
+    #[test]
+    fn shared_leaf_listed_once_at_first_path() {
+        let tree_model = sample();
+        let rows = template_rows(&tree_model, 0).unwrap();
+        assert_eq!(names(&rows), ["s", "a", "b"]);
+        assert_eq!(rows[0].path, "Root / A / s");
+        assert_eq!(rows[0].unit, "分");
+        let rows = template_rows(&tree_model, id_of(&tree_model, "B")).unwrap();
+        assert_eq!(names(&rows), ["b", "s"]);
+        assert_eq!(rows[1].path, "B / s");
+    }
+
+    #[test]
+    fn unreachable_leaves_only_in_their_own_subtree() {
+        let tree_model = sample();
+        let rows = template_rows(&tree_model, 0).unwrap();
+        assert!(!names(&rows).contains(&"u"));
+        let rows = template_rows(&tree_model, id_of(&tree_model, "Old")).unwrap();
+        assert_eq!(names(&rows), ["u"]);
+        assert_eq!(rows[0].path, "Old / u");
+    }
+
+    // 生成的模板用同样的数据文件设置读取，每个叶节点一个数值
+    #[test]
+    fn templates_read_back_with_data_settings() {
+        let tree_model = sample();
+        let rows = template_rows(&tree_model, 0).unwrap();
+        let values = [Some(1.0), None, Some(2.5)];
+        let mut settings = TemplateSettings {
+            include_path: true,
+            include_unit: true,
+            ..Default::default()
+        };
+        let json = build_template(&rows, &values, &settings, &Default::default()).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json, serde_json::json!({"s": 1.0, "a": 0.0, "b": 2.5}));
+
+        // 留空的单元格读取时报告为缺少数值，这里全部填写
+        settings.format = TemplateFormat::Csv;
+        let values = [Some(1.0), Some(0.0), Some(2.5)];
+        for layout in [DataLayout::KeyValue, DataLayout::Wide] {
+            let data_settings = DataFileSettings {
+                layout,
+                ..Default::default()
+            };
+            let csv = build_template(&rows, &values, &settings, &data_settings).unwrap();
+            let table = read_csv_table(&String::from_utf8(csv).unwrap()).unwrap();
+            let data = table_to_data(&table, &data_settings).unwrap();
+            assert_eq!(data.keys().collect::<Vec<_>>(), ["a", "b", "s"]);
+            assert_eq!(data["s"], 1.0);
+            assert_eq!(data["b"], 2.5);
+        }
+    }
+}
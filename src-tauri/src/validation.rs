@@ -0,0 +1,306 @@
+use crate::datafile::{
+    detect_delimiter, is_workbook, read_csv_table, read_table, rename_in_table, write_csv_table,
+    write_workbook_table,
+};
+use crate::helper::pinyin_syllables;
+use crate::models::FileData;
+use crate::saver::write_atomically;
+use serde_json::{Map, Value};
+use shared::{DataFileSettings, DataValidationReport, RenameSuggestion};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+// 报告中每类问题最多列出的名称数量
+const MAX_LISTED_NAMES: usize = 20;
+
+// 忽略全角半角、大小写、空白以及括号和引号样式的差别
+pub fn normalize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '\u{3000}' => ' ',
+            '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+            c => c,
+        })
+        .map(|c| match c {
+            '[' | '【' | '〔' | '{' => '(',
+            ']' | '】' | '〕' | '}' => ')',
+            '“' | '”' | '‘' | '’' | '\'' => '"',
+            '—' | '–' | '_' => '-',
+            c => c,
+        })
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// 允许的编辑距离随名称长度增加，短名称至少允许一处差别
+fn max_distance(key: &str, leaf: &str) -> usize {
+    (key.chars().count().min(leaf.chars().count()) / 3).max(1)
+}
+
+// 为每个未使用的名称在缺少的叶节点中找最接近的一个：编辑距离足够小或拼音相同（同音字）时配对，
+// 只差规范化的和同音的优先，其次距离小的优先，每个名称只配对一次
+pub fn suggest_renames(missing: &[String], unused: &[String]) -> Vec<RenameSuggestion> {
+    let leaves = missing
+        .iter()
+        .map(|leaf| {
+            let normalized_leaf = normalize_name(leaf);
+            let syllables = pinyin_syllables(&normalized_leaf);
+            (normalized_leaf, syllables)
+        })
+        .collect::<Vec<(String, Vec<String>)>>();
+    let mut candidates = Vec::new();
+    for (key_index, key) in unused.iter().enumerate() {
+        let normalized_key = normalize_name(key);
+        let key_syllables = pinyin_syllables(&normalized_key);
+        for (leaf_index, (normalized_leaf, leaf_syllables)) in leaves.iter().enumerate() {
+            let distance = strsim::damerau_levenshtein(&normalized_key, normalized_leaf);
+            let homophone = distance > 0 && key_syllables == *leaf_syllables;
+            if homophone || distance <= max_distance(&normalized_key, normalized_leaf) {
+                let rank = distance > 0 && !homophone;
+                candidates.push((rank, distance, leaf_index, key_index, homophone));
+            }
+        }
+    }
+    candidates.sort();
+    let mut used_keys = HashSet::new();
+    let mut used_leaves = HashSet::new();
+    let mut suggestions = Vec::new();
+    for (_, distance, leaf_index, key_index, homophone) in candidates {
+        if used_keys.contains(&key_index) || used_leaves.contains(&leaf_index) {
+            continue;
+        }
+        used_keys.insert(key_index);
+        used_leaves.insert(leaf_index);
+        suggestions.push((
+            leaf_index,
+            RenameSuggestion {
+                key: unused[key_index].clone(),
+                leaf: missing[leaf_index].clone(),
+                distance: distance as u64,
+                homophone,
+            },
+        ));
+    }
+    // 按叶节点在模型中的顺序排列
+    suggestions.sort_by_key(|(leaf_index, _)| *leaf_index);
+    suggestions
+        .into_iter()
+        .map(|(_, suggestion)| suggestion)
+        .collect()
+}
+
+// leaves为参与计算的叶节点名称，按模型中的顺序排列
+pub fn validate_data(leaves: &[String], file_data: &FileData) -> DataValidationReport {
+    let leaf_names = leaves.iter().collect::<HashSet<&String>>();
+    let missing = leaves
+        .iter()
+        .filter(|leaf| !file_data.contains_key(*leaf))
+        .cloned()
+        .collect::<Vec<String>>();
+    let unused = file_data
+        .keys()
+        .filter(|key| !leaf_names.contains(key))
+        .cloned()
+        .collect::<Vec<String>>();
+    let non_finite = leaves
+        .iter()
+        .filter(|leaf| file_data.get(*leaf).is_some_and(|value| !value.is_finite()))
+        .cloned()
+        .collect();
+    let suggestions = suggest_renames(&missing, &unused);
+    DataValidationReport {
+        file_path: String::new(),
+        leaf_count: leaves.len() as u64,
+        missing,
+        unused,
+        non_finite,
+        suggestions,
+    }
+}
+
+pub fn list_names(names: &[String]) -> String {
+    let mut text = names
+        .iter()
+        .take(MAX_LISTED_NAMES)
+        .map(|name| format!("\"{}\"", name))
+        .collect::<Vec<String>>()
+        .join("、");
+    if names.len() > MAX_LISTED_NAMES {
+        text.push_str("……");
+    }
+    text
+}
+
+pub fn format_report(report: &DataValidationReport) -> String {
+    let mut lines = Vec::new();
+    if !report.missing.is_empty() {
+        lines.push(format!(
+            "数据文件中缺少{}个叶节点的数值：{}",
+            report.missing.len(),
+            list_names(&report.missing)
+        ));
+    }
+    if !report.non_finite.is_empty() {
+        lines.push(format!(
+            "{}个叶节点的数值不是有限数：{}",
+            report.non_finite.len(),
+            list_names(&report.non_finite)
+        ));
+    }
+    if !report.unused.is_empty() {
+        lines.push(format!(
+            "数据文件中有{}个名称不是模型中的叶节点：{}",
+            report.unused.len(),
+            list_names(&report.unused)
+        ));
+    }
+    for suggestion in report.suggestions.iter().take(MAX_LISTED_NAMES) {
+        lines.push(format!(
+            "\"{}\"可能是\"{}\"{}",
+            suggestion.key,
+            suggestion.leaf,
+            if suggestion.homophone {
+                "（同音）"
+            } else {
+                ""
+            }
+        ));
+    }
+    if lines.is_empty() {
+        lines.push(format!(
+            "数据文件包含全部{}个叶节点的数值",
+            report.leaf_count
+        ));
+    }
+    lines.join("\n")
+}
+
+// JSON文件保持原有的键顺序
+fn rename_in_json(
+    content: &str,
+    renames: &HashMap<String, String>,
+) -> Result<(String, usize), String> {
+    let data = serde_json::from_str::<Map<String, Value>>(content)
+        .map_err(|e| format!("解析数据文件错误\n{}", e))?;
+    for name in renames.values() {
+        if data.contains_key(name) && !renames.contains_key(name) {
+            Err(format!("数据文件中已有名称\"{}\"", name))?;
+        }
+    }
+    let count = data.keys().filter(|key| renames.contains_key(*key)).count();
+    let data = data
+        .into_iter()
+        .map(|(key, value)| (renames.get(&key).cloned().unwrap_or(key), value))
+        .collect::<Map<String, Value>>();
+    let content =
+        serde_json::to_string_pretty(&data).map_err(|e| format!("生成JSON错误\n{}", e))?;
+    Ok((content, count))
+}
+
+// 将建议的重命名应用到数据文件，结果写入output_path，返回修改的名称数量；
+// Excel文件只写出读取的工作表，因此应写入新文件
+pub fn rename_data_keys(
+    file_path: &str,
+    output_path: &str,
+    settings: &DataFileSettings,
+    renames: &[RenameSuggestion],
+) -> Result<usize, String> {
+    let renames = renames
+        .iter()
+        .map(|suggestion| (suggestion.key.clone(), suggestion.leaf.clone()))
+        .collect::<HashMap<String, String>>();
+    let (content, count) = if is_workbook(file_path) {
+        let mut table = read_table(file_path, &settings.sheet)?;
+        let count = rename_in_table(&mut table, settings, &renames)?;
+        let sheet = match settings.sheet.trim() {
+            "" => "数据",
+            sheet => sheet,
+        };
+        (write_workbook_table(&table, sheet)?, count)
+    } else {
+        let content = fs::read_to_string(file_path)
+            .map_err(|e| format!("读取数据文件{:?}错误\n{}", file_path, e))?;
+        if file_path.to_lowercase().ends_with(".json") {
+            let (content, count) = rename_in_json(&content, &renames)?;
+            (content.into_bytes(), count)
+        } else {
+            let content = content.trim_start_matches('\u{feff}');
+            let mut table = read_csv_table(content)?;
+            let count = rename_in_table(&mut table, settings, &renames)?;
+            (write_csv_table(&table, detect_delimiter(content))?, count)
+        }
+    };
+    write_atomically(output_path, &content)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn report_missing_unused_and_non_finite() {
+        let leaves = names(&["心跳", "体温", "血糖"]);
+        let file_data = FileData::from([
+            ("心跳".to_string(), 72.0),
+            ("体温".to_string(), f64::NAN),
+            ("血 糖（空腹）".to_string(), 5.1),
+            ("备注".to_string(), 0.0),
+        ]);
+        let report = validate_data(&leaves, &file_data);
+        assert_eq!(report.leaf_count, 3);
+        assert_eq!(report.missing, ["血糖"]);
+        assert_eq!(report.non_finite, ["体温"]);
+        assert_eq!(report.unused, ["备注", "血 糖（空腹）"]);
+        assert!(report.suggestions.is_empty());
+    }
+
+    // 全角括号和空白的差别不计入编辑距离
+    #[test]
+    fn suggest_by_edit_distance() {
+        let suggestions =
+            suggest_renames(&names(&["血糖(空腹)", "心跳"]), &names(&["血 糖（空腹）"]));
+        assert_eq!(
+            suggestions,
+            [RenameSuggestion {
+                key: "血 糖（空腹）".to_string(),
+                leaf: "血糖(空腹)".to_string(),
+                distance: 0,
+                homophone: false,
+            }]
+        );
+    }
+
+    // 两处同音字超出了编辑距离的限制，按拼音仍能配对，且优先于只差一个字的候选
+    #[test]
+    fn suggest_homophones() {
+        let missing = names(&["肌酐清除率", "肌酐清除"]);
+        let suggestions = suggest_renames(&missing, &names(&["肌干清楚率"]));
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].leaf, "肌酐清除率");
+        assert_eq!(suggestions[0].distance, 2);
+        assert!(suggestions[0].homophone);
+    }
+
+    #[test]
+    fn rename_json_keeps_key_order() {
+        let renames = HashMap::from([("血 糖".to_string(), "血糖".to_string())]);
+        let (content, count) =
+            rename_in_json(r#"{"心跳": 72, "血 糖": 5.1, "体温": 36.5}"#, &renames).unwrap();
+        assert_eq!(count, 1);
+        let keys = serde_json::from_str::<Map<String, Value>>(&content)
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<String>>();
+        assert_eq!(keys, ["心跳", "血糖", "体温"]);
+        // 改为已有的名称会丢失数值
+        let renames = HashMap::from([("心跳".to_string(), "体温".to_string())]);
+        assert!(rename_in_json(r#"{"心跳": 72, "体温": 36.5}"#, &renames).is_err());
+    }
+}
@@ -1,9 +1,11 @@
 use core::error;
 use std::sync::{Arc, RwLock};
 
-use crate::models::LeptosContext;
+use crate::models::{Clipboard, DraggedNode, LeptosContext, SelectedNode, UndoHistory};
+use crate::pages::data::Data;
 use crate::pages::home::Home;
 use crate::pages::save::Save;
+use crate::pages::template::Template;
 use crate::pages::tree::Tree;
 use leptos::task::spawn_local;
 use leptos::{ev::SubmitEvent, prelude::*};
@@ -12,7 +14,7 @@ use leptos_router::hooks::use_navigate;
 use leptos_router::path;
 use serde::{Deserialize, Serialize};
 use serde_wasm_bindgen::to_value;
-use shared::LogArgs;
+use shared::{HistoryState, LogArgs};
 use tokio::sync::Mutex;
 use wasm_bindgen::prelude::*;
 #[wasm_bindgen]
@@ -39,11 +41,17 @@ struct GreetArgs<'a> {
 #[component]
 pub fn App() -> impl IntoView {
     let err_msg = ArcRwSignal::new(String::new());
+    let history = ArcRwSignal::new(HistoryState::default());
     let leptos_context = Arc::new(Mutex::new(LeptosContext {
         models: Default::default(),
         err_msg: err_msg.clone(),
+        history: history.clone(),
     }));
     provide_context(leptos_context);
+    provide_context(SelectedNode::default());
+    provide_context(DraggedNode::default());
+    provide_context(Clipboard::default());
+    provide_context(UndoHistory(history));
     view! {
         <div>
             <Router>
@@ -52,6 +60,8 @@ pub fn App() -> impl IntoView {
                     <Route path=path!("/") view=Home />
                     <Route path=path!("/tree") view=Tree />
                     <Route path=path!("/save") view=Save />
+                    <Route path=path!("/data") view=Data />
+                    <Route path=path!("/template") view=Template />
                     <Route path=path!("/*any") view=|| view! { <h1>"Not Found"</h1> } />
                 </Routes>
             </Router>
@@ -1,2 +1,10 @@
+pub mod clipboard;
+pub mod data_report;
+pub mod find_replace_panel;
+pub mod merge_panel;
+pub mod model_header;
+pub mod node_inspector;
+pub mod node_picker;
 pub mod tree_node;
 pub mod tree_node_children;
+pub mod unreachable_panel;
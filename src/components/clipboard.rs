@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use leptos::prelude::*;
+use serde_wasm_bindgen::{from_value, to_value};
+use shared::{CopySettings, CopySubtreeArgs, MyResult, RenameRule};
+use tokio::sync::Mutex;
+
+use crate::{
+    app::invoke,
+    models::{Clipboard, LeptosContext},
+};
+
+// 复制id的子树并加到parent下，成功后刷新parent；复制和粘贴副本都调用这里
+pub async fn paste_copy(
+    leptos_context: Arc<Mutex<LeptosContext>>,
+    id: u64,
+    parent: u64,
+    settings: CopySettings,
+) {
+    let mut context = leptos_context.lock().await;
+    let args = to_value(&CopySubtreeArgs {
+        id,
+        parent,
+        settings,
+    })
+    .unwrap();
+    let response = invoke("request_copy_subtree", args).await;
+    let response = from_value::<MyResult<u64, String>>(response).unwrap();
+    match response {
+        MyResult::Ok(parent) => {
+            if context.models.contains_key(&parent) {
+                context.update_model(parent).await;
+            }
+            context.refresh_history().await;
+        }
+        MyResult::Err(e) => {
+            context.err_msg.set(e);
+        }
+    }
+}
+
+fn rule_kind(rule: &RenameRule) -> &'static str {
+    match rule {
+        RenameRule::Prefix(_) => "prefix",
+        RenameRule::Suffix(_) => "suffix",
+        RenameRule::Replace { .. } => "replace",
+    }
+}
+
+// 显示复制的节点，并设置粘贴副本时新名称的生成规则和共享叶节点的处理方式
+#[component]
+pub fn ClipboardView() -> impl IntoView {
+    let Clipboard { node, settings } = use_context::<Clipboard>().unwrap();
+
+    let on_kind_change = {
+        let settings = settings.clone();
+        move |ev| {
+            let rule = match event_target_value(&ev).as_str() {
+                "prefix" => RenameRule::Prefix(String::new()),
+                "replace" => RenameRule::Replace {
+                    from: String::new(),
+                    to: String::new(),
+                },
+                _ => RenameRule::Suffix("（副本）".to_string()),
+            };
+            settings.update(|settings| settings.rule = rule);
+        }
+    };
+    // 前缀、后缀或替换后的文字
+    let on_text_change = {
+        let settings = settings.clone();
+        move |ev| {
+            let text = event_target_value(&ev);
+            settings.update(|settings| match &mut settings.rule {
+                RenameRule::Prefix(value) | RenameRule::Suffix(value) => *value = text,
+                RenameRule::Replace { to, .. } => *to = text,
+            });
+        }
+    };
+    let on_from_change = {
+        let settings = settings.clone();
+        move |ev| {
+            let text = event_target_value(&ev);
+            settings.update(|settings| {
+                if let RenameRule::Replace { from, .. } = &mut settings.rule {
+                    *from = text;
+                }
+            });
+        }
+    };
+    let on_duplicate_change = {
+        let settings = settings.clone();
+        move |ev| {
+            settings
+                .update(|settings| settings.duplicate_shared_leaves = event_target_checked(&ev));
+        }
+    };
+    let on_clear = {
+        let node = node.clone();
+        move |_| node.set(None)
+    };
+
+    let kind = {
+        let settings = settings.clone();
+        move || settings.with(|settings| rule_kind(&settings.rule))
+    };
+    let is_hidden = {
+        let kind = kind.clone();
+        move || kind() != "replace"
+    };
+    let text = {
+        let settings = settings.clone();
+        move || {
+            settings.with(|settings| match &settings.rule {
+                RenameRule::Prefix(value) | RenameRule::Suffix(value) => value.clone(),
+                RenameRule::Replace { to, .. } => to.clone(),
+            })
+        }
+    };
+    let from = {
+        let settings = settings.clone();
+        move || {
+            settings.with(|settings| match &settings.rule {
+                RenameRule::Replace { from, .. } => from.clone(),
+                _ => String::new(),
+            })
+        }
+    };
+    let duplicate_shared_leaves = {
+        let settings = settings.clone();
+        move || settings.with(|settings| settings.duplicate_shared_leaves)
+    };
+    let copied_name = {
+        let node = node.clone();
+        move || node.get().map(|(_, name)| name)
+    };
+    let has_node = move || node.with(|node| node.is_some());
+
+    view! {
+        <Show when=has_node.clone()>
+            <div class="my-2 p-3 border border-gray-300 rounded-lg bg-gray-50 flex flex-wrap items-center gap-2">
+                <span class="font-semibold">"已复制："</span>
+                <span>{copied_name.clone()}</span>
+                <span class="ml-4">"新名称："</span>
+                <select
+                    class="px-2 py-1 border rounded-lg"
+                    on:change=on_kind_change.clone()
+                    prop:value=kind.clone()
+                >
+                    <option value="suffix">"加后缀"</option>
+                    <option value="prefix">"加前缀"</option>
+                    <option value="replace">"替换文字"</option>
+                </select>
+                <span class:hidden=is_hidden.clone()>
+                    <input
+                        type="text"
+                        placeholder="原文字"
+                        class="px-2 py-1 border rounded-lg mr-2"
+                        prop:value=from.clone()
+                        on:change=on_from_change.clone()
+                    />
+                    "→"
+                </span>
+                <input
+                    type="text"
+                    class="px-2 py-1 border rounded-lg"
+                    prop:value=text.clone()
+                    on:change=on_text_change.clone()
+                />
+                <label class="ml-4">
+                    <input
+                        type="checkbox"
+                        class="mr-1"
+                        prop:checked=duplicate_shared_leaves.clone()
+                        on:change=on_duplicate_change.clone()
+                    />
+                    "共享的叶节点也复制（否则副本引用原叶节点）"
+                </label>
+                <button
+                    class="ml-auto text-gray-500 hover:text-gray-700"
+                    on:click=on_clear.clone()
+                >
+                    "清空"
+                </button>
+            </div>
+        </Show>
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use leptos::{prelude::*, task::spawn_local};
+use serde_wasm_bindgen::{from_value, to_value};
+use shared::{ApplyDataRenamesArgs, DataValidationReport, MyResult};
+use tokio::sync::Mutex;
+
+use crate::{app::invoke, models::LeptosContext};
+
+// 列表中最多显示的名称数量，其余的只显示数量
+const MAX_SHOWN_NAMES: usize = 200;
+
+fn name_list(title: &'static str, names: Vec<String>) -> impl IntoView {
+    let count = names.len();
+    let hidden = count.saturating_sub(MAX_SHOWN_NAMES);
+    (count > 0).then(|| {
+        view! {
+            <div>
+                <div class="font-semibold">{title}"（"{count}"）："</div>
+                <div class="max-h-40 overflow-y-auto border rounded p-1 bg-white text-sm">
+                    {names
+                        .into_iter()
+                        .take(MAX_SHOWN_NAMES)
+                        .map(|name| view! { <span class="inline-block mr-3">{name}</span> })
+                        .collect_view()}
+                    {(hidden > 0).then(|| format!("……另有{}个", hidden))}
+                </div>
+            </div>
+        }
+    })
+}
+
+// 显示数据文件的检查结果，可以勾选疑似拼写错误的名称并在数据文件中改为建议的叶节点名称
+#[component]
+pub fn DataReportView(report: RwSignal<Option<DataValidationReport>>) -> impl IntoView {
+    let leptos_context = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
+    // 勾选的建议，以数据文件中的名称标识，默认全部勾选
+    let checked = RwSignal::new(HashSet::<String>::new());
+    Effect::new(move || {
+        let keys = report
+            .get()
+            .map(|report| {
+                report
+                    .suggestions
+                    .iter()
+                    .map(|suggestion| suggestion.key.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        checked.set(keys);
+    });
+
+    let on_apply = {
+        let leptos_context = leptos_context.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            let Some(curr_report) = report.get_untracked() else {
+                return;
+            };
+            let checked = checked.get_untracked();
+            let renames = curr_report
+                .suggestions
+                .into_iter()
+                .filter(|suggestion| checked.contains(&suggestion.key))
+                .collect::<Vec<_>>();
+            spawn_local(async move {
+                let context = leptos_context.lock().await;
+                if renames.is_empty() {
+                    context.err_msg.set("没有选中的重命名".to_string());
+                    return;
+                }
+                let args = ApplyDataRenamesArgs {
+                    filePath: curr_report.file_path,
+                    renames,
+                };
+                let args = to_value(&args).unwrap();
+                let result = invoke("request_apply_data_renames", args).await;
+                let result = from_value::<MyResult<String, String>>(result).unwrap();
+                match result {
+                    MyResult::Ok(message) => {
+                        report.set(None);
+                        context.err_msg.set(message);
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+    let on_close = move |_| {
+        report.set(None);
+    };
+
+    view! {
+        {move || {
+            let on_apply = on_apply.clone();
+            report
+                .get()
+                .map(|report| {
+                    let summary = if report.is_ok() {
+                        format!("数据文件包含全部{}个叶节点的数值，可以计算", report.leaf_count)
+                    } else {
+                        format!(
+                            "共{}个叶节点，{}个缺少数值，{}个数值不是有限数，无法计算",
+                            report.leaf_count,
+                            report.missing.len(),
+                            report.non_finite.len(),
+                        )
+                    };
+                    let has_suggestions = !report.suggestions.is_empty();
+                    view! {
+                        <div class="my-2 p-3 border border-gray-300 rounded-lg bg-gray-50 space-y-2">
+                            <div class="flex items-center gap-2">
+                                <span class="font-semibold">"数据文件检查"</span>
+                                <span class="text-gray-500 text-sm">{report.file_path.clone()}</span>
+                                <button
+                                    class="ml-auto text-gray-500 hover:text-gray-700"
+                                    on:click=on_close
+                                >
+                                    "关闭"
+                                </button>
+                            </div>
+                            <div>{summary}</div>
+                            {name_list("缺少数值的叶节点", report.missing.clone())}
+                            {name_list("数值不是有限数的叶节点", report.non_finite.clone())}
+                            {name_list("数据文件中未使用的名称", report.unused.clone())}
+                            <Show when=move || has_suggestions>
+                                <div class="font-semibold">"疑似拼写错误："</div>
+                                <div class="max-h-60 overflow-y-auto border rounded p-1 bg-white">
+                                    {report
+                                        .suggestions
+                                        .iter()
+                                        .map(|suggestion| {
+                                            let key = suggestion.key.clone();
+                                            let key_checked = key.clone();
+                                            view! {
+                                                <label class="block">
+                                                    <input
+                                                        type="checkbox"
+                                                        class="mr-1"
+                                                        prop:checked=move || {
+                                                            checked.with(|checked| checked.contains(&key_checked))
+                                                        }
+                                                        on:change=move |ev| {
+                                                            let key = key.clone();
+                                                            checked
+                                                                .update(|checked| {
+                                                                    if event_target_checked(&ev) {
+                                                                        checked.insert(key);
+                                                                    } else {
+                                                                        checked.remove(&key);
+                                                                    }
+                                                                });
+                                                        }
+                                                    />
+                                                    "\""{suggestion.key.clone()}"\" → \""
+                                                    {suggestion.leaf.clone()}"\""
+                                                    <span class="text-gray-500">
+                                                        {if suggestion.homophone { "（同音）" } else { "" }}
+                                                    </span>
+                                                </label>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </div>
+                                <button
+                                    class="px-4 py-1 bg-blue-600 text-white rounded-lg hover:bg-blue-700"
+                                    on:click=on_apply.clone()
+                                >
+                                    "将选中的重命名应用到数据文件"
+                                </button>
+                            </Show>
+                        </div>
+                    }
+                })
+        }}
+    }
+}
@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use leptos::{prelude::*, task::spawn_local};
+use serde_wasm_bindgen::{from_value, to_value};
+use shared::{
+    FindMode, FindReplaceArgs, FindReplacePreview, FindReplaceSettings, MyResult,
+    RemoveNodesResponse,
+};
+use tokio::sync::Mutex;
+
+use crate::{app::invoke, models::LeptosContext};
+
+// 预览中最多显示的重命名数量，其余的只显示数量
+const MAX_SHOWN_CHANGES: usize = 200;
+
+// 在所有节点名称中查找替换：输入时预览每个重命名和替换后的重名，确认后一次性执行
+#[component]
+pub fn FindReplacePanel(open: RwSignal<bool>) -> impl IntoView {
+    let leptos_context = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
+    let find = RwSignal::new(String::new());
+    let replace = RwSignal::new(String::new());
+    let (mode, set_mode) = signal(String::from("literal"));
+    let preview = RwSignal::new(None::<FindReplacePreview>);
+    // 查找内容为空、正则表达式无效等，无法预览的原因
+    let preview_error = RwSignal::new(String::new());
+
+    let settings = move || FindReplaceSettings {
+        find: find.get(),
+        replace: replace.get(),
+        mode: if mode.get() == "regex" {
+            FindMode::Regex
+        } else {
+            FindMode::Literal
+        },
+    };
+
+    // 输入变化时重新预览，只保留与当前输入对应的结果
+    Effect::new(move || {
+        if !open.get() {
+            return;
+        }
+        let curr_settings = settings();
+        if curr_settings.find.is_empty() {
+            preview.set(None);
+            preview_error.set(String::new());
+            return;
+        }
+        spawn_local(async move {
+            let args = to_value(&FindReplaceArgs {
+                settings: curr_settings.clone(),
+            })
+            .unwrap();
+            let response = invoke("query_find_replace_preview", args).await;
+            let response = from_value::<MyResult<FindReplacePreview, String>>(response).unwrap();
+            let latest = find.get_untracked() == curr_settings.find
+                && replace.get_untracked() == curr_settings.replace
+                && (mode.get_untracked() == "regex") == (curr_settings.mode == FindMode::Regex);
+            if !latest {
+                return;
+            }
+            match response {
+                MyResult::Ok(new_preview) => {
+                    preview.set(Some(new_preview));
+                    preview_error.set(String::new());
+                }
+                MyResult::Err(e) => {
+                    preview.set(None);
+                    preview_error.set(e);
+                }
+            }
+        });
+    });
+
+    let can_apply = move || {
+        preview.with(|preview| {
+            preview.as_ref().is_some_and(|preview| {
+                !preview.changes.is_empty()
+                    && preview
+                        .collisions
+                        .iter()
+                        .all(|collision| collision.error.is_none())
+            })
+        })
+    };
+
+    let on_apply = {
+        let leptos_context = leptos_context.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            let curr_settings = settings();
+            spawn_local(async move {
+                let mut context = leptos_context.lock().await;
+                let args = to_value(&FindReplaceArgs {
+                    settings: curr_settings,
+                })
+                .unwrap();
+                let response = invoke("request_find_replace", args).await;
+                let response =
+                    from_value::<MyResult<RemoveNodesResponse, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(response) => {
+                        let merged = response.removed.len();
+                        let changed = preview
+                            .get_untracked()
+                            .map(|preview| preview.changes.len())
+                            .unwrap_or_default();
+                        context.apply_removal(response).await;
+                        context.err_msg.set(format!(
+                            "已替换{}个名称，其中{}个叶节点合并到同名的节点",
+                            changed, merged
+                        ));
+                        find.set(String::new());
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+
+    view! {
+        <Show when=move || open.get()>
+            <div class="my-2 p-3 border border-gray-300 rounded-lg bg-gray-50 space-y-2">
+                <div class="flex flex-wrap items-center gap-2">
+                    <span class="font-semibold">"查找替换名称"</span>
+                    <input
+                        type="text"
+                        placeholder="查找"
+                        class="px-2 py-1 border rounded-lg"
+                        prop:value=find
+                        on:input=move |ev| find.set(event_target_value(&ev))
+                    />
+                    "→"
+                    <input
+                        type="text"
+                        placeholder="替换为"
+                        class="px-2 py-1 border rounded-lg"
+                        prop:value=replace
+                        on:input=move |ev| replace.set(event_target_value(&ev))
+                    />
+                    <select
+                        class="px-2 py-1 border rounded-lg"
+                        on:change=move |ev| set_mode.set(event_target_value(&ev))
+                        prop:value=mode
+                    >
+                        <option value="literal">"按原文"</option>
+                        <option value="regex">"正则表达式（可用$1引用分组）"</option>
+                    </select>
+                    <button
+                        class="ml-auto text-gray-500 hover:text-gray-700"
+                        on:click=move |_| open.set(false)
+                    >
+                        "关闭"
+                    </button>
+                </div>
+                <div class="text-red-600" class:hidden=move || preview_error.with(String::is_empty)>
+                    {preview_error}
+                </div>
+                {move || {
+                    preview
+                        .get()
+                        .map(|preview| {
+                            let count = preview.changes.len();
+                            let hidden = count.saturating_sub(MAX_SHOWN_CHANGES);
+                            view! {
+                                <div>"共"{count}"个名称将被替换"</div>
+                                <div class="max-h-60 overflow-y-auto border rounded p-1 bg-white text-sm">
+                                    {preview
+                                        .changes
+                                        .into_iter()
+                                        .take(MAX_SHOWN_CHANGES)
+                                        .map(|change| {
+                                            view! {
+                                                <div>
+                                                    "\""{change.old_name}"\" → \""{change.new_name}"\""
+                                                    <span class="text-gray-500">
+                                                        {if change.is_leaf { "（叶节点）" } else { "" }}
+                                                    </span>
+                                                </div>
+                                            }
+                                        })
+                                        .collect_view()}
+                                    {(hidden > 0).then(|| format!("……另有{}个", hidden))}
+                                </div>
+                                {(!preview.collisions.is_empty())
+                                    .then(|| {
+                                        view! {
+                                            <div class="font-semibold">"替换后重名："</div>
+                                            <div class="max-h-40 overflow-y-auto border rounded p-1 bg-white text-sm">
+                                                {preview
+                                                    .collisions
+                                                    .into_iter()
+                                                    .map(|collision| {
+                                                        let text = match &collision.error {
+                                                            Some(error) => error.clone(),
+                                                            None => {
+                                                                format!(
+                                                                    "\"{}\"合并到\"{}\"",
+                                                                    collision.old_names[1..].join("\"、\""),
+                                                                    collision.old_names[0],
+                                                                )
+                                                            }
+                                                        };
+                                                        view! {
+                                                            <div class:text-red-600=collision
+                                                                .error
+                                                                .is_some()>{text}</div>
+                                                        }
+                                                    })
+                                                    .collect_view()}
+                                            </div>
+                                        }
+                                    })}
+                            }
+                        })
+                }}
+                <button
+                    class="px-4 py-1 bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:opacity-50"
+                    prop:disabled=move || !can_apply()
+                    on:click=on_apply.clone()
+                >
+                    "全部替换"
+                </button>
+            </div>
+        </Show>
+    }
+}
@@ -0,0 +1,244 @@
+use std::sync::Arc;
+
+use leptos::{prelude::*, task::spawn_local};
+use serde_wasm_bindgen::{from_value, to_value};
+use shared::{
+    Algorithm, ChildrenMerge, MergeArgs, MergePreview, MergePreviewArgs, MergeSettings, MergeSide,
+    MyResult, NodeSearchResult, RemoveNodesResponse, SearchNodesArgs,
+};
+use tokio::sync::Mutex;
+
+use crate::{app::invoke, models::LeptosContext};
+
+fn algorithm_text(algorithm: &Option<Algorithm>) -> String {
+    match algorithm {
+        Some(algorithm) => algorithm.to_string(),
+        None => "无（叶节点）".to_string(),
+    }
+}
+
+fn names_text(names: &[String]) -> String {
+    if names.is_empty() {
+        "无".to_string()
+    } else {
+        names.join("、")
+    }
+}
+
+// 选择另一个节点合并到left：预览受影响的父节点，选择子节点和算法的取法后合并
+#[component]
+pub fn MergePanel(left: u64) -> impl IntoView {
+    let leptos_context = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
+    let query = RwSignal::new(String::new());
+    let results = RwSignal::new(Vec::<NodeSearchResult>::new());
+    let preview = RwSignal::new(None::<(u64, MergePreview)>);
+    let (children, set_children) = signal(String::from("union"));
+    let (algorithm, set_algorithm) = signal(String::from("left"));
+
+    Effect::new(move || {
+        let curr_query = query.get();
+        if curr_query.trim().is_empty() {
+            results.set(Vec::new());
+            return;
+        }
+        spawn_local(async move {
+            let args = to_value(&SearchNodesArgs {
+                query: curr_query.clone(),
+                parent: None,
+            })
+            .unwrap();
+            let response = invoke("query_search_nodes", args).await;
+            let response = from_value::<MyResult<Vec<NodeSearchResult>, String>>(response).unwrap();
+            if let MyResult::Ok(new_results) = response {
+                if query.get_untracked() == curr_query {
+                    results.set(
+                        new_results
+                            .into_iter()
+                            .filter(|result| result.id != left)
+                            .collect(),
+                    );
+                }
+            }
+        });
+    });
+
+    let on_pick = {
+        let leptos_context = leptos_context.clone();
+        move |right: u64| {
+            let leptos_context = leptos_context.clone();
+            spawn_local(async move {
+                let args = to_value(&MergePreviewArgs { left, right }).unwrap();
+                let response = invoke("query_merge_preview", args).await;
+                let response = from_value::<MyResult<MergePreview, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(new_preview) => {
+                        preview.set(Some((right, new_preview)));
+                        query.set(String::new());
+                    }
+                    MyResult::Err(e) => {
+                        leptos_context.lock().await.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+
+    let on_merge = {
+        let leptos_context = leptos_context.clone();
+        move |_| {
+            let Some((right, _)) = preview.get_untracked() else {
+                return;
+            };
+            let leptos_context = leptos_context.clone();
+            let settings = MergeSettings {
+                children: match children.get_untracked().as_str() {
+                    "left" => ChildrenMerge::KeepLeft,
+                    "right" => ChildrenMerge::KeepRight,
+                    _ => ChildrenMerge::Union,
+                },
+                algorithm: match algorithm.get_untracked().as_str() {
+                    "right" => MergeSide::Right,
+                    _ => MergeSide::Left,
+                },
+            };
+            spawn_local(async move {
+                let mut context = leptos_context.lock().await;
+                let args = to_value(&MergeArgs {
+                    left,
+                    right,
+                    settings,
+                })
+                .unwrap();
+                let response = invoke("request_merge", args).await;
+                let response =
+                    from_value::<MyResult<RemoveNodesResponse, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(response) => {
+                        context.apply_removal(response).await;
+                        preview.set(None);
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+
+    view! {
+        <div class="space-y-2 border-t pt-2">
+            <div class="font-semibold">"合并其他节点到此节点"</div>
+            <input
+                type="text"
+                placeholder="搜索要合并的节点"
+                class="w-full border rounded p-1"
+                prop:value=query
+                on:input=move |ev| query.set(event_target_value(&ev))
+            />
+            <div class="max-h-40 overflow-y-auto">
+                {move || {
+                    let on_pick = on_pick.clone();
+                    results
+                        .get()
+                        .into_iter()
+                        .map(|result| {
+                            let on_pick = on_pick.clone();
+                            let right = result.id;
+                            view! {
+                                <div
+                                    class="px-1 cursor-pointer hover:bg-gray-100 rounded"
+                                    on:click=move |_| on_pick(right)
+                                >
+                                    {result.name}
+                                    <span class="text-gray-500 text-sm">" id: "{result.id}</span>
+                                </div>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </div>
+            {move || {
+                let on_merge = on_merge.clone();
+                preview
+                    .get()
+                    .map(|(_, curr_preview)| {
+                        let left_algorithm = algorithm_text(&curr_preview.left_algorithm);
+                        let right_algorithm = algorithm_text(&curr_preview.right_algorithm);
+                        // 两者都有算法且不同时才需要选择
+                        let differing_algorithms = curr_preview.left_algorithm.is_some()
+                            && curr_preview.right_algorithm.is_some()
+                            && left_algorithm != right_algorithm;
+                        view! {
+                            <div class="p-2 border rounded bg-white text-sm space-y-1">
+                                <div>
+                                    "将\""{curr_preview.right_name.clone()}"\"合并到\""
+                                    {curr_preview.left_name.clone()}"\"，合并后删除前者"
+                                </div>
+                                <div>
+                                    "改为引用此节点的父节点："
+                                    {names_text(&curr_preview.rewritten_parents)}
+                                </div>
+                                <div>
+                                    "同时引用两者、将只保留一个引用的父节点："
+                                    {names_text(&curr_preview.shared_parents)}
+                                </div>
+                                <div>
+                                    "子节点：此节点"{curr_preview.left_children}"个，被合并的节点"
+                                    {curr_preview.right_children}"个，相同的"
+                                    {curr_preview.common_children}"个"
+                                </div>
+                                <div>
+                                    "算法：此节点"{left_algorithm}
+                                    "，被合并的节点"{right_algorithm}
+                                </div>
+                                <div class="flex items-center gap-2">
+                                    "子节点取："
+                                    <select
+                                        class="border rounded p-1"
+                                        on:change=move |ev| set_children.set(event_target_value(&ev))
+                                        prop:value=children
+                                    >
+                                        <option value="union">"两者的并集"</option>
+                                        <option value="left">"此节点的"</option>
+                                        <option value="right">"被合并的节点的"</option>
+                                    </select>
+                                </div>
+                                {differing_algorithms
+                                    .then(|| {
+                                        view! {
+                                            <div class="flex items-center gap-2">
+                                                "算法取："
+                                                <select
+                                                    class="border rounded p-1"
+                                                    on:change=move |ev| {
+                                                        set_algorithm.set(event_target_value(&ev))
+                                                    }
+                                                    prop:value=algorithm
+                                                >
+                                                    <option value="left">"此节点的"</option>
+                                                    <option value="right">"被合并的节点的"</option>
+                                                </select>
+                                            </div>
+                                        }
+                                    })}
+                                <div class="flex gap-2">
+                                    <button
+                                        class="px-4 py-1 bg-red-600 text-white rounded-lg hover:bg-red-700"
+                                        on:click=on_merge
+                                    >
+                                        "合并"
+                                    </button>
+                                    <button
+                                        class="text-gray-500 hover:text-gray-700"
+                                        on:click=move |_| preview.set(None)
+                                    >
+                                        "取消"
+                                    </button>
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+        </div>
+    }
+}
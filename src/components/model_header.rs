@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use leptos::{either::Either, prelude::*, task::spawn_local};
+use serde_wasm_bindgen::{from_value, to_value};
+use shared::{ModelHeader, MyResult, UpdateHeaderArgs};
+use tokio::sync::Mutex;
+
+use crate::{app::invoke, models::LeptosContext};
+
+// 显示并编辑模型文件的头部信息，header_data由所在页面在保存后刷新
+#[component]
+pub fn ModelHeaderView(
+    header_data: LocalResource<Option<ModelHeader>>,
+    file_path: Signal<String, LocalStorage>,
+) -> impl IntoView {
+    let leptos_context = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
+    let header = move || header_data.get().and_then(|header| header.take());
+
+    let (editing, set_editing) = signal(false);
+    let (title, set_title) = signal(String::new());
+    let (description, set_description) = signal(String::new());
+    let (authors, set_authors) = signal(String::new());
+    let (version, set_version) = signal(String::new());
+    let (changelog, set_changelog) = signal(String::new());
+
+    let on_edit = move |_| {
+        let header = header().unwrap_or_default();
+        set_title.set(header.title);
+        set_description.set(header.description);
+        set_authors.set(header.authors.join("，"));
+        set_version.set(header.version);
+        set_changelog.set(header.changelog);
+        set_editing.set(true);
+    };
+    let on_cancel = move |_| {
+        set_editing.set(false);
+    };
+    let on_confirm = {
+        let leptos_context = leptos_context.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            let header = ModelHeader {
+                title: title.get_untracked().trim().to_string(),
+                description: description.get_untracked(),
+                authors: authors
+                    .get_untracked()
+                    .split([',', '，'])
+                    .map(|author| author.trim().to_string())
+                    .filter(|author| !author.is_empty())
+                    .collect(),
+                version: version.get_untracked().trim().to_string(),
+                changelog: changelog.get_untracked(),
+                // 创建和修改时间由后端维护
+                ..Default::default()
+            };
+            spawn_local(async move {
+                let context = leptos_context.lock().await;
+                let args = to_value(&UpdateHeaderArgs { header }).unwrap();
+                let response = invoke("request_update_header", args).await;
+                let response = from_value::<MyResult<(), String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(_) => {
+                        set_editing.set(false);
+                        header_data.refetch();
+                        context.refresh_history().await;
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+
+    view! {
+        <div class="mb-4">
+            {move || {
+                if editing.get() {
+                    let on_confirm = on_confirm.clone();
+                    Either::Left(
+                        view! {
+                            <div class="grid grid-cols-[auto_1fr] gap-2 items-start max-w-2xl">
+                                <div>"标题："</div>
+                                <input
+                                    type="text"
+                                    class="border rounded p-1"
+                                    prop:value=title
+                                    on:input=move |ev| set_title.set(event_target_value(&ev))
+                                />
+                                <div>"作者（用逗号分隔）："</div>
+                                <input
+                                    type="text"
+                                    class="border rounded p-1"
+                                    prop:value=authors
+                                    on:input=move |ev| set_authors.set(event_target_value(&ev))
+                                />
+                                <div>"版本："</div>
+                                <input
+                                    type="text"
+                                    class="border rounded p-1"
+                                    prop:value=version
+                                    on:input=move |ev| set_version.set(event_target_value(&ev))
+                                />
+                                <div>"描述："</div>
+                                <textarea
+                                    class="border rounded p-1"
+                                    prop:value=description
+                                    on:input=move |ev| set_description.set(event_target_value(&ev))
+                                />
+                                <div>"更新记录："</div>
+                                <textarea
+                                    class="border rounded p-1 h-24"
+                                    prop:value=changelog
+                                    on:input=move |ev| set_changelog.set(event_target_value(&ev))
+                                />
+                            </div>
+                            <div class="mt-2">
+                                <button
+                                    class="mr-3 px-4 py-1 bg-blue-600 text-white rounded-lg hover:bg-blue-700"
+                                    on:click=on_confirm
+                                >
+                                    "确定"
+                                </button>
+                                <button
+                                    class="px-4 py-1 bg-gray-400 text-white rounded-lg hover:bg-gray-500"
+                                    on:click=on_cancel
+                                >
+                                    "取消"
+                                </button>
+                            </div>
+                        },
+                    )
+                } else {
+                    let header = header().unwrap_or_default();
+                    let title = if header.title.is_empty() {
+                        file_path.get()
+                    } else {
+                        header.title.clone()
+                    };
+                    Either::Right(
+                        view! {
+                            <div class="flex items-center gap-3">
+                                <h1 class="text-xl font-bold">{title}</h1>
+                                {(!header.version.is_empty())
+                                    .then(|| {
+                                        view! {
+                                            <span class="text-gray-500">"版本 "{header.version.clone()}</span>
+                                        }
+                                    })}
+                                <button class="text-blue-500 hover:text-blue-700" on:click=on_edit>
+                                    "编辑"
+                                </button>
+                            </div>
+                            <div class="text-sm text-gray-500">
+                                "文件："{file_path}
+                                {(!header.authors.is_empty())
+                                    .then(|| format!("　作者：{}", header.authors.join("，")))}
+                                {(!header.created.is_empty())
+                                    .then(|| format!("　创建于：{}", header.created))}
+                                {(!header.modified.is_empty())
+                                    .then(|| format!("　修改于：{}", header.modified))}
+                            </div>
+                            {(!header.description.is_empty())
+                                .then(|| {
+                                    view! { <p class="text-gray-700">{header.description.clone()}</p> }
+                                })}
+                        },
+                    )
+                }
+            }}
+        </div>
+    }
+}
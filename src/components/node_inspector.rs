@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use leptos::{prelude::*, task::spawn_local};
+use leptos_icons::Icon;
+use serde_wasm_bindgen::{from_value, to_value};
+use shared::{MyResult, NodeMetadata, UpdateMetadataArgs};
+use tokio::sync::Mutex;
+
+use crate::{
+    app::invoke,
+    components::merge_panel::MergePanel,
+    models::{LeptosContext, SelectedNode},
+};
+
+// 将多行文本拆分为参考文献列表，忽略空行
+fn split_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+// 标签可以用中文或英文逗号分隔
+fn split_tags(text: &str) -> Vec<String> {
+    text.split([',', '，'])
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+#[component]
+pub fn NodeInspector() -> impl IntoView {
+    let leptos_context = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
+    let SelectedNode(selected) = use_context::<SelectedNode>().unwrap();
+
+    let (description, set_description) = signal(String::new());
+    let (unit, set_unit) = signal(String::new());
+    let (expected_range, set_expected_range) = signal(String::new());
+    let (references, set_references) = signal(String::new());
+    let (tags, set_tags) = signal(String::new());
+    let (notes, set_notes) = signal(String::new());
+
+    let model_resource = LocalResource::new({
+        let leptos_context = leptos_context.clone();
+        let selected = selected.clone();
+        move || {
+            let leptos_context = leptos_context.clone();
+            let id = selected.get();
+            async move {
+                let id = id?;
+                let mut context = leptos_context.lock().await;
+                Some(context.get_model(id).await)
+            }
+        }
+    });
+    let selected_model = move || model_resource.get().and_then(|model| model.take());
+
+    // 选中的节点变化或其说明信息被更新时，重置编辑框的内容
+    Effect::new(move || {
+        if let Some(model) = selected_model() {
+            let metadata = model.metadata.get();
+            set_description.set(metadata.description);
+            set_unit.set(metadata.unit);
+            set_expected_range.set(metadata.expected_range);
+            set_references.set(metadata.references.join("\n"));
+            set_tags.set(metadata.tags.join("，"));
+            set_notes.set(metadata.notes);
+        }
+    });
+
+    let on_save = {
+        let leptos_context = leptos_context.clone();
+        let selected = selected.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            let id = match selected.get_untracked() {
+                Some(id) => id,
+                None => return,
+            };
+            let metadata = NodeMetadata {
+                description: description.get_untracked().trim().to_string(),
+                unit: unit.get_untracked().trim().to_string(),
+                expected_range: expected_range.get_untracked().trim().to_string(),
+                references: split_lines(&references.get_untracked()),
+                tags: split_tags(&tags.get_untracked()),
+                notes: notes.get_untracked(),
+            };
+            spawn_local(async move {
+                let mut context = leptos_context.lock().await;
+                if !context.models.contains_key(&id) {
+                    return;
+                }
+                let args = UpdateMetadataArgs { id, metadata };
+                let args = to_value(&args).unwrap();
+                let response = invoke("request_update_metadata", args).await;
+                let response = from_value::<MyResult<u64, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(id) => {
+                        context.update_model(id).await;
+                        context.refresh_history().await;
+                        context.err_msg.set("说明信息已更新".to_string());
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+    let on_close = {
+        let selected = selected.clone();
+        move |_| {
+            selected.set(None);
+        }
+    };
+
+    view! {
+        {move || {
+            let on_save = on_save.clone();
+            let on_close = on_close.clone();
+            selected_model()
+                .map(|model| {
+                    let name = model.name.clone();
+                    view! {
+                        <div class="w-80 shrink-0 p-3 border border-gray-300 rounded-lg bg-gray-50 space-y-2">
+                            <div class="flex items-center gap-2">
+                                <Icon width="16" height="16" icon=icondata::LuInfo />
+                                <span class="font-semibold">{name}</span>
+                                <span class="text-gray-500">"id: "{model.id}</span>
+                                <button
+                                    class="ml-auto text-gray-500 hover:text-gray-700"
+                                    on:click=on_close
+                                >
+                                    "关闭"
+                                </button>
+                            </div>
+                            <div>"描述："</div>
+                            <textarea
+                                class="w-full border rounded p-1"
+                                prop:value=description
+                                on:input=move |ev| set_description.set(event_target_value(&ev))
+                            />
+                            <div>"单位："</div>
+                            <input
+                                type="text"
+                                class="w-full border rounded p-1"
+                                prop:value=unit
+                                on:input=move |ev| set_unit.set(event_target_value(&ev))
+                            />
+                            <div>"预期范围（如60～100）："</div>
+                            <input
+                                type="text"
+                                class="w-full border rounded p-1"
+                                prop:value=expected_range
+                                on:input=move |ev| set_expected_range.set(event_target_value(&ev))
+                            />
+                            <div>"参考文献（每行一条）："</div>
+                            <textarea
+                                class="w-full border rounded p-1"
+                                prop:value=references
+                                on:input=move |ev| set_references.set(event_target_value(&ev))
+                            />
+                            <div>"标签（用逗号分隔）："</div>
+                            <input
+                                type="text"
+                                class="w-full border rounded p-1"
+                                prop:value=tags
+                                on:input=move |ev| set_tags.set(event_target_value(&ev))
+                            />
+                            <div>"备注："</div>
+                            <textarea
+                                class="w-full border rounded p-1"
+                                prop:value=notes
+                                on:input=move |ev| set_notes.set(event_target_value(&ev))
+                            />
+                            <button
+                                class="px-4 py-1 bg-blue-600 text-white rounded-lg hover:bg-blue-700"
+                                on:click=on_save
+                            >
+                                "保存说明信息"
+                            </button>
+                            <MergePanel left=model.id />
+                        </div>
+                    }
+                })
+        }}
+    }
+}
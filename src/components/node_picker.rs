@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use leptos::{prelude::*, task::spawn_local};
+use serde_wasm_bindgen::{from_value, to_value};
+use shared::{LinkChildArgs, MyResult, NodeSearchResult, SearchNodesArgs};
+use tokio::sync::Mutex;
+
+use crate::{app::invoke, models::LeptosContext};
+
+// 搜索已有的节点并将选中的节点加为parent的子节点，open为false时关闭
+#[component]
+pub fn NodePicker(parent: u64, open: RwSignal<bool>) -> impl IntoView {
+    let leptos_context = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
+    let query = RwSignal::new(String::new());
+    let results = RwSignal::new(Vec::<NodeSearchResult>::new());
+
+    // 输入变化时重新搜索，只保留与当前输入对应的结果
+    Effect::new(move || {
+        let curr_query = query.get();
+        spawn_local(async move {
+            let args = to_value(&SearchNodesArgs {
+                query: curr_query.clone(),
+                parent: Some(parent),
+            })
+            .unwrap();
+            let response = invoke("query_search_nodes", args).await;
+            let response = from_value::<MyResult<Vec<NodeSearchResult>, String>>(response).unwrap();
+            if let MyResult::Ok(new_results) = response {
+                if query.get_untracked() == curr_query {
+                    results.set(new_results);
+                }
+            }
+        });
+    });
+
+    let on_pick = move |id: u64| {
+        let leptos_context = leptos_context.clone();
+        spawn_local(async move {
+            let mut context = leptos_context.lock().await;
+            let args = to_value(&LinkChildArgs { id, parent }).unwrap();
+            let response = invoke("request_link_child", args).await;
+            let response = from_value::<MyResult<u64, String>>(response).unwrap();
+            match response {
+                MyResult::Ok(parent) => {
+                    context.update_model(parent).await;
+                    if context.models.contains_key(&id) {
+                        context.update_model(id).await;
+                    }
+                    context.refresh_history().await;
+                    open.set(false);
+                }
+                MyResult::Err(e) => {
+                    context.err_msg.set(e);
+                }
+            }
+        });
+    };
+
+    view! {
+        <div class="ml-6 my-1 p-2 border border-gray-300 rounded-lg bg-white w-96">
+            <div class="flex items-center gap-2">
+                <input
+                    type="text"
+                    placeholder="搜索节点名称"
+                    class="flex-1 px-2 py-1 border rounded"
+                    prop:value=query
+                    on:input=move |ev| query.set(event_target_value(&ev))
+                    autofocus
+                />
+                <button class="text-gray-500 hover:text-gray-700" on:click=move |_| open.set(false)>
+                    "取消"
+                </button>
+            </div>
+            <div class="max-h-60 overflow-y-auto mt-1">
+                {move || {
+                    let on_pick = on_pick.clone();
+                    results
+                        .get()
+                        .into_iter()
+                        .map(|result| {
+                            let on_pick = on_pick.clone();
+                            let id = result.id;
+                            view! {
+                                <div
+                                    class="flex items-center gap-2 px-1 cursor-pointer hover:bg-gray-100 rounded"
+                                    on:click=move |_| on_pick(id)
+                                >
+                                    <span>{result.name}</span>
+                                    <span class="text-gray-500 text-sm">
+                                        {if result.is_leaf { "叶节点" } else { "非叶节点" }}
+                                        "，引用计数："
+                                        {result.ref_count}
+                                    </span>
+                                </div>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </div>
+            <p class="text-gray-500 text-sm">"已排除自身、祖先节点和已有的子节点"</p>
+        </div>
+    }
+}
@@ -1,5 +1,6 @@
+use crate::components::clipboard::paste_copy;
 use crate::components::tree_node_children::TreeNodeChildren;
-use crate::models::TreeNodeModel;
+use crate::models::{Clipboard, DraggedNode, SelectedNode, TreeNodeModel};
 use crate::{
     app::{invoke, terminal_log},
     models::LeptosContext,
@@ -7,7 +8,7 @@ use crate::{
 use leptos::server_fn::response;
 use leptos::{
     either::Either,
-    ev::{Event, KeyboardEvent, MouseEvent},
+    ev::{DragEvent, Event, KeyboardEvent, MouseEvent},
     leptos_dom::logging::console_log,
     prelude::*,
     task::spawn_local,
@@ -15,13 +16,19 @@ use leptos::{
 use leptos_icons::Icon;
 use serde::de::value;
 use serde_wasm_bindgen::{from_value, to_value};
-use shared::{DeleteArgs, DeleteResponse, IdArgs, MyResult, RenameArgs, RenameResponse};
+use shared::{
+    DeleteArgs, DeleteResponse, DeleteSubtreeArgs, IdArgs, MoveArgs, MyResult, RemoveNodesResponse,
+    RenameArgs, RenameResponse, UnshareArgs, UnshareResponse,
+};
 use std::sync::{Arc, RwLock};
 use tokio::sync::Mutex;
 
 #[component]
 pub fn TreeNode(tree_node_model: TreeNodeModel, parent: Option<u64>) -> impl IntoView {
     let leptos_context = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
+    let SelectedNode(selected) = use_context::<SelectedNode>().unwrap();
+    let DraggedNode(dragged) = use_context::<DraggedNode>().unwrap();
+    let clipboard = use_context::<Clipboard>().unwrap();
 
     let TreeNodeModel {
         id,
@@ -29,11 +36,13 @@ pub fn TreeNode(tree_node_model: TreeNodeModel, parent: Option<u64>) -> impl Int
         ref_count,
         expand_signal,
         value,
+        metadata,
     } = tree_node_model;
 
     let (expanded, set_expanded) = signal(false);
     let (editing, set_editing) = signal(false);
     let (new_name, set_new_name) = signal(String::new());
+    let (drag_over, set_drag_over) = signal(false);
 
     let on_rename = {
         let leptos_context = leptos_context.clone();
@@ -63,24 +72,27 @@ pub fn TreeNode(tree_node_model: TreeNodeModel, parent: Option<u64>) -> impl Int
 
                 let result = from_value::<MyResult<RenameResponse, String>>(result).unwrap();
                 match result {
-                    MyResult::Ok(response) => match response {
-                        RenameResponse::RemoveSelfUpdateRelated {
-                            id_to_remove,
-                            ids_to_update,
-                        } => {
-                            console_log(&format!("remove id: {}", id_to_remove));
-                            context.models.remove(&id_to_remove);
-                            for parent in ids_to_update {
-                                if context.models.contains_key(&parent) {
-                                    context.update_model(parent).await;
+                    MyResult::Ok(response) => {
+                        match response {
+                            RenameResponse::RemoveSelfUpdateRelated {
+                                id_to_remove,
+                                ids_to_update,
+                            } => {
+                                console_log(&format!("remove id: {}", id_to_remove));
+                                context.models.remove(&id_to_remove);
+                                for parent in ids_to_update {
+                                    if context.models.contains_key(&parent) {
+                                        context.update_model(parent).await;
+                                    }
                                 }
                             }
+                            RenameResponse::RenameSelf(new_name) => {
+                                console_log(&format!("rename id: {}", id));
+                                context.update_model(id).await;
+                            }
                         }
-                        RenameResponse::RenameSelf(new_name) => {
-                            console_log(&format!("rename id: {}", id));
-                            context.update_model(id).await;
-                        }
-                    },
+                        context.refresh_history().await;
+                    }
                     MyResult::Err(e) => {
                         context.err_msg.set(e);
                     }
@@ -127,6 +139,32 @@ pub fn TreeNode(tree_node_model: TreeNodeModel, parent: Option<u64>) -> impl Int
                                 context.update_model(parent).await;
                             }
                         }
+                        context.refresh_history().await;
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+    // 删除子树前后端会列出将被删除的节点请用户确认
+    let on_delete_subtree = {
+        let leptos_context = leptos_context.clone();
+        move |_| {
+            let Some(parent) = parent else {
+                return;
+            };
+            let leptos_context = leptos_context.clone();
+            spawn_local(async move {
+                let mut context = leptos_context.lock().await;
+                let args = to_value(&DeleteSubtreeArgs { id, parent }).unwrap();
+                let response = invoke("request_delete_subtree", args).await;
+                let response =
+                    from_value::<MyResult<RemoveNodesResponse, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(response) => {
+                        context.apply_removal(response).await;
                     }
                     MyResult::Err(e) => {
                         context.err_msg.set(e);
@@ -136,11 +174,13 @@ pub fn TreeNode(tree_node_model: TreeNodeModel, parent: Option<u64>) -> impl Int
         }
     };
     let expand_signal2 = expand_signal.clone();
+    let expand_signal3 = expand_signal.clone();
 
     let has_children = move || expand_signal.get().is_some();
     let has_children2 = has_children.clone();
     let has_children3 = has_children.clone();
     let has_children4 = has_children.clone();
+    let has_children5 = has_children.clone();
 
     let toggle_expand = move |_| {
         set_expanded.set(!expanded.get());
@@ -164,6 +204,7 @@ pub fn TreeNode(tree_node_model: TreeNodeModel, parent: Option<u64>) -> impl Int
                 match response {
                     MyResult::Ok(id) => {
                         context.update_model(id).await;
+                        context.refresh_history().await;
                     }
                     MyResult::Err(e) => {
                         context.err_msg.set(e);
@@ -177,9 +218,195 @@ pub fn TreeNode(tree_node_model: TreeNodeModel, parent: Option<u64>) -> impl Int
         set_new_name.set(event_target_value(&ev));
     };
 
+    let is_selected = {
+        let selected = selected.clone();
+        move || selected.get() == Some(id)
+    };
+    let on_select = {
+        let selected = selected.clone();
+        move |_| {
+            selected.set(Some(id));
+        }
+    };
+
+    let on_copy = {
+        let name = name.clone();
+        let clipboard_node = clipboard.node.clone();
+        move |_| {
+            clipboard_node.set(Some((id, name.get_untracked())));
+        }
+    };
+    // 在同一个父节点下创建副本，紧跟在原节点之后
+    let on_duplicate = {
+        let leptos_context = leptos_context.clone();
+        let settings = clipboard.settings.clone();
+        move |_| {
+            if let Some(parent) = parent {
+                spawn_local(paste_copy(
+                    leptos_context.clone(),
+                    id,
+                    parent,
+                    settings.get_untracked(),
+                ));
+            }
+        }
+    };
+
+    // 拆分共享节点：在当前父节点下换成副本，深复制时子节点按复制设置命名
+    let (unsharing, set_unsharing) = signal(false);
+    let (unshare_name, set_unshare_name) = signal(String::new());
+    let (unshare_deep, set_unshare_deep) = signal(false);
+    let on_unshare_start = {
+        let name = name.clone();
+        move |_| {
+            set_unshare_name.set(format!("{}（独立）", name.get_untracked()));
+            set_unsharing.set(true);
+        }
+    };
+    let on_unshare = {
+        let leptos_context = leptos_context.clone();
+        let settings = clipboard.settings.clone();
+        let selected = selected.clone();
+        move |_| {
+            let Some(parent) = parent else {
+                return;
+            };
+            let leptos_context = leptos_context.clone();
+            let selected = selected.clone();
+            let new_name = unshare_name.get_untracked().trim().to_string();
+            let unshare_args = UnshareArgs {
+                id,
+                parent,
+                newName: new_name.clone(),
+                copySettings: unshare_deep
+                    .get_untracked()
+                    .then(|| settings.get_untracked()),
+            };
+            spawn_local(async move {
+                let mut context = leptos_context.lock().await;
+                let unshare_args = to_value(&unshare_args).unwrap();
+                let response = invoke("request_unshare", unshare_args).await;
+                let response = from_value::<MyResult<UnshareResponse, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(UnshareResponse {
+                        new_id,
+                        created,
+                        ids_to_update,
+                    }) => {
+                        for id in ids_to_update {
+                            if context.models.contains_key(&id) {
+                                context.update_model(id).await;
+                            }
+                        }
+                        context.refresh_history().await;
+                        context.err_msg.set(format!(
+                            "已拆分：新节点\"{}\"（id: {}）替换了此处的原节点，共新建{}个节点",
+                            new_name, new_id, created
+                        ));
+                        selected.set(Some(new_id));
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+            set_unsharing.set(false);
+        }
+    };
+
+    // 根节点不能拖动，节点只能拖到可展开的节点上，成为其最后一个子节点
+    let on_drag_start = {
+        let dragged = dragged.clone();
+        move |_: DragEvent| {
+            dragged.set(parent.map(|parent| (id, parent)));
+        }
+    };
+    let on_drag_end = {
+        let dragged = dragged.clone();
+        move |_: DragEvent| {
+            dragged.set(None);
+        }
+    };
+    let can_drop = {
+        let dragged = dragged.clone();
+        move || {
+            expand_signal3.get_untracked().is_some()
+                && dragged
+                    .get_untracked()
+                    .is_some_and(|(dragged_id, dragged_parent)| {
+                        dragged_id != id && dragged_parent != id
+                    })
+        }
+    };
+    let on_drag_over = {
+        let can_drop = can_drop.clone();
+        move |ev: DragEvent| {
+            if can_drop() {
+                ev.prevent_default();
+                set_drag_over.set(true);
+            }
+        }
+    };
+    let on_drag_leave = move |_: DragEvent| {
+        set_drag_over.set(false);
+    };
+    let on_drop = {
+        let leptos_context = leptos_context.clone();
+        let dragged = dragged.clone();
+        move |ev: DragEvent| {
+            set_drag_over.set(false);
+            if !can_drop() {
+                return;
+            }
+            ev.prevent_default();
+            let Some((dragged_id, old_parent)) = dragged.get_untracked() else {
+                return;
+            };
+            dragged.set(None);
+            let leptos_context = leptos_context.clone();
+            spawn_local(async move {
+                let mut context = leptos_context.lock().await;
+                let move_args = MoveArgs {
+                    id: dragged_id,
+                    oldParent: old_parent,
+                    newParent: id,
+                    index: None,
+                };
+                let move_args = to_value(&move_args).unwrap();
+                let response = invoke("request_move", move_args).await;
+                let response = from_value::<MyResult<Vec<u64>, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(ids_to_update) => {
+                        for id in ids_to_update {
+                            if context.models.contains_key(&id) {
+                                context.update_model(id).await;
+                            }
+                        }
+                        context.refresh_history().await;
+                        set_expanded.set(true);
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+
     view! {
         // Node Header
-        <div class="flex items-center gap-2 cursor-pointer hover:bg-gray-100 p-1 rounded-md">
+        <div
+            class="flex items-center gap-2 cursor-pointer hover:bg-gray-100 p-1 rounded-md"
+            class:bg-blue-50=is_selected
+            class:ring-2=drag_over
+            class:ring-blue-400=drag_over
+            draggable=if parent.is_some() { "true" } else { "false" }
+            on:dragstart=on_drag_start
+            on:dragend=on_drag_end
+            on:dragover=on_drag_over
+            on:dragleave=on_drag_leave
+            on:drop=on_drop
+        >
             // Expand/Collapse Button for Parent Nodes
             {move || {
                 // let has_children = has_children.clone();
@@ -245,12 +472,20 @@ pub fn TreeNode(tree_node_model: TreeNodeModel, parent: Option<u64>) -> impl Int
                     )
                 }
             }} // {/* Delete Button */}
+            {move || {
+                let unit = metadata.get().unit;
+                (!unit.is_empty())
+                    .then(|| view! { <span class="text-gray-500">"单位："{unit}</span> })
+            }}
             {
                 move || value.get().map(|value| {
                     view! { <span class="ml-3 text-green-500 hover:text-green-300">"计算结果："{value}</span> }
                 })
             }
             <div class="ml-auto">
+                <button class="text-blue-500 hover:text-blue-700 mr-3" on:click=on_select>
+                    <Icon width="16" height="16" icon=icondata::LuInfo />
+                </button>
                 <button
                     class="text-blue-500 hover:text-blue-700 mr-3"
                     on:click=request_can_expand_toggling
@@ -264,12 +499,89 @@ pub fn TreeNode(tree_node_model: TreeNodeModel, parent: Option<u64>) -> impl Int
                     }}
                 </button>
 
+                <button class="text-blue-500 hover:text-blue-700 mr-3" title="复制" on:click=on_copy>
+                    <Icon width="16" height="16" icon=icondata::LuCopy />
+                </button>
+                {parent
+                    .is_some()
+                    .then(|| {
+                        view! {
+                            <button
+                                class="text-blue-500 hover:text-blue-700 mr-3"
+                                title="创建副本"
+                                on:click=on_duplicate
+                            >
+                                <Icon width="16" height="16" icon=icondata::LuFiles />
+                            </button>
+                        }
+                    })}
+                {
+                    let ref_count = ref_count.clone();
+                    move || {
+                        (parent.is_some() && ref_count.get() > 1)
+                            .then(|| {
+                                let on_unshare_start = on_unshare_start.clone();
+                                view! {
+                                    <button
+                                        class="text-blue-500 hover:text-blue-700 mr-3"
+                                        title="拆分为独立副本"
+                                        on:click=on_unshare_start
+                                    >
+                                        <Icon width="16" height="16" icon=icondata::LuUnlink />
+                                    </button>
+                                }
+                            })
+                    }
+                }
                 <div class="inline-block mr-3">"引用计数："{ref_count}</div>
-                <button class="text-red-500 hover:text-red-700" on:click=on_delete>
+                <button class="text-red-500 hover:text-red-700" title="删除" on:click=on_delete>
                     <Icon width="16" height="16" icon=icondata::LuTrash />
                 </button>
+                {move || {
+                    (parent.is_some() && has_children5())
+                        .then(|| {
+                            let on_delete_subtree = on_delete_subtree.clone();
+                            view! {
+                                <button
+                                    class="text-red-500 hover:text-red-700 ml-3"
+                                    title="删除子树"
+                                    on:click=on_delete_subtree
+                                >
+                                    <Icon width="16" height="16" icon=icondata::LuTrash2 />
+                                </button>
+                            }
+                        })
+                }}
             </div>
         </div>
+        <Show when=move || unsharing.get()>
+            <div class="ml-6 my-1 p-2 border border-gray-300 rounded-lg bg-white flex items-center gap-2">
+                <span>"在此处替换为新节点："</span>
+                <input
+                    type="text"
+                    class="px-2 py-1 border rounded"
+                    prop:value=unshare_name
+                    on:change=move |ev| set_unshare_name.set(event_target_value(&ev))
+                />
+                <select
+                    class="px-2 py-1 border rounded"
+                    on:change=move |ev| set_unshare_deep.set(event_target_value(&ev) == "deep")
+                    prop:value=move || if unshare_deep.get() { "deep" } else { "shallow" }
+                >
+                    <option value="shallow">"浅复制（共用原节点的子节点）"</option>
+                    <option value="deep">"深复制（子节点按复制设置命名）"</option>
+                </select>
+                <button class="text-blue-500 hover:text-blue-700" on:click=on_unshare.clone()>
+                    "拆分"
+                </button>
+                <button
+                    class="text-gray-500 hover:text-gray-700"
+                    on:click=move |_| set_unsharing.set(false)
+                >
+                    "取消"
+                </button>
+            </div>
+        </Show>
         <div class="pl-4 border-l border-gray-300">
             // {/* Children Nodes (if expanded) */}
             {move || {
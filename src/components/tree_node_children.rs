@@ -6,17 +6,24 @@ use std::{
 
 use futures::future::join_all;
 use leptos::{
-    either::Either, ev::Event, leptos_dom::logging::console_log, prelude::*, task::spawn_local,
+    either::Either,
+    ev::{DragEvent, Event},
+    leptos_dom::logging::console_log,
+    prelude::*,
+    task::spawn_local,
 };
 use leptos_icons::Icon;
 use serde_wasm_bindgen::{from_value, to_value};
-use shared::{Algorithm, IdArgs, MyResult, UpdateAlgorithmArgs};
+use shared::{
+    Algorithm, ChildrenOrder, IdArgs, MyResult, ReorderChildArgs, SortChildrenArgs,
+    UpdateAlgorithmArgs,
+};
 use tokio::sync::Mutex;
 
 use crate::{
     app::{invoke, terminal_log},
-    components::tree_node::TreeNode,
-    models::{ExpandSignal, LeptosContext, TreeNodeModel},
+    components::{clipboard::paste_copy, node_picker::NodePicker, tree_node::TreeNode},
+    models::{Clipboard, ExpandSignal, LeptosContext, TreeNodeModel},
 };
 
 // asynchronously retrieve the tree_node_model for each child, if every mode is ready, display the children
@@ -30,6 +37,10 @@ use crate::{
 #[component]
 pub fn TreeNodeChildren(id: u64, expand_signal: ExpandSignal) -> impl IntoView {
     let leptos_context = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
+    let Clipboard {
+        node: copied,
+        settings: copy_settings,
+    } = use_context::<Clipboard>().unwrap();
     let ExpandSignal {
         algorithm,
         children: children_ids,
@@ -142,6 +153,7 @@ pub fn TreeNodeChildren(id: u64, expand_signal: ExpandSignal) -> impl IntoView {
                     MyResult::Ok(id) => {
                         let mut context = leptos_context.lock().await;
                         context.update_model(id).await;
+                        context.refresh_history().await;
                     }
                     MyResult::Err(e) => {
                         terminal_log(&format!("更新算法失败：{}", e)).await;
@@ -164,6 +176,7 @@ pub fn TreeNodeChildren(id: u64, expand_signal: ExpandSignal) -> impl IntoView {
                 match response {
                     MyResult::Ok(id) => {
                         context.update_model(id).await;
+                        context.refresh_history().await;
                     }
                     MyResult::Err(e) => {
                         terminal_log(&e).await;
@@ -172,6 +185,89 @@ pub fn TreeNodeChildren(id: u64, expand_signal: ExpandSignal) -> impl IntoView {
             });
         }
     };
+    let on_paste = {
+        let leptos_context = leptos_context.clone();
+        let copied = copied.clone();
+        move |_| {
+            if let Some((copied_id, _)) = copied.get_untracked() {
+                spawn_local(paste_copy(
+                    leptos_context.clone(),
+                    copied_id,
+                    id,
+                    copy_settings.get_untracked(),
+                ));
+            }
+        }
+    };
+    let has_copied = move || copied.with(|copied| copied.is_some());
+    let picking = RwSignal::new(false);
+
+    // 将第from个子节点移到第to个位置
+    let reorder = {
+        let leptos_context = leptos_context.clone();
+        move |from: usize, to: usize| {
+            let leptos_context = leptos_context.clone();
+            if from == to {
+                return;
+            }
+            spawn_local(async move {
+                let mut context = leptos_context.lock().await;
+                let reorder_args = ReorderChildArgs {
+                    id,
+                    from: from as u64,
+                    to: to as u64,
+                };
+                let reorder_args = to_value(&reorder_args).unwrap();
+                let response = invoke("request_reorder_child", reorder_args).await;
+                let response = from_value::<MyResult<u64, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(id) => {
+                        context.update_model(id).await;
+                        context.refresh_history().await;
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+    // 拖动子节点左侧的把手调整顺序，记录被拖动的子节点的位置
+    let (reorder_from, set_reorder_from) = signal::<Option<usize>>(None);
+
+    let (sort_order, set_sort_order) = signal(String::from("name"));
+    let (descending, set_descending) = signal(false);
+    let on_sort = {
+        let leptos_context = leptos_context.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            spawn_local(async move {
+                let mut context = leptos_context.lock().await;
+                let sort_args = SortChildrenArgs {
+                    id,
+                    order: match sort_order.get_untracked().as_str() {
+                        "pinyin" => ChildrenOrder::Pinyin,
+                        "value" => ChildrenOrder::Value,
+                        _ => ChildrenOrder::Name,
+                    },
+                    descending: descending.get_untracked(),
+                };
+                let sort_args = to_value(&sort_args).unwrap();
+                let response = invoke("request_sort_children", sort_args).await;
+                let response = from_value::<MyResult<u64, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(id) => {
+                        context.update_model(id).await;
+                        context.refresh_history().await;
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+
     view! {
         <div class="transition-opacity duration-500 ease-in-out opacity-100">
             <div class="flex items-center gap-2 cursor-pointer hover:bg-gray-100 p-1 rounded-md">
@@ -204,15 +300,103 @@ pub fn TreeNodeChildren(id: u64, expand_signal: ExpandSignal) -> impl IntoView {
                     <option value=Algorithm::Min
                         .to_string()>{Algorithm::Min.to_string()}</option>
                 </select>
-
+                <div class="inline-block ml-4">"排序："</div>
+                <select
+                    class="inline-block border border-gray-300 rounded p-2"
+                    on:change=move |ev| set_sort_order.set(event_target_value(&ev))
+                    prop:value=sort_order
+                >
+                    <option value="name">"名称"</option>
+                    <option value="pinyin">"拼音"</option>
+                    <option value="value">"计算结果"</option>
+                </select>
+                <label>
+                    <input
+                        type="checkbox"
+                        class="mr-1"
+                        prop:checked=descending
+                        on:change=move |ev| set_descending.set(event_target_checked(&ev))
+                    />
+                    "降序"
+                </label>
+                <button class="text-blue-500 hover:text-blue-700" on:click=on_sort>
+                    <Icon width="16" height="16" icon=icondata::LuArrowDownAZ />
+                </button>
             </div>
 
 
             <For
                 each=move||children.get()
                 key=|(index, _model)| *index
-                children=move |(_id, model)| {
-                    view! { <TreeNode tree_node_model=model parent=Some(id) /> }.into_any()
+                children=move |(key, model)| {
+                    let position = move || {
+                        children
+                            .with_untracked(|children| {
+                                children.iter().position(|(index, _)| *index == key).unwrap_or(0)
+                            })
+                    };
+                    let reorder_up = reorder.clone();
+                    let reorder_down = reorder.clone();
+                    let reorder_drop = reorder.clone();
+                    view! {
+                        <div
+                            class="flex items-start"
+                            on:dragover=move |ev: DragEvent| {
+                                if reorder_from.get_untracked().is_some() {
+                                    ev.prevent_default();
+                                }
+                            }
+                            on:drop=move |ev: DragEvent| {
+                                if let Some(from) = reorder_from.get_untracked() {
+                                    ev.prevent_default();
+                                    set_reorder_from.set(None);
+                                    reorder_drop(from, position());
+                                }
+                            }
+                        >
+                            <div class="flex flex-col items-center pt-1 text-gray-400">
+                                <div
+                                    class="cursor-move hover:text-gray-600"
+                                    draggable="true"
+                                    on:dragstart=move |_| set_reorder_from.set(Some(position()))
+                                    on:dragend=move |_| set_reorder_from.set(None)
+                                >
+                                    <Icon width="12" height="12" icon=icondata::LuGripVertical />
+                                </div>
+                                <button
+                                    class="hover:text-blue-700 disabled:opacity-30"
+                                    prop:disabled=move || {
+                                        children.track();
+                                        position() == 0
+                                    }
+                                    on:click=move |_| {
+                                        let position = position();
+                                        if position > 0 {
+                                            reorder_up(position, position - 1);
+                                        }
+                                    }
+                                >
+                                    <Icon width="12" height="12" icon=icondata::LuArrowUp />
+                                </button>
+                                <button
+                                    class="hover:text-blue-700 disabled:opacity-30"
+                                    prop:disabled=move || position() + 1 >= children.with(Vec::len)
+                                    on:click=move |_| {
+                                        let position = position();
+                                        if position + 1 < children.with_untracked(Vec::len) {
+                                            reorder_down(position, position + 1);
+                                        }
+                                    }
+                                >
+                                    <Icon width="12" height="12" icon=icondata::LuArrowDown />
+                                </button>
+                            </div>
+                            <div class="flex-1">
+                                <TreeNode tree_node_model=model parent=Some(id) />
+                            </div>
+                        </div>
+                    }
+                        .into_any()
                 }
             />
 
@@ -221,7 +405,21 @@ pub fn TreeNodeChildren(id: u64, expand_signal: ExpandSignal) -> impl IntoView {
                 <button on:click=on_add class="text-blue-500 hover:text-blue-700">
                     "添加"
                 </button>
+                <button
+                    on:click=move |_| picking.set(true)
+                    class="text-blue-500 hover:text-blue-700"
+                >
+                    "添加已有节点"
+                </button>
+                <Show when=has_copied.clone()>
+                    <button on:click=on_paste.clone() class="text-blue-500 hover:text-blue-700">
+                        "粘贴副本"
+                    </button>
+                </Show>
             </div>
+            <Show when=move || picking.get()>
+                <NodePicker parent=id open=picking />
+            </Show>
         </div>
     }
 }
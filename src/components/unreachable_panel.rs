@@ -0,0 +1,274 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use leptos::{prelude::*, task::spawn_local};
+use serde_wasm_bindgen::{from_value, to_value};
+use shared::{
+    LinkChildArgs, MyResult, NodeSearchResult, RemoveNodesResponse, RemoveUnreachableArgs,
+    SearchNodesArgs, UnreachableNode,
+};
+use tokio::sync::Mutex;
+use wasm_bindgen::JsValue;
+
+use crate::{app::invoke, models::LeptosContext};
+
+async fn query_unreachable(nodes: RwSignal<Vec<UnreachableNode>>, err_msg: ArcRwSignal<String>) {
+    let response = invoke("query_unreachable_nodes", JsValue::NULL).await;
+    let response = from_value::<MyResult<Vec<UnreachableNode>, String>>(response).unwrap();
+    match response {
+        MyResult::Ok(new_nodes) => nodes.set(new_nodes),
+        MyResult::Err(e) => err_msg.set(e),
+    }
+}
+
+// 列出从根节点无法到达的节点及其原来的父节点，可以挂接到选定的节点下或永久删除
+#[component]
+pub fn UnreachablePanel(open: RwSignal<bool>) -> impl IntoView {
+    let leptos_context = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
+    let nodes = RwSignal::new(Vec::<UnreachableNode>::new());
+    // 勾选要删除的节点
+    let checked = RwSignal::new(HashSet::<u64>::new());
+    // 正在选择挂接位置的节点
+    let attaching = RwSignal::new(None::<(u64, String)>);
+    let target_query = RwSignal::new(String::new());
+    let targets = RwSignal::new(Vec::<NodeSearchResult>::new());
+
+    let refresh = {
+        let leptos_context = leptos_context.clone();
+        move || {
+            let leptos_context = leptos_context.clone();
+            spawn_local(async move {
+                let err_msg = leptos_context.lock().await.err_msg.clone();
+                query_unreachable(nodes, err_msg).await;
+                checked.set(HashSet::new());
+            });
+        }
+    };
+    Effect::new({
+        let refresh = refresh.clone();
+        move || {
+            if open.get() {
+                attaching.set(None);
+                refresh();
+            }
+        }
+    });
+
+    // 挂接位置只能是非叶节点
+    Effect::new(move || {
+        let query = target_query.get();
+        if attaching.with(|attaching| attaching.is_none()) {
+            return;
+        }
+        spawn_local(async move {
+            let args = to_value(&SearchNodesArgs {
+                query: query.clone(),
+                parent: None,
+            })
+            .unwrap();
+            let response = invoke("query_search_nodes", args).await;
+            let response = from_value::<MyResult<Vec<NodeSearchResult>, String>>(response).unwrap();
+            if let MyResult::Ok(results) = response {
+                if target_query.get_untracked() == query {
+                    targets.set(
+                        results
+                            .into_iter()
+                            .filter(|result| !result.is_leaf)
+                            .collect(),
+                    );
+                }
+            }
+        });
+    });
+
+    let on_attach = {
+        let leptos_context = leptos_context.clone();
+        let refresh = refresh.clone();
+        move |parent: u64| {
+            let Some((id, _)) = attaching.get_untracked() else {
+                return;
+            };
+            let leptos_context = leptos_context.clone();
+            let refresh = refresh.clone();
+            spawn_local(async move {
+                let mut context = leptos_context.lock().await;
+                let args = to_value(&LinkChildArgs { id, parent }).unwrap();
+                let response = invoke("request_link_child", args).await;
+                let response = from_value::<MyResult<u64, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(parent) => {
+                        if context.models.contains_key(&parent) {
+                            context.update_model(parent).await;
+                        }
+                        context.refresh_history().await;
+                        attaching.set(None);
+                        refresh();
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+
+    let on_remove = {
+        let leptos_context = leptos_context.clone();
+        let refresh = refresh.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            let refresh = refresh.clone();
+            let ids = checked.get_untracked().into_iter().collect::<Vec<u64>>();
+            spawn_local(async move {
+                let mut context = leptos_context.lock().await;
+                let args = to_value(&RemoveUnreachableArgs { ids }).unwrap();
+                let response = invoke("request_remove_unreachable", args).await;
+                let response =
+                    from_value::<MyResult<RemoveNodesResponse, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(response) => {
+                        let count = response.removed.len();
+                        context.apply_removal(response).await;
+                        context.err_msg.set(format!("已删除{}个不可达节点", count));
+                        refresh();
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+
+    view! {
+        <Show when=move || open.get()>
+            <div class="my-2 p-3 border border-gray-300 rounded-lg bg-gray-50 space-y-2">
+                <div class="flex items-center gap-2">
+                    <span class="font-semibold">"不可达节点"</span>
+                    <span class="text-gray-500 text-sm">
+                        {move || format!("共{}个，不参与计算和数据模板", nodes.with(Vec::len))}
+                    </span>
+                    <button
+                        class="ml-auto text-gray-500 hover:text-gray-700"
+                        on:click=move |_| open.set(false)
+                    >
+                        "关闭"
+                    </button>
+                </div>
+                <div class="text-gray-500 text-sm">
+                    "已删除父节点的节点，其原父节点只能从本次打开文件后的撤销历史中查到，重新打开文件后显示为“未知”"
+                </div>
+                <div class="max-h-60 overflow-y-auto border rounded p-1 bg-white">
+                    {move || {
+                        nodes
+                            .get()
+                            .into_iter()
+                            .map(|node| {
+                                let id = node.id;
+                                let name = node.name.clone();
+                                let former_parents = if node.former_parents.is_empty() {
+                                    "未知".to_string()
+                                } else {
+                                    node.former_parents.join("、")
+                                };
+                                view! {
+                                    <div class="flex items-center gap-2">
+                                        <input
+                                            type="checkbox"
+                                            prop:checked=move || {
+                                                checked.with(|checked| checked.contains(&id))
+                                            }
+                                            on:change=move |ev| {
+                                                checked
+                                                    .update(|checked| {
+                                                        if event_target_checked(&ev) {
+                                                            checked.insert(id);
+                                                        } else {
+                                                            checked.remove(&id);
+                                                        }
+                                                    });
+                                            }
+                                        />
+                                        <span>{node.name}</span>
+                                        <span class="text-gray-500 text-sm">
+                                            {if node.is_leaf { "叶节点" } else { "非叶节点" }}
+                                            "，原父节点："
+                                            {former_parents}
+                                        </span>
+                                        <button
+                                            class="ml-auto text-blue-500 hover:text-blue-700"
+                                            on:click=move |_| {
+                                                target_query.set(String::new());
+                                                attaching.set(Some((id, name.clone())));
+                                            }
+                                        >
+                                            "挂接到…"
+                                        </button>
+                                    </div>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </div>
+                <div
+                    class="p-2 border rounded bg-white"
+                    class:hidden=move || attaching.with(|attaching| attaching.is_none())
+                >
+                    <div class="flex items-center gap-2">
+                        <span>
+                            {move || {
+                                attaching
+                                    .get()
+                                    .map(|(_, name)| format!("将\"{}\"挂接到：", name))
+                            }}
+                        </span>
+                        <input
+                            type="text"
+                            placeholder="搜索非叶节点"
+                            class="flex-1 px-2 py-1 border rounded"
+                            prop:value=target_query
+                            on:input=move |ev| target_query.set(event_target_value(&ev))
+                        />
+                        <button
+                            class="text-gray-500 hover:text-gray-700"
+                            on:click=move |_| attaching.set(None)
+                        >
+                            "取消"
+                        </button>
+                    </div>
+                    <div class="max-h-40 overflow-y-auto mt-1">
+                        {
+                            let on_attach = on_attach.clone();
+                            move || {
+                                let on_attach = on_attach.clone();
+                                targets
+                                    .get()
+                                    .into_iter()
+                                    .map(|target| {
+                                        let on_attach = on_attach.clone();
+                                        let parent = target.id;
+                                        view! {
+                                            <div
+                                                class="px-1 cursor-pointer hover:bg-gray-100 rounded"
+                                                on:click=move |_| on_attach(parent)
+                                            >
+                                                {target.name}
+                                            </div>
+                                        }
+                                    })
+                                    .collect_view()
+                            }
+                        }
+                    </div>
+                </div>
+                <button
+                    class="px-4 py-1 bg-red-600 text-white rounded-lg hover:bg-red-700 disabled:opacity-50"
+                    prop:disabled=move || checked.with(|checked| checked.is_empty())
+                    on:click=on_remove.clone()
+                >
+                    "永久删除选中的节点"
+                </button>
+            </div>
+        </Show>
+    }
+}
@@ -4,8 +4,12 @@ use leptos::leptos_dom::logging::console_log;
 use leptos::prelude::{ArcRwSignal, Get, GetUntracked, Set};
 use leptos::task::spawn_local;
 use serde_wasm_bindgen::{from_value, to_value};
-use shared::{Algorithm, ExpandInfo, IdArgs, Model, MyResult};
+use shared::{
+    Algorithm, CopySettings, ExpandInfo, HistoryState, IdArgs, Model, MyResult, NodeMetadata,
+    RemoveNodesResponse,
+};
 use std::{collections::HashMap, future::Future};
+use wasm_bindgen::JsValue;
 
 #[derive(Clone, Debug, Default)]
 pub struct ExpandSignal {
@@ -20,11 +24,33 @@ pub struct TreeNodeModel {
     pub ref_count: ArcRwSignal<u64>,
     pub expand_signal: ArcRwSignal<Option<ExpandSignal>>,
     pub value: ArcRwSignal<Option<f64>>,
+    pub metadata: ArcRwSignal<NodeMetadata>,
 }
 
+// 当前在节点检查器中显示的节点
+#[derive(Clone, Debug, Default)]
+pub struct SelectedNode(pub ArcRwSignal<Option<u64>>);
+
+// 正在拖动的节点及其所在的父节点
+#[derive(Clone, Debug, Default)]
+pub struct DraggedNode(pub ArcRwSignal<Option<(u64, u64)>>);
+
+// 复制的节点（id和名称）以及粘贴副本时的设置
+#[derive(Clone, Debug, Default)]
+pub struct Clipboard {
+    pub node: ArcRwSignal<Option<(u64, String)>>,
+    pub settings: ArcRwSignal<CopySettings>,
+}
+
+// 与LeptosContext中的history相同，不需要锁定LeptosContext就可以读取
+#[derive(Clone, Debug, Default)]
+pub struct UndoHistory(pub ArcRwSignal<HistoryState>);
+
 pub struct LeptosContext {
     pub models: HashMap<u64, TreeNodeModel>,
     pub err_msg: ArcRwSignal<String>,
+    // 可以撤销和重做的操作，每次修改模型后刷新
+    pub history: ArcRwSignal<HistoryState>,
 }
 
 impl LeptosContext {
@@ -40,6 +66,7 @@ impl LeptosContext {
                 ref_count: ArcRwSignal::new(0),
                 expand_signal: ArcRwSignal::new(None),
                 value: ArcRwSignal::new(None),
+                metadata: ArcRwSignal::new(NodeMetadata::default()),
             };
             self.models.insert(id, tree_node_model);
             self.update_model(id).await;
@@ -59,29 +86,7 @@ impl LeptosContext {
         let result = from_value::<MyResult<Model, String>>(result).unwrap();
         match result {
             MyResult::Ok(new_model) => {
-                model.name.set(new_model.name);
-                model.ref_count.set(new_model.ref_count);
-                // when the expand signal goes from none to some, create the signals
-                // when the expand signal goes from some to none, delete the signals
-                // when the expand signal goes from some to some, update the signals
-                match (model.expand_signal.get_untracked(), new_model.expand_info) {
-                    (Some(expand_signal), Some(new_expand_signal)) => {
-                        expand_signal.algorithm.set(new_expand_signal.algorithm);
-                        expand_signal.children.set(new_expand_signal.children);
-                    }
-                    (Some(_expand_signal), None) => {
-                        model.expand_signal.set(None);
-                    }
-                    (None, Some(expand_signal)) => {
-                        let new_expand_signal = ExpandSignal {
-                            algorithm: ArcRwSignal::new(expand_signal.algorithm),
-                            children: ArcRwSignal::new(expand_signal.children),
-                        };
-                        model.expand_signal.set(Some(new_expand_signal));
-                    }
-                    _ => {}
-                }
-                model.value.set(new_model.value);
+                Self::set_model(model, new_model);
             }
             MyResult::Err(e) => {
                 // handle error
@@ -89,6 +94,71 @@ impl LeptosContext {
             }
         }
     }
+    fn set_model(model: &TreeNodeModel, new_model: Model) {
+        model.name.set(new_model.name);
+        model.ref_count.set(new_model.ref_count);
+        // when the expand signal goes from none to some, create the signals
+        // when the expand signal goes from some to none, delete the signals
+        // when the expand signal goes from some to some, update the signals
+        match (model.expand_signal.get_untracked(), new_model.expand_info) {
+            (Some(expand_signal), Some(new_expand_signal)) => {
+                expand_signal.algorithm.set(new_expand_signal.algorithm);
+                expand_signal.children.set(new_expand_signal.children);
+            }
+            (Some(_expand_signal), None) => {
+                model.expand_signal.set(None);
+            }
+            (None, Some(expand_signal)) => {
+                let new_expand_signal = ExpandSignal {
+                    algorithm: ArcRwSignal::new(expand_signal.algorithm),
+                    children: ArcRwSignal::new(expand_signal.children),
+                };
+                model.expand_signal.set(Some(new_expand_signal));
+            }
+            _ => {}
+        }
+        model.value.set(new_model.value);
+        model.metadata.set(new_model.metadata);
+    }
+    // 撤销或重做后任何节点都可能变化，重新查询所有已加载的节点，后端已不存在的节点从缓存中删除
+    pub async fn refresh_all(&mut self) {
+        let ids = self.models.keys().copied().collect::<Vec<u64>>();
+        for id in ids {
+            let id_args = to_value(&IdArgs { id }).unwrap();
+            let result = invoke("query_node", id_args).await;
+            let result = from_value::<MyResult<Model, String>>(result).unwrap();
+            match result {
+                MyResult::Ok(new_model) => {
+                    if let Some(model) = self.models.get(&id) {
+                        Self::set_model(model, new_model);
+                    }
+                }
+                MyResult::Err(_) => {
+                    self.models.remove(&id);
+                }
+            }
+        }
+        self.refresh_history().await;
+    }
+    // 删除子树或清除不可达节点后，去掉已删除的节点并刷新受影响的节点
+    pub async fn apply_removal(&mut self, response: RemoveNodesResponse) {
+        for id in response.removed {
+            self.models.remove(&id);
+        }
+        for id in response.ids_to_update {
+            if self.models.contains_key(&id) {
+                self.update_model(id).await;
+            }
+        }
+        self.refresh_history().await;
+    }
+    pub async fn refresh_history(&self) {
+        let result = invoke("query_history", JsValue::NULL).await;
+        let result = from_value::<MyResult<HistoryState, String>>(result).unwrap();
+        if let MyResult::Ok(history) = result {
+            self.history.set(history);
+        }
+    }
     pub fn update_values(&mut self, values: &HashMap<u64, f64>) {
         for (id, value) in values {
             if let Some(model) = self.models.get_mut(id) {
@@ -1,3 +1,5 @@
+pub mod data;
 pub mod home;
 pub mod save;
+pub mod template;
 pub mod tree;
@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use crate::app::invoke;
+use crate::models::LeptosContext;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_router::hooks::use_navigate;
+use serde_wasm_bindgen::{from_value, to_value};
+use shared::{DataFileSettings, DataFileSettingsArgs, DataLayout, MyResult};
+use tokio::sync::Mutex;
+use wasm_bindgen::JsValue;
+
+#[component]
+pub fn Data() -> impl IntoView {
+    let leptos_context = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
+    let (layout, set_layout) = signal(String::from("key_value"));
+    let (name_column, set_name_column) = signal(String::new());
+    let (value_column, set_value_column) = signal(String::new());
+    let (subject_column, set_subject_column) = signal(String::new());
+    let (subject, set_subject) = signal(String::new());
+    let (sheet, set_sheet) = signal(String::new());
+
+    // 进入页面时读取当前的数据文件设置
+    spawn_local({
+        let leptos_context = leptos_context.clone();
+        async move {
+            let result = invoke("query_data_file_settings", JsValue::NULL).await;
+            let result = from_value::<MyResult<DataFileSettings, String>>(result).unwrap();
+            match result {
+                MyResult::Ok(settings) => {
+                    set_layout.set(
+                        match settings.layout {
+                            DataLayout::KeyValue => "key_value",
+                            DataLayout::Wide => "wide",
+                        }
+                        .to_string(),
+                    );
+                    set_name_column.set(settings.name_column);
+                    set_value_column.set(settings.value_column);
+                    set_subject_column.set(settings.subject_column);
+                    set_subject.set(settings.subject);
+                    set_sheet.set(settings.sheet);
+                }
+                MyResult::Err(e) => {
+                    leptos_context
+                        .lock()
+                        .await
+                        .err_msg
+                        .set(format!("错误信息：{}", e));
+                }
+            }
+        }
+    });
+
+    let on_update_settings = {
+        let leptos_context = leptos_context.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            spawn_local(async move {
+                let context = leptos_context.lock().await;
+                let layout = match layout.get_untracked().as_str() {
+                    "wide" => DataLayout::Wide,
+                    _ => DataLayout::KeyValue,
+                };
+                if layout == DataLayout::KeyValue
+                    && (name_column.get_untracked().trim().is_empty()
+                        || value_column.get_untracked().trim().is_empty())
+                {
+                    context.err_msg.set("名称列和数值列不能为空".to_string());
+                    return;
+                }
+                let args = DataFileSettingsArgs {
+                    settings: DataFileSettings {
+                        layout,
+                        name_column: name_column.get_untracked().trim().to_string(),
+                        value_column: value_column.get_untracked().trim().to_string(),
+                        subject_column: subject_column.get_untracked().trim().to_string(),
+                        subject: subject.get_untracked().trim().to_string(),
+                        sheet: sheet.get_untracked().trim().to_string(),
+                    },
+                };
+                let args = to_value(&args).unwrap();
+                let result = invoke("request_update_data_file_settings", args).await;
+                let result = from_value::<MyResult<(), String>>(result).unwrap();
+                match result {
+                    MyResult::Ok(_) => {
+                        context.err_msg.set("数据文件设置已更新".to_string());
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e.to_string());
+                    }
+                }
+            });
+        }
+    };
+
+    let navigate = use_navigate();
+    let on_back = move |_| {
+        navigate("/tree", Default::default());
+    };
+
+    view! {
+        <div class="flex flex-col items-center p-6 space-y-4 bg-gray-100 rounded-lg shadow-md">
+            <h1 class="text-xl font-bold">"数据文件设置"</h1>
+            <p class="text-gray-500 text-sm">
+                "JSON数据文件为名称到数值的对象；CSV和Excel（xlsx/xls/ods）数据文件按以下设置读取。"
+            </p>
+            <div class="flex items-center space-x-2">
+                <div class="inline-block">"数据排列方式："</div>
+                <select
+                    class="px-2 py-2 border rounded-lg"
+                    on:change=move |ev| set_layout.set(event_target_value(&ev))
+                    prop:value=layout
+                >
+                    <option value="key_value">"每行一个指标（名称列+数值列）"</option>
+                    <option value="wide">"每行一个对象（表头为指标名称）"</option>
+                </select>
+            </div>
+            <Show when=move || layout.get() == "key_value">
+                <div class="flex items-center space-x-2">
+                    <div class="inline-block">"名称列："</div>
+                    <input
+                        type="text"
+                        bind:value=(name_column, set_name_column)
+                        class="px-3 py-2 border rounded-lg shadow-sm focus:ring focus:ring-blue-300"
+                    />
+                    <div class="inline-block">"数值列："</div>
+                    <input
+                        type="text"
+                        bind:value=(value_column, set_value_column)
+                        class="px-3 py-2 border rounded-lg shadow-sm focus:ring focus:ring-blue-300"
+                    />
+                </div>
+            </Show>
+            <Show when=move || layout.get() == "wide">
+                <div class="flex items-center space-x-2">
+                    <div class="inline-block">"对象列："</div>
+                    <input
+                        type="text"
+                        bind:value=(subject_column, set_subject_column)
+                        class="px-3 py-2 border rounded-lg shadow-sm focus:ring focus:ring-blue-300"
+                    />
+                    <div class="inline-block">"对象："</div>
+                    <input
+                        type="text"
+                        bind:value=(subject, set_subject)
+                        class="px-3 py-2 border rounded-lg shadow-sm focus:ring focus:ring-blue-300"
+                    />
+                </div>
+                <p class="text-gray-500 text-sm">"对象列或对象为空时使用第一行数据。"</p>
+            </Show>
+            <p class="text-gray-500 text-sm">
+                "列可以用表头文字、列字母（如B）或从1开始的列号指定。"
+            </p>
+            <div class="flex items-center space-x-2">
+                <div class="inline-block">"Excel工作表（为空时使用第一个）："</div>
+                <input
+                    type="text"
+                    bind:value=(sheet, set_sheet)
+                    class="px-3 py-2 border rounded-lg shadow-sm focus:ring focus:ring-blue-300"
+                />
+            </div>
+            <div class="flex space-x-2">
+                <button
+                    class="px-4 py-2 text-white bg-blue-500 rounded-lg hover:bg-blue-600 transition"
+                    on:click=on_update_settings
+                >
+                    "应用"
+                </button>
+                <button
+                    class="px-4 py-2 text-white bg-blue-500 rounded-lg hover:bg-blue-600 transition"
+                    on:click=on_back
+                >
+                    "返回"
+                </button>
+            </div>
+        </div>
+    }
+}
@@ -17,7 +17,10 @@ pub fn Home() -> impl IntoView {
     // let (root_name, set_root_name) = signal(String::new());
     let leptos_context1 = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
     let leptos_context2 = leptos_context1.clone();
+    let leptos_context3 = leptos_context1.clone();
+    let leptos_context4 = leptos_context1.clone();
     let navigate = use_navigate();
+    let navigate_import = navigate.clone();
     // Function to open the file dialog and get the selected file path
     let open_file_dialog = move |_| {
         let leptos_context = leptos_context1.clone();
@@ -71,6 +74,49 @@ pub fn Home() -> impl IntoView {
         });
     };
 
+    // 在JSON、YAML和TOML格式之间转换模型文件，格式由扩展名决定
+    let convert = move |_| {
+        let leptos_context = leptos_context3.clone();
+        spawn_local(async move {
+            let result = invoke("request_convert_model", JsValue::NULL).await;
+            let result = from_value::<MyResult<String, String>>(result).unwrap();
+            let context = leptos_context.lock().await;
+            match result {
+                MyResult::Ok(path) => {
+                    context.err_msg.set(format!("已转换为：{}", path));
+                }
+                MyResult::Err(e) => {
+                    context.err_msg.set(format!("错误信息：{}", e));
+                }
+            }
+        });
+    };
+
+    // 从其他格式导入模型，导入的模型另存为模型文件后直接打开
+    let import = move |command: &'static str| {
+        let leptos_context = leptos_context4.clone();
+        let navigate = navigate_import.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            let navigate = navigate.clone();
+            spawn_local(async move {
+                let result = invoke(command, JsValue::NULL).await;
+                let result = from_value::<MyResult<String, String>>(result).unwrap();
+                let context = leptos_context.lock().await;
+                match result {
+                    MyResult::Ok(path) => {
+                        set_file_path.set(path);
+                        context.err_msg.set("".to_string());
+                        navigate("/tree", Default::default());
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(format!("错误信息：{}", e));
+                    }
+                }
+            });
+        }
+    };
+
     view! {
         <div class="flex flex-col items-center p-6 space-y-4 bg-gray-100 rounded-lg shadow-md">
             <button
@@ -99,6 +145,41 @@ pub fn Home() -> impl IntoView {
             >
                 "提交"
             </button>
+
+            <button
+                class="px-4 py-2 text-white bg-blue-500 rounded-lg hover:bg-blue-600 transition"
+                on:click=convert
+            >
+                "模型文件格式转换（JSON/YAML/TOML）"
+            </button>
+
+            <div class="flex flex-wrap justify-center gap-2">
+                <span class="self-center text-gray-700">"导入模型："</span>
+                <button
+                    class="px-4 py-2 text-white bg-blue-500 rounded-lg hover:bg-blue-600 transition"
+                    on:click=import("request_import_nested_json")
+                >
+                    "嵌套结构JSON"
+                </button>
+                <button
+                    class="px-4 py-2 text-white bg-blue-500 rounded-lg hover:bg-blue-600 transition"
+                    on:click=import("request_import_outline")
+                >
+                    "大纲（Markdown/缩进文本/OPML）"
+                </button>
+                <button
+                    class="px-4 py-2 text-white bg-blue-500 rounded-lg hover:bg-blue-600 transition"
+                    on:click=import("request_import_mind_map")
+                >
+                    "思维导图（XMind/FreeMind）"
+                </button>
+                <button
+                    class="px-4 py-2 text-white bg-blue-500 rounded-lg hover:bg-blue-600 transition"
+                    on:click=import("request_import_levels")
+                >
+                    "层级指标表（CSV）"
+                </button>
+            </div>
         </div>
     }
 }
@@ -1,11 +1,167 @@
+use std::sync::Arc;
+
+use crate::app::invoke;
+use crate::models::LeptosContext;
 use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_router::hooks::use_navigate;
+use serde_wasm_bindgen::{from_value, to_value};
+use shared::{MyResult, SaveSettings, SaveSettingsArgs};
+use tokio::sync::Mutex;
+use wasm_bindgen::JsValue;
 
 #[component]
 pub fn Save() -> impl IntoView {
+    let leptos_context = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
+    let (file_path, set_file_path) = signal(String::new());
+    let (backup_count, set_backup_count) = signal(String::new());
+    let (autosave_minutes, set_autosave_minutes) = signal(String::new());
+
+    // 进入页面时读取当前的文件路径和保存设置
+    spawn_local({
+        let leptos_context = leptos_context.clone();
+        async move {
+            let result = invoke("query_file_path", JsValue::NULL).await;
+            let result = from_value::<MyResult<String, String>>(result).unwrap();
+            match result {
+                MyResult::Ok(path) => set_file_path.set(path),
+                MyResult::Err(e) => {
+                    leptos_context
+                        .lock()
+                        .await
+                        .err_msg
+                        .set(format!("错误信息：{}", e));
+                }
+            }
+            let result = invoke("query_save_settings", JsValue::NULL).await;
+            let result = from_value::<MyResult<SaveSettings, String>>(result).unwrap();
+            if let MyResult::Ok(settings) = result {
+                set_backup_count.set(settings.backup_count.to_string());
+                set_autosave_minutes.set(settings.autosave_minutes.to_string());
+            }
+        }
+    });
+
+    let on_update_settings = {
+        let leptos_context = leptos_context.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            spawn_local(async move {
+                let context = leptos_context.lock().await;
+                let backup_count = match backup_count.get_untracked().trim().parse::<u64>() {
+                    Ok(backup_count) => backup_count,
+                    Err(_) => {
+                        context.err_msg.set("备份数量必须为非负整数".to_string());
+                        return;
+                    }
+                };
+                let autosave_minutes = match autosave_minutes.get_untracked().trim().parse::<u64>()
+                {
+                    Ok(autosave_minutes) => autosave_minutes,
+                    Err(_) => {
+                        context
+                            .err_msg
+                            .set("自动保存间隔必须为非负整数".to_string());
+                        return;
+                    }
+                };
+                let args = SaveSettingsArgs {
+                    settings: SaveSettings {
+                        backup_count,
+                        autosave_minutes,
+                    },
+                };
+                let args = to_value(&args).unwrap();
+                let result = invoke("request_update_save_settings", args).await;
+                let result = from_value::<MyResult<(), String>>(result).unwrap();
+                match result {
+                    MyResult::Ok(_) => {
+                        context.err_msg.set("保存设置已更新".to_string());
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e.to_string());
+                    }
+                }
+            });
+        }
+    };
+
+    let on_save_as = {
+        let leptos_context = leptos_context.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            spawn_local(async move {
+                let context = leptos_context.lock().await;
+                let result = invoke("request_save_as", JsValue::NULL).await;
+                let result = from_value::<MyResult<String, String>>(result).unwrap();
+                match result {
+                    MyResult::Ok(path) => {
+                        set_file_path.set(path);
+                        context.err_msg.set("另存为成功".to_string());
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e.to_string());
+                    }
+                }
+            });
+        }
+    };
+
+    let navigate = use_navigate();
+    let on_back = move |_| {
+        navigate("/tree", Default::default());
+    };
+
     view! {
-        <div>
-            <h1>"Save"</h1>
-            <p>"Welcome to the save page!"</p>
+        <div class="flex flex-col items-center p-6 space-y-4 bg-gray-100 rounded-lg shadow-md">
+            <h1 class="text-xl font-bold">"保存设置"</h1>
+            <p class="text-gray-700 font-medium">
+                "当前文件：" <span class="text-blue-600">{file_path}</span>
+            </p>
+            <div class="flex items-center space-x-2">
+                <div class="inline-block">"保留备份数量："</div>
+                <input
+                    type="number"
+                    min="0"
+                    bind:value=(backup_count, set_backup_count)
+                    class="px-3 py-2 border rounded-lg shadow-sm focus:ring focus:ring-blue-300"
+                />
+            </div>
+            <div class="flex items-center space-x-2">
+                <div class="inline-block">"自动保存间隔（分钟，0为关闭）："</div>
+                <input
+                    type="number"
+                    min="0"
+                    bind:value=(autosave_minutes, set_autosave_minutes)
+                    class="px-3 py-2 border rounded-lg shadow-sm focus:ring focus:ring-blue-300"
+                />
+                <button
+                    class="px-4 py-2 text-white bg-blue-500 rounded-lg hover:bg-blue-600 transition"
+                    on:click=on_update_settings
+                >
+                    "应用"
+                </button>
+            </div>
+            <p class="text-gray-500 text-sm">
+                "每次保存前，原文件会被复制为同目录下的“文件名.bak1”，旧的备份依次后移。"
+            </p>
+            <p class="text-gray-500 text-sm">
+                "未保存的修改会实时记录在同目录下的“文件名.journal”中，程序异常退出后再次打开该文件时可以恢复。"
+            </p>
+            <div class="flex space-x-2">
+                <button
+                    class="px-4 py-2 text-white bg-green-500 rounded-lg hover:bg-green-600 transition"
+                    on:click=on_save_as
+                >
+                    "另存为"
+                </button>
+                <button
+                    class="px-4 py-2 text-white bg-blue-500 rounded-lg hover:bg-blue-600 transition"
+                    on:click=on_back
+                >
+                    "返回"
+                </button>
+            </div>
         </div>
     }
 }
@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use crate::app::invoke;
+use crate::models::{LeptosContext, SelectedNode};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_router::hooks::use_navigate;
+use serde_wasm_bindgen::{from_value, to_value};
+use shared::{MyResult, TemplateFill, TemplateFormat, TemplateSettings, TemplateSettingsArgs};
+use tokio::sync::Mutex;
+
+#[component]
+pub fn Template() -> impl IntoView {
+    let leptos_context = use_context::<Arc<Mutex<LeptosContext>>>().unwrap();
+    let SelectedNode(selected) = use_context::<SelectedNode>().unwrap();
+    let (format, set_format) = signal(String::from("json"));
+    let (fill, set_fill) = signal(String::from("zero"));
+    let (subtree, set_subtree) = signal(false);
+    let (include_path, set_include_path) = signal(true);
+    let (include_unit, set_include_unit) = signal(true);
+    let (include_expected_range, set_include_expected_range) = signal(true);
+    let (include_description, set_include_description) = signal(false);
+
+    let on_generate = {
+        let leptos_context = leptos_context.clone();
+        let selected = selected.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            let selected = selected.get_untracked();
+            spawn_local(async move {
+                let context = leptos_context.lock().await;
+                let root = if subtree.get_untracked() {
+                    match selected {
+                        Some(id) => Some(id),
+                        None => {
+                            context.err_msg.set(
+                                "请先在模型页面点击节点的信息按钮选中子树的根节点".to_string(),
+                            );
+                            return;
+                        }
+                    }
+                } else {
+                    None
+                };
+                let args = TemplateSettingsArgs {
+                    settings: TemplateSettings {
+                        format: match format.get_untracked().as_str() {
+                            "csv" => TemplateFormat::Csv,
+                            "xlsx" => TemplateFormat::Xlsx,
+                            _ => TemplateFormat::Json,
+                        },
+                        root,
+                        fill: match fill.get_untracked().as_str() {
+                            "empty" => TemplateFill::Empty,
+                            "random" => TemplateFill::Random,
+                            "dataset" => TemplateFill::Dataset,
+                            _ => TemplateFill::Zero,
+                        },
+                        include_path: include_path.get_untracked(),
+                        include_unit: include_unit.get_untracked(),
+                        include_expected_range: include_expected_range.get_untracked(),
+                        include_description: include_description.get_untracked(),
+                    },
+                };
+                let args = to_value(&args).unwrap();
+                let result = invoke("request_template_generation", args).await;
+                let result = from_value::<MyResult<String, String>>(result).unwrap();
+                match result {
+                    MyResult::Ok(message) => {
+                        context.err_msg.set(message);
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e.to_string());
+                    }
+                }
+            });
+        }
+    };
+
+    let navigate = use_navigate();
+    let on_back = move |_| {
+        navigate("/tree", Default::default());
+    };
+
+    view! {
+        <div class="flex flex-col items-center p-6 space-y-4 bg-gray-100 rounded-lg shadow-md">
+            <h1 class="text-xl font-bold">"生成数据模板"</h1>
+            <div class="flex items-center space-x-2">
+                <div class="inline-block">"格式："</div>
+                <select
+                    class="px-2 py-2 border rounded-lg"
+                    on:change=move |ev| set_format.set(event_target_value(&ev))
+                    prop:value=format
+                >
+                    <option value="json">"JSON"</option>
+                    <option value="csv">"CSV"</option>
+                    <option value="xlsx">"Excel（xlsx）"</option>
+                </select>
+                <div class="inline-block">"数值："</div>
+                <select
+                    class="px-2 py-2 border rounded-lg"
+                    on:change=move |ev| set_fill.set(event_target_value(&ev))
+                    prop:value=fill
+                >
+                    <option value="zero">"全部为0"</option>
+                    <option value="empty">"留空"</option>
+                    <option value="dataset">"从已有数据文件预填"</option>
+                    <option value="random">"随机数（调试）"</option>
+                </select>
+            </div>
+            <label>
+                <input
+                    type="checkbox"
+                    class="mr-1"
+                    prop:checked=subtree
+                    on:change=move |ev| set_subtree.set(event_target_checked(&ev))
+                />
+                "仅包含选中节点的子树"
+            </label>
+            <Show when=move || format.get() != "json">
+                <div class="flex items-center space-x-4">
+                    <div class="inline-block">"附加列："</div>
+                    <label>
+                        <input
+                            type="checkbox"
+                            class="mr-1"
+                            prop:checked=include_path
+                            on:change=move |ev| set_include_path.set(event_target_checked(&ev))
+                        />
+                        "节点路径"
+                    </label>
+                    <label>
+                        <input
+                            type="checkbox"
+                            class="mr-1"
+                            prop:checked=include_unit
+                            on:change=move |ev| set_include_unit.set(event_target_checked(&ev))
+                        />
+                        "单位"
+                    </label>
+                    <label>
+                        <input
+                            type="checkbox"
+                            class="mr-1"
+                            prop:checked=include_expected_range
+                            on:change=move |ev| {
+                                set_include_expected_range.set(event_target_checked(&ev))
+                            }
+                        />
+                        "预期范围"
+                    </label>
+                    <label>
+                        <input
+                            type="checkbox"
+                            class="mr-1"
+                            prop:checked=include_description
+                            on:change=move |ev| {
+                                set_include_description.set(event_target_checked(&ev))
+                            }
+                        />
+                        "说明"
+                    </label>
+                </div>
+                <p class="text-gray-500 text-sm">
+                    "CSV和Excel模板按数据文件设置中的列排列，附加列只在“每行一个指标”时加入，读取时会被忽略。"
+                </p>
+            </Show>
+            <p class="text-gray-500 text-sm">
+                "指标按模型中的顺序排列；JSON模板中空白的数值写为0。"
+            </p>
+            <div class="flex space-x-2">
+                <button
+                    class="px-4 py-2 text-white bg-green-500 rounded-lg hover:bg-green-600 transition"
+                    on:click=on_generate
+                >
+                    "生成"
+                </button>
+                <button
+                    class="px-4 py-2 text-white bg-blue-500 rounded-lg hover:bg-blue-600 transition"
+                    on:click=on_back
+                >
+                    "返回"
+                </button>
+            </div>
+        </div>
+    }
+}
@@ -1,19 +1,29 @@
 use crate::app::invoke;
+use crate::components::clipboard::ClipboardView;
+use crate::components::data_report::DataReportView;
+use crate::components::find_replace_panel::FindReplacePanel;
+use crate::components::model_header::ModelHeaderView;
+use crate::components::node_inspector::NodeInspector;
 use crate::components::tree_node::TreeNode;
-use crate::models::{LeptosContext, TreeNodeModel};
+use crate::components::unreachable_panel::UnreachablePanel;
+use crate::models::{LeptosContext, SelectedNode, TreeNodeModel, UndoHistory};
 use leptos::html::Q;
 use leptos::leptos_dom::logging::console_log;
 use leptos::prelude::*;
 use leptos::task::spawn_local;
+use leptos::{ev, web_sys};
 use leptos_router::hooks::use_navigate;
 use leptos_router::NavigateOptions;
 use send_wrapper::SendWrapper;
 use serde_wasm_bindgen::{from_value, to_value};
-use shared::{Algorithm, ExpandInfo, Model, MyResult, QueryValuesArgs, QueryValuesResponse};
+use shared::{
+    Algorithm, DataValidationReport, ExpandInfo, ExportGraphArgs, Model, ModelHeader, MyResult,
+    QueryValuesArgs, QueryValuesResponse, RemoveNodesResponse,
+};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use tokio::sync::Mutex;
-use wasm_bindgen::JsValue;
+use wasm_bindgen::{JsCast, JsValue};
 
 #[component]
 pub fn Tree() -> impl IntoView {
@@ -45,6 +55,121 @@ pub fn Tree() -> impl IntoView {
             .as_deref()
             .map_or_else(|| "加载中".to_string(), |s| s.clone())
     };
+    let header_data = LocalResource::new({
+        let leptos_context = leptos_context.clone();
+        move || {
+            let leptos_context = leptos_context.clone();
+            async move {
+                let result = invoke("query_model_header", JsValue::NULL).await;
+                let result = from_value::<MyResult<ModelHeader, String>>(result).unwrap();
+                match result {
+                    MyResult::Ok(header) => Some(header),
+                    MyResult::Err(e) => {
+                        leptos_context
+                            .lock()
+                            .await
+                            .err_msg
+                            .set(format!("错误信息：{}", e));
+                        None
+                    }
+                }
+            }
+        }
+    });
+    // 撤销或重做后刷新所有已加载的节点和模型信息
+    let undo_redo = {
+        let leptos_context = leptos_context.clone();
+        move |command: &'static str| {
+            let leptos_context = leptos_context.clone();
+            spawn_local(async move {
+                let mut context = leptos_context.lock().await;
+                let result = invoke(command, JsValue::NULL).await;
+                let result = from_value::<MyResult<String, String>>(result).unwrap();
+                match result {
+                    MyResult::Ok(label) => {
+                        context.refresh_all().await;
+                        header_data.refetch();
+                        let action = if command == "request_undo" {
+                            "已撤销"
+                        } else {
+                            "已重做"
+                        };
+                        context.err_msg.set(format!("{}：{}", action, label));
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+    let UndoHistory(history) = use_context::<UndoHistory>().unwrap();
+    spawn_local({
+        let leptos_context = leptos_context.clone();
+        async move {
+            leptos_context.lock().await.refresh_history().await;
+        }
+    });
+    // Ctrl+Z撤销，Ctrl+Y或Ctrl+Shift+Z重做；焦点在输入框中时保留输入框自带的撤销
+    let keydown_handle = window_event_listener(ev::keydown, {
+        let undo_redo = undo_redo.clone();
+        move |ev| {
+            if !(ev.ctrl_key() || ev.meta_key()) {
+                return;
+            }
+            let in_input = ev
+                .target()
+                .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+                .is_some_and(|element| {
+                    matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+                });
+            if in_input {
+                return;
+            }
+            let command = match ev.key().to_lowercase().as_str() {
+                "z" if ev.shift_key() => "request_redo",
+                "z" => "request_undo",
+                "y" => "request_redo",
+                _ => return,
+            };
+            ev.prevent_default();
+            undo_redo(command);
+        }
+    });
+    on_cleanup(move || keydown_handle.remove());
+    let on_undo = {
+        let undo_redo = undo_redo.clone();
+        move |_| undo_redo("request_undo")
+    };
+    let on_redo = move |_| undo_redo("request_redo");
+    let undo_title = {
+        let history = history.clone();
+        move || {
+            history
+                .get()
+                .undo
+                .map_or("没有可以撤销的修改".to_string(), |label| {
+                    format!("撤销：{}（Ctrl+Z）", label)
+                })
+        }
+    };
+    let redo_title = {
+        let history = history.clone();
+        move || {
+            history
+                .get()
+                .redo
+                .map_or("没有可以重做的修改".to_string(), |label| {
+                    format!("重做：{}（Ctrl+Y）", label)
+                })
+        }
+    };
+    let cannot_undo = {
+        let history = history.clone();
+        move || history.get().undo.is_none()
+    };
+    let cannot_redo = move || history.get().redo.is_none();
+
     let navigate = use_navigate();
     let on_save = {
         let leptos_context = leptos_context.clone();
@@ -56,6 +181,7 @@ pub fn Tree() -> impl IntoView {
                 let result = from_value::<MyResult<(), String>>(result).unwrap();
                 match result {
                     MyResult::Ok(_) => {
+                        header_data.refetch();
                         context.err_msg.set("保存成功".to_string());
                     }
                     MyResult::Err(e) => {
@@ -65,11 +191,76 @@ pub fn Tree() -> impl IntoView {
             });
         }
     };
+    let on_save_as = {
+        let leptos_context = leptos_context.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            spawn_local(async move {
+                let context = leptos_context.lock().await;
+                let result = invoke("request_save_as", JsValue::NULL).await;
+                let result = from_value::<MyResult<String, String>>(result).unwrap();
+                match result {
+                    MyResult::Ok(_) => {
+                        curr_file_path_data.refetch();
+                        header_data.refetch();
+                        context.err_msg.set("另存为成功".to_string());
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e.to_string());
+                    }
+                }
+            });
+        }
+    };
+    let on_save_settings = {
+        let navigate = navigate.clone();
+        move |_| {
+            navigate("/save", Default::default());
+        }
+    };
+    let find_replace_open = RwSignal::new(false);
+    let unreachable_open = RwSignal::new(false);
+    let on_purge_unreachable = {
+        let leptos_context = leptos_context.clone();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            spawn_local(async move {
+                let mut context = leptos_context.lock().await;
+                let response = invoke("request_purge_unreachable", JsValue::NULL).await;
+                let response =
+                    from_value::<MyResult<RemoveNodesResponse, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(response) => {
+                        let count = response.removed.len();
+                        context.apply_removal(response).await;
+                        context.err_msg.set(format!("已清除{}个不可达节点", count));
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e);
+                    }
+                }
+            });
+        }
+    };
+    let on_data_settings = {
+        let navigate = navigate.clone();
+        move |_| {
+            navigate("/data", Default::default());
+        }
+    };
+    let on_template_generation = {
+        let navigate = navigate.clone();
+        move |_| {
+            navigate("/template", Default::default());
+        }
+    };
     let on_back = {
         let leptos_context = leptos_context.clone();
+        let SelectedNode(selected) = use_context::<SelectedNode>().unwrap();
         move |_| {
             let leptos_context = leptos_context.clone();
             let navigate = navigate.clone();
+            selected.set(None);
             spawn_local(async move {
                 let mut context = leptos_context.lock().await;
                 context.models.clear();
@@ -79,20 +270,24 @@ pub fn Tree() -> impl IntoView {
         }
     };
 
-    let on_template_generation = {
+    // 检查数据文件，结果显示在工具栏下方
+    let data_report = RwSignal::new(None::<DataValidationReport>);
+    let on_validate_data = {
         let leptos_context = leptos_context.clone();
         move |_| {
             let leptos_context = leptos_context.clone();
             spawn_local(async move {
+                let response = invoke("request_validate_data", JsValue::NULL).await;
+                let response =
+                    from_value::<MyResult<DataValidationReport, String>>(response).unwrap();
                 let context = leptos_context.lock().await;
-                let response = invoke("request_template_generation", JsValue::NULL).await;
-                let response = from_value::<MyResult<(), String>>(response).unwrap();
                 match response {
-                    MyResult::Ok(_) => {
-                        context.err_msg.set("模板生成成功".to_string());
+                    MyResult::Ok(report) => {
+                        context.err_msg.set("".to_string());
+                        data_report.set(Some(report));
                     }
                     MyResult::Err(e) => {
-                        context.err_msg.set(format!("{}", e));
+                        context.err_msg.set(e.to_string());
                     }
                 }
             });
@@ -145,6 +340,67 @@ pub fn Tree() -> impl IntoView {
         }
     };
 
+    // 导出格式对应的后端命令
+    let (export_command, set_export_command) = signal("request_export_nested_json".to_string());
+    // 导出图形时可以只导出选中节点的子树，并限制深度
+    let (graph_subtree, set_graph_subtree) = signal(false);
+    let (graph_depth, set_graph_depth) = signal(String::new());
+    let on_export = {
+        let leptos_context = leptos_context.clone();
+        let SelectedNode(selected) = use_context::<SelectedNode>().unwrap();
+        move |_| {
+            let leptos_context = leptos_context.clone();
+            let command = export_command.get_untracked();
+            let selected = selected.get_untracked();
+            spawn_local(async move {
+                let context = leptos_context.lock().await;
+                let args = if command == "request_export_graph" {
+                    let root = if graph_subtree.get_untracked() {
+                        match selected {
+                            Some(id) => Some(id),
+                            None => {
+                                context
+                                    .err_msg
+                                    .set("请先点击节点的信息按钮选中子树的根节点".to_string());
+                                return;
+                            }
+                        }
+                    } else {
+                        None
+                    };
+                    let depth = graph_depth.get_untracked();
+                    let max_depth = match depth.trim() {
+                        "" => None,
+                        depth => match depth.parse::<u64>() {
+                            Ok(depth) => Some(depth),
+                            Err(_) => {
+                                context.err_msg.set("深度必须为非负整数".to_string());
+                                return;
+                            }
+                        },
+                    };
+                    to_value(&ExportGraphArgs {
+                        root,
+                        maxDepth: max_depth,
+                    })
+                    .unwrap()
+                } else {
+                    JsValue::NULL
+                };
+                let response = invoke(&command, args).await;
+                let response = from_value::<MyResult<String, String>>(response).unwrap();
+                match response {
+                    MyResult::Ok(path) => {
+                        context.err_msg.set(format!("已导出到：{}", path));
+                    }
+                    MyResult::Err(e) => {
+                        context.err_msg.set(e.to_string());
+                    }
+                }
+            });
+        }
+    };
+
     let root_resource = LocalResource::new({
         let leptos_context = leptos_context.clone();
         move || {
@@ -165,6 +421,34 @@ pub fn Tree() -> impl IntoView {
                 >
                     "保存"
                 </button>
+                <button
+                    on:click=on_save_as
+                    class="mx-3 px-4 py-2 bg-blue-600 text-white font-semibold rounded-2xl shadow-md hover:bg-blue-700 transition-all duration-200 ease-in-out active:scale-95"
+                >
+                    "另存为"
+                </button>
+                <button
+                    on:click=on_undo
+                    prop:disabled=cannot_undo
+                    title=undo_title
+                    class="mx-3 px-4 py-2 bg-blue-600 text-white font-semibold rounded-2xl shadow-md hover:bg-blue-700 transition-all duration-200 ease-in-out active:scale-95 disabled:opacity-50"
+                >
+                    "撤销"
+                </button>
+                <button
+                    on:click=on_redo
+                    prop:disabled=cannot_redo
+                    title=redo_title
+                    class="mx-3 px-4 py-2 bg-blue-600 text-white font-semibold rounded-2xl shadow-md hover:bg-blue-700 transition-all duration-200 ease-in-out active:scale-95 disabled:opacity-50"
+                >
+                    "重做"
+                </button>
+                <button
+                    on:click=on_save_settings
+                    class="mx-3 px-4 py-2 bg-blue-600 text-white font-semibold rounded-2xl shadow-md hover:bg-blue-700 transition-all duration-200 ease-in-out active:scale-95"
+                >
+                    "保存设置"
+                </button>
                 <button
                     on:click=on_back
                     class="mx-3 px-4 py-2 bg-blue-600 text-white font-semibold rounded-2xl shadow-md hover:bg-blue-700 transition-all duration-200 ease-in-out active:scale-95"
@@ -183,19 +467,94 @@ pub fn Tree() -> impl IntoView {
                 >
                     "选取数据文件并计算"
                 </button>
+                <button
+                    on:click=on_validate_data
+                    class="mx-3 px-4 py-2 bg-blue-600 text-white font-semibold rounded-2xl shadow-md hover:bg-blue-700 transition-all duration-200 ease-in-out active:scale-95"
+                >
+                    "检查数据文件"
+                </button>
+                <button
+                    on:click=on_data_settings
+                    class="mx-3 px-4 py-2 bg-blue-600 text-white font-semibold rounded-2xl shadow-md hover:bg-blue-700 transition-all duration-200 ease-in-out active:scale-95"
+                >
+                    "数据文件设置"
+                </button>
+                <button
+                    on:click=move |_| find_replace_open.update(|open| *open = !*open)
+                    class="mx-3 px-4 py-2 bg-blue-600 text-white font-semibold rounded-2xl shadow-md hover:bg-blue-700 transition-all duration-200 ease-in-out active:scale-95"
+                >
+                    "查找替换"
+                </button>
+                <button
+                    on:click=move |_| unreachable_open.update(|open| *open = !*open)
+                    class="mx-3 px-4 py-2 bg-blue-600 text-white font-semibold rounded-2xl shadow-md hover:bg-blue-700 transition-all duration-200 ease-in-out active:scale-95"
+                >
+                    "不可达节点"
+                </button>
+                <button
+                    on:click=on_purge_unreachable
+                    class="mx-3 px-4 py-2 bg-blue-600 text-white font-semibold rounded-2xl shadow-md hover:bg-blue-700 transition-all duration-200 ease-in-out active:scale-95"
+                >
+                    "清除不可达节点"
+                </button>
+                <select
+                    class="mx-3 px-2 py-2 border rounded-lg"
+                    on:change=move |ev| set_export_command.set(event_target_value(&ev))
+                    prop:value=export_command
+                >
+                    <option value="request_export_nested_json">"嵌套结构JSON"</option>
+                    <option value="request_export_outline">"大纲（Markdown/缩进文本/OPML）"</option>
+                    <option value="request_export_levels">"层级指标表（CSV）"</option>
+                    <option value="request_export_graph">"图形（Graphviz DOT/Mermaid）"</option>
+                    <option value="request_export_xlsx">"带公式的Excel工作簿"</option>
+                </select>
+                <Show when=move || export_command.get() == "request_export_graph">
+                    <label class="mx-2">
+                        <input
+                            type="checkbox"
+                            class="mr-1"
+                            prop:checked=graph_subtree
+                            on:change=move |ev| set_graph_subtree.set(event_target_checked(&ev))
+                        />
+                        "仅选中节点的子树"
+                    </label>
+                    <input
+                        type="number"
+                        min="0"
+                        placeholder="深度（空为不限）"
+                        class="mx-2 px-2 py-1 w-36 border rounded-lg"
+                        prop:value=graph_depth
+                        on:input=move |ev| set_graph_depth.set(event_target_value(&ev))
+                    />
+                </Show>
+                <button
+                    on:click=on_export
+                    class="px-4 py-2 bg-blue-600 text-white font-semibold rounded-2xl shadow-md hover:bg-blue-700 transition-all duration-200 ease-in-out active:scale-95"
+                >
+                    "导出"
+                </button>
+            </div>
+            <DataReportView report=data_report />
+            <ClipboardView />
+            <FindReplacePanel open=find_replace_open />
+            <UnreachablePanel open=unreachable_open />
+            <ModelHeaderView header_data=header_data file_path=Signal::derive_local(curr_file_path) />
+            <div class="flex items-start gap-4">
+                <div class="flex-1">
+                    <Suspense>
+                        {move || {
+                            let model = root_resource.get();
+                            model.map(|model|{
+                                let model = model.into_taken();
+                                view! {
+                                    <TreeNode tree_node_model=model parent=None/>
+                                }
+                            })
+                        }}
+                    </Suspense>
+                </div>
+                <NodeInspector />
             </div>
-            <h1 class="text-xl font-bold mb-4">"文件："{curr_file_path}</h1>
-            <Suspense>
-                {move || {
-                    let model = root_resource.get();
-                    model.map(|model|{
-                        let model = model.into_taken();
-                        view! {
-                            <TreeNode tree_node_model=model parent=None/>
-                        }
-                    })
-                }}
-            </Suspense>
         </div>
     }
 }